@@ -0,0 +1,78 @@
+//! XMP properties from ID3 tags.
+
+use id3::{Tag, TagLike, Timestamp};
+
+use crate::{DateTime, Namespace, XmpWriter};
+
+/// XMP properties from audio tags.
+///
+/// Enabled by the `id3` feature.
+impl XmpWriter<'_> {
+    /// Write the XMP properties that correspond to the frames of an ID3 tag,
+    /// as parsed by the `id3` crate.
+    ///
+    /// Writes the title to `dc:title` and the artist, album artist, album,
+    /// composer, genre, track and disc numbers, and release date to the
+    /// `xmpDM:` properties of the dynamic media schema. If the tag has no
+    /// release date, its year is used instead. Frames that are missing from
+    /// the tag are skipped, as are the `dc:title` property if it was already
+    /// written.
+    pub fn id3(&mut self, tag: &Tag) -> &mut Self {
+        if let Some(title) = tag.title() {
+            if !self.has_property(Namespace::DublinCore, "title") {
+                self.title([(None, title)]);
+            }
+        }
+
+        for (name, value) in [
+            ("artist", tag.artist()),
+            ("albumArtist", tag.album_artist()),
+            ("album", tag.album()),
+            ("composer", tag.get("TCOM").and_then(|frame| frame.content().text())),
+        ] {
+            if let Some(value) = value.filter(|v| !v.is_empty()) {
+                self.element(name, Namespace::XmpDynamicMedia).value(value);
+            }
+        }
+
+        if let Some(genre) = tag.genre_parsed().filter(|g| !g.is_empty()) {
+            self.element("genre", Namespace::XmpDynamicMedia).value(&*genre);
+        }
+        if let Some(track) = tag.track() {
+            self.element("trackNumber", Namespace::XmpDynamicMedia)
+                .value(i64::from(track));
+        }
+        if let Some(disc) = tag.disc() {
+            let disc = match tag.total_discs() {
+                Some(total) => format!("{disc}/{total}"),
+                None => disc.to_string(),
+            };
+            self.element("discNumber", Namespace::XmpDynamicMedia)
+                .value(disc.as_str());
+        }
+
+        let released = tag.date_released().and_then(timestamp).or_else(|| {
+            tag.year()
+                .and_then(|year| u16::try_from(year).ok())
+                .filter(|&year| year > 0)
+                .map(DateTime::year)
+        });
+        if let Some(date) = released {
+            self.element("releaseDate", Namespace::XmpDynamicMedia).value(date);
+        }
+
+        self
+    }
+}
+
+/// A date from an ID3 timestamp, truncated before its first missing part.
+fn timestamp(stamp: Timestamp) -> Option<DateTime> {
+    let year = u16::try_from(stamp.year).ok().filter(|&year| year > 0)?;
+    let mut date = DateTime::year(year);
+    date.month = stamp.month;
+    date.day = stamp.month.and(stamp.day);
+    date.hour = date.day.and(stamp.hour);
+    date.minute = date.hour.and(stamp.minute);
+    date.second = date.minute.and(stamp.second);
+    Some(date)
+}
@@ -0,0 +1,386 @@
+//! Embedding of packets into image files.
+
+use xmp_writer::JPEG_MAX_PACKET_SIZE;
+
+/// The signature that starts a JPEG `APP1` segment with an XMP packet.
+pub const JPEG_XMP_SIGNATURE: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+/// The keyword of a PNG `iTXt` chunk with an XMP packet.
+pub const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+/// The signature of a PNG file.
+pub const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// The TIFF tag of an XMP packet.
+pub const TIFF_XMP_TAG: u16 = 700;
+
+/// The file formats a packet can be embedded into.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    Jpeg,
+    Png,
+    Tiff,
+    WebP,
+}
+
+impl Format {
+    /// Detect the format of a file from its first bytes.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\xff\xd8\xff") {
+            Some(Self::Jpeg)
+        } else if data.starts_with(PNG_SIGNATURE) {
+            Some(Self::Png)
+        } else if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+            Some(Self::Tiff)
+        } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+            Some(Self::WebP)
+        } else {
+            None
+        }
+    }
+}
+
+/// Embed a packet into a file, replacing the packet it already contains.
+pub fn embed(data: &[u8], packet: &str) -> Result<Vec<u8>, String> {
+    match Format::detect(data) {
+        Some(Format::Jpeg) => embed_jpeg(data, packet),
+        Some(Format::Png) => embed_png(data, packet),
+        Some(Format::Tiff) => embed_tiff(data, packet),
+        Some(Format::WebP) => embed_webp(data, packet),
+        None => Err("unsupported file format".into()),
+    }
+}
+
+/// Embed a packet into an `APP1` segment of a JPEG file.
+///
+/// The segment is placed after the leading `APP0` and Exif segments.
+/// Packets that need extended XMP are not supported.
+fn embed_jpeg(data: &[u8], packet: &str) -> Result<Vec<u8>, String> {
+    if packet.len() > JPEG_MAX_PACKET_SIZE {
+        return Err(format!(
+            "packet of {} bytes does not fit into a JPEG segment",
+            packet.len()
+        ));
+    }
+
+    let mut out = Vec::with_capacity(data.len() + packet.len() + 64);
+    out.extend_from_slice(&data[..2]);
+
+    let mut pos = 2;
+    let mut inserted = false;
+    loop {
+        let marker = jpeg_marker(data, pos)?;
+        // Standalone markers and the start of scan end the header segments.
+        if marker == 0xda || (0xd0..=0xd9).contains(&marker) {
+            break;
+        }
+
+        let len = usize::from(read_u16(data, pos + 2, false)?);
+        let end = pos + 2 + len;
+        let segment = data.get(pos..end).ok_or("truncated JPEG segment")?;
+        let body = segment.get(4..).ok_or("malformed JPEG segment")?;
+        let leading = marker == 0xe0 || (marker == 0xe1 && body.starts_with(b"Exif\0"));
+        if !leading && !inserted {
+            push_jpeg_xmp(&mut out, packet);
+            inserted = true;
+        }
+        if !(marker == 0xe1 && body.starts_with(JPEG_XMP_SIGNATURE)) {
+            out.extend_from_slice(segment);
+        }
+        pos = end;
+    }
+
+    if !inserted {
+        push_jpeg_xmp(&mut out, packet);
+    }
+    out.extend_from_slice(&data[pos..]);
+    Ok(out)
+}
+
+/// The marker of the JPEG segment at the position.
+fn jpeg_marker(data: &[u8], pos: usize) -> Result<u8, String> {
+    match data.get(pos..pos + 2) {
+        Some(&[0xff, marker]) => Ok(marker),
+        _ => Err("malformed JPEG segment".into()),
+    }
+}
+
+/// Append an `APP1` segment with the packet.
+fn push_jpeg_xmp(out: &mut Vec<u8>, packet: &str) {
+    let len = 2 + JPEG_XMP_SIGNATURE.len() + packet.len();
+    out.extend_from_slice(&[0xff, 0xe1]);
+    out.extend_from_slice(&(len as u16).to_be_bytes());
+    out.extend_from_slice(JPEG_XMP_SIGNATURE);
+    out.extend_from_slice(packet.as_bytes());
+}
+
+/// Embed a packet into an uncompressed `iTXt` chunk of a PNG file.
+///
+/// The chunk is placed directly after the `IHDR` chunk.
+fn embed_png(data: &[u8], packet: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(data.len() + packet.len() + 64);
+    out.extend_from_slice(PNG_SIGNATURE);
+
+    let mut pos = PNG_SIGNATURE.len();
+    while pos < data.len() {
+        let len = read_u32(data, pos, false)? as usize;
+        let end = pos + 12 + len;
+        let chunk = data.get(pos..end).ok_or("truncated PNG chunk")?;
+        let kind = &chunk[4..8];
+        let body = &chunk[8..8 + len];
+
+        let is_xmp = kind == b"iTXt"
+            && body.starts_with(PNG_XMP_KEYWORD)
+            && body.get(PNG_XMP_KEYWORD.len()) == Some(&0);
+        if !is_xmp {
+            out.extend_from_slice(chunk);
+        }
+        if kind == b"IHDR" {
+            push_png_xmp(&mut out, packet);
+        }
+        pos = end;
+    }
+
+    Ok(out)
+}
+
+/// Append an `iTXt` chunk with the packet.
+fn push_png_xmp(out: &mut Vec<u8>, packet: &str) {
+    let mut chunk = b"iTXt".to_vec();
+    chunk.extend_from_slice(PNG_XMP_KEYWORD);
+    // The keyword's terminator, no compression, and an empty language tag
+    // and translated keyword.
+    chunk.extend_from_slice(&[0, 0, 0, 0, 0]);
+    chunk.extend_from_slice(packet.as_bytes());
+
+    out.extend_from_slice(&((chunk.len() - 4) as u32).to_be_bytes());
+    out.extend_from_slice(&chunk);
+    out.extend_from_slice(&crc32(&chunk).to_be_bytes());
+}
+
+/// The CRC-32 checksum used by PNG.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Embed a packet into a TIFF file.
+///
+/// The packet and a copy of the first IFD with the XMP tag are appended to
+/// the file and the header is pointed to the new IFD. The data of the old IFD
+/// stays in place, so all offsets it contains remain valid.
+fn embed_tiff(data: &[u8], packet: &str) -> Result<Vec<u8>, String> {
+    let le = data.starts_with(b"II");
+    let ifd = read_u32(data, 4, le)? as usize;
+    let count = usize::from(read_u16(data, ifd, le)?);
+    let entries = data
+        .get(ifd + 2..ifd + 2 + 12 * count)
+        .ok_or("truncated TIFF directory")?;
+    let next = read_u32(data, ifd + 2 + 12 * count, le)?;
+
+    let mut out = data.to_vec();
+    // TIFF offsets must be word-aligned.
+    let align = |out: &mut Vec<u8>| {
+        if out.len() % 2 == 1 {
+            out.push(0);
+        }
+    };
+
+    align(&mut out);
+    let packet_offset = u32::try_from(out.len()).map_err(|_| "TIFF file is too large")?;
+    out.extend_from_slice(packet.as_bytes());
+    align(&mut out);
+
+    let u16_bytes = |v: u16| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+    let u32_bytes = |v: u32| if le { v.to_le_bytes() } else { v.to_be_bytes() };
+
+    let mut xmp = Vec::with_capacity(12);
+    xmp.extend_from_slice(&u16_bytes(TIFF_XMP_TAG));
+    // The type BYTE.
+    xmp.extend_from_slice(&u16_bytes(1));
+    xmp.extend_from_slice(&u32_bytes(packet.len() as u32));
+    xmp.extend_from_slice(&u32_bytes(packet_offset));
+
+    // Entries must be sorted by tag, so the XMP entry replaces an existing
+    // one or is inserted before the first entry with a larger tag.
+    let mut new_entries: Vec<&[u8]> = vec![];
+    let mut inserted = false;
+    for entry in entries.chunks(12) {
+        let tag = read_u16(entry, 0, le)?;
+        if !inserted && tag >= TIFF_XMP_TAG {
+            new_entries.push(&xmp);
+            inserted = true;
+        }
+        if tag != TIFF_XMP_TAG {
+            new_entries.push(entry);
+        }
+    }
+    if !inserted {
+        new_entries.push(&xmp);
+    }
+
+    let new_ifd = u32::try_from(out.len()).map_err(|_| "TIFF file is too large")?;
+    out.extend_from_slice(&u16_bytes(new_entries.len() as u16));
+    for entry in new_entries {
+        out.extend_from_slice(entry);
+    }
+    out.extend_from_slice(&u32_bytes(next));
+    out[4..8].copy_from_slice(&u32_bytes(new_ifd));
+    Ok(out)
+}
+
+/// Embed a packet into an `XMP ` chunk of a WebP file.
+///
+/// Simple files are converted to the extended format, as only it can hold
+/// metadata.
+fn embed_webp(data: &[u8], packet: &str) -> Result<Vec<u8>, String> {
+    let mut chunks = vec![];
+    let mut pos = 12;
+    while pos < data.len() {
+        let len = read_u32(data, pos + 4, true)? as usize;
+        let end = pos + 8 + len + len % 2;
+        let chunk = data.get(pos..end.min(data.len())).ok_or("truncated WebP chunk")?;
+        chunks.push(chunk);
+        pos = end;
+    }
+
+    let mut out = Vec::with_capacity(data.len() + packet.len() + 64);
+    out.extend_from_slice(b"RIFF\0\0\0\0WEBP");
+
+    match chunks.first() {
+        Some(chunk) if chunk.starts_with(b"VP8X") => {
+            let mut header = chunk.to_vec();
+            *header.get_mut(8).ok_or("truncated WebP chunk")? |= 0x04;
+            out.extend_from_slice(&header);
+        }
+        Some(chunk) => {
+            let (width, height, alpha) = webp_canvas(chunk)?;
+            let (Some(width), Some(height)) =
+                (width.checked_sub(1), height.checked_sub(1))
+            else {
+                return Err("WebP image has an empty canvas".into());
+            };
+            out.extend_from_slice(b"VP8X");
+            out.extend_from_slice(&10u32.to_le_bytes());
+            out.push(0x04 | if alpha { 0x10 } else { 0 });
+            out.extend_from_slice(&[0; 3]);
+            out.extend_from_slice(&width.to_le_bytes()[..3]);
+            out.extend_from_slice(&height.to_le_bytes()[..3]);
+            out.extend_from_slice(chunk);
+        }
+        None => return Err("WebP file has no image data".into()),
+    }
+
+    for chunk in &chunks[1..] {
+        if !chunk.starts_with(b"XMP ") {
+            out.extend_from_slice(chunk);
+        }
+    }
+
+    out.extend_from_slice(b"XMP ");
+    out.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+    out.extend_from_slice(packet.as_bytes());
+    if packet.len() % 2 == 1 {
+        out.push(0);
+    }
+
+    let size = u32::try_from(out.len() - 8).map_err(|_| "WebP file is too large")?;
+    out[4..8].copy_from_slice(&size.to_le_bytes());
+    Ok(out)
+}
+
+/// The canvas size and whether the image has an alpha channel for the image
+/// chunk of a simple WebP file.
+fn webp_canvas(chunk: &[u8]) -> Result<(u32, u32, bool), String> {
+    let body = &chunk[8..];
+    if chunk.starts_with(b"VP8 ") && body.len() >= 10 {
+        let width = u32::from(read_u16(body, 6, true)? & 0x3fff);
+        let height = u32::from(read_u16(body, 8, true)? & 0x3fff);
+        Ok((width, height, false))
+    } else if chunk.starts_with(b"VP8L") && body.len() >= 5 && body[0] == 0x2f {
+        let bits = read_u32(body, 1, true)?;
+        let width = (bits & 0x3fff) + 1;
+        let height = ((bits >> 14) & 0x3fff) + 1;
+        Ok((width, height, bits & (1 << 28) != 0))
+    } else {
+        Err("unsupported WebP image data".into())
+    }
+}
+
+/// Read a 16-bit integer.
+pub fn read_u16(data: &[u8], pos: usize, le: bool) -> Result<u16, String> {
+    let bytes = data.get(pos..pos + 2).ok_or("unexpected end of file")?;
+    let bytes = [bytes[0], bytes[1]];
+    Ok(if le { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+}
+
+/// Read a 32-bit integer.
+pub fn read_u32(data: &[u8], pos: usize, le: bool) -> Result<u32, String> {
+    let bytes = data.get(pos..pos + 4).ok_or("unexpected end of file")?;
+    let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+    Ok(if le { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKET: &str = "<x:xmpmeta/>";
+
+    #[test]
+    fn jpeg_round_trip() {
+        let data = b"\xff\xd8\xff\xe0\x00\x04ab\xff\xd9";
+        let out = embed(data, PACKET).unwrap();
+        assert!(out.starts_with(b"\xff\xd8\xff\xe0\x00\x04ab\xff\xe1"));
+        assert!(out.ends_with(b"<x:xmpmeta/>\xff\xd9"));
+        assert_eq!(embed(&out, PACKET).unwrap(), out);
+    }
+
+    #[test]
+    fn jpeg_malformed() {
+        assert!(embed(b"\xff\xd8\xff\xe1\x00\x00", PACKET).is_err());
+        assert!(embed(b"\xff\xd8\xff\xe1\x00\x01", PACKET).is_err());
+        assert!(embed(b"\xff\xd8\xff\xe1\x00\x10abc", PACKET).is_err());
+        assert!(embed(b"\xff\xd8\xff", PACKET).is_err());
+    }
+
+    #[test]
+    fn png_malformed() {
+        let mut data = PNG_SIGNATURE.to_vec();
+        data.extend_from_slice(b"\x00\x00\x00\x0dIHDR");
+        assert!(embed(&data, PACKET).is_err());
+        data.truncate(PNG_SIGNATURE.len() + 2);
+        assert!(embed(&data, PACKET).is_err());
+    }
+
+    #[test]
+    fn tiff_malformed() {
+        assert!(embed(b"II*\0\xff\xff\xff\xff", PACKET).is_err());
+        assert!(embed(b"MM\0*\0\0\0\x08\0\x05", PACKET).is_err());
+    }
+
+    #[test]
+    fn webp_malformed() {
+        let webp = |chunk: &[u8]| {
+            let mut data = b"RIFF\0\0\0\0WEBP".to_vec();
+            data.extend_from_slice(chunk);
+            data
+        };
+
+        // A lossy image with an empty canvas.
+        let mut chunk = b"VP8 \x0a\0\0\0".to_vec();
+        chunk.extend_from_slice(&[0, 0, 0, 0x9d, 0x01, 0x2a, 0, 0, 0, 0]);
+        assert!(embed(&webp(&chunk), PACKET).is_err());
+
+        // A truncated extended header.
+        assert!(embed(&webp(b"VP8X\x0a\0\0\0"), PACKET).is_err());
+        assert!(embed(&webp(b"VP8X"), PACKET).is_err());
+        assert!(embed(&webp(b""), PACKET).is_err());
+    }
+}
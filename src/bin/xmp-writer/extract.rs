@@ -0,0 +1,359 @@
+//! Extraction of packets from files.
+
+use std::borrow::Cow;
+use std::io::Read;
+
+use crate::embed::{
+    read_u16, read_u32, Format, JPEG_XMP_SIGNATURE, PNG_SIGNATURE, PNG_XMP_KEYWORD,
+    TIFF_XMP_TAG,
+};
+
+/// Find the packet embedded into a file.
+///
+/// JPEG, PNG, TIFF, and WebP files are read structurally. For other files,
+/// e.g. PDFs, the last packet wrapper in the file or in one of its
+/// `FlateDecode` metadata streams is returned, as incremental updates append
+/// newer packets.
+pub fn extract(data: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    let packet = match Format::detect(data) {
+        Some(Format::Jpeg) => extract_jpeg(data)?.map(Cow::Borrowed),
+        Some(Format::Png) => extract_png(data)?,
+        Some(Format::Tiff) => extract_tiff(data)?.map(Cow::Borrowed),
+        Some(Format::WebP) => extract_webp(data)?.map(Cow::Borrowed),
+        None => scan(data),
+    };
+    packet.ok_or_else(|| "file contains no XMP packet".into())
+}
+
+/// Find the packet in the `APP1` segments of a JPEG file.
+///
+/// Only the main packet is returned, extended XMP is not reassembled.
+fn extract_jpeg(data: &[u8]) -> Result<Option<&[u8]>, String> {
+    let mut pos = 2;
+    while let Some(&[0xff, marker]) = data.get(pos..pos + 2) {
+        if marker == 0xda || (0xd0..=0xd9).contains(&marker) {
+            break;
+        }
+
+        let len = usize::from(read_u16(data, pos + 2, false)?);
+        let body = data.get(pos + 4..pos + 2 + len).ok_or("truncated JPEG segment")?;
+        if marker == 0xe1 {
+            if let Some(packet) = body.strip_prefix(JPEG_XMP_SIGNATURE) {
+                return Ok(Some(packet));
+            }
+        }
+        pos += 2 + len;
+    }
+    Ok(None)
+}
+
+/// Find the packet in the `iTXt` chunks of a PNG file.
+fn extract_png(data: &[u8]) -> Result<Option<Cow<'_, [u8]>>, String> {
+    let mut pos = PNG_SIGNATURE.len();
+    while pos < data.len() {
+        let len = read_u32(data, pos, false)? as usize;
+        let kind = data.get(pos + 4..pos + 8).ok_or("truncated PNG chunk")?;
+        let body = data.get(pos + 8..pos + 8 + len).ok_or("truncated PNG chunk")?;
+        pos += 12 + len;
+
+        let Some(rest) = body.strip_prefix(PNG_XMP_KEYWORD) else { continue };
+        if kind != b"iTXt" || rest.first() != Some(&0) {
+            continue;
+        }
+        let compressed = rest.get(1).is_some_and(|&flag| flag != 0);
+
+        // Skip the keyword's terminator, the compression flag and method, and
+        // the language tag and translated keyword.
+        let mut text = rest.get(3..).unwrap_or_default();
+        for _ in 0..2 {
+            let end = text.iter().position(|&b| b == 0).ok_or("malformed iTXt chunk")?;
+            text = &text[end + 1..];
+        }
+
+        if compressed {
+            let text = inflate(text).ok_or("malformed compressed iTXt chunk")?;
+            return Ok(Some(Cow::Owned(text)));
+        }
+        return Ok(Some(Cow::Borrowed(text)));
+    }
+    Ok(None)
+}
+
+/// Find the packet in the first IFD of a TIFF file.
+fn extract_tiff(data: &[u8]) -> Result<Option<&[u8]>, String> {
+    let le = data.starts_with(b"II");
+    let ifd = read_u32(data, 4, le)? as usize;
+    let count = usize::from(read_u16(data, ifd, le)?);
+    for i in 0..count {
+        let entry = ifd + 2 + 12 * i;
+        if read_u16(data, entry, le)? != TIFF_XMP_TAG {
+            continue;
+        }
+
+        let len = read_u32(data, entry + 4, le)? as usize;
+        let start =
+            if len <= 4 { entry + 8 } else { read_u32(data, entry + 8, le)? as usize };
+        let packet = data.get(start..start + len).ok_or("truncated TIFF packet")?;
+        return Ok(Some(packet));
+    }
+    Ok(None)
+}
+
+/// Find the packet in the `XMP ` chunk of a WebP file.
+fn extract_webp(data: &[u8]) -> Result<Option<&[u8]>, String> {
+    let mut pos = 12;
+    while pos < data.len() {
+        let len = read_u32(data, pos + 4, true)? as usize;
+        if data.get(pos..pos + 4) == Some(b"XMP ") {
+            let packet =
+                data.get(pos + 8..pos + 8 + len).ok_or("truncated WebP chunk")?;
+            return Ok(Some(packet));
+        }
+        pos += 8 + len + len % 2;
+    }
+    Ok(None)
+}
+
+/// Find the last packet in arbitrary data, either as plain text or in a
+/// `FlateDecode` metadata stream of a PDF.
+fn scan(data: &[u8]) -> Option<Cow<'_, [u8]>> {
+    let mut found = scan_plain(data).map(|(pos, packet)| (pos, Cow::Borrowed(packet)));
+    for (pos, stream) in flate_metadata_streams(data) {
+        if found.as_ref().is_some_and(|&(found, _)| found > pos) {
+            continue;
+        }
+        let Some(decoded) = inflate(stream) else { continue };
+        if let Some((_, packet)) = scan_plain(&decoded) {
+            found = Some((pos, Cow::Owned(packet.to_vec())));
+        }
+    }
+    found.map(|(_, packet)| packet)
+}
+
+/// Find the last packet wrapper in data and its position.
+fn scan_plain(data: &[u8]) -> Option<(usize, &[u8])> {
+    let start = rfind(data, b"<?xpacket begin=")?;
+    let end = start + find(&data[start..], b"<?xpacket end=")?;
+    let close = end + find(&data[end..], b"?>")? + 2;
+    Some((start, &data[start..close]))
+}
+
+/// The positions and data of the PDF streams whose dictionary declares the
+/// `Metadata` type and the `FlateDecode` filter.
+fn flate_metadata_streams(data: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut pos = 0;
+    std::iter::from_fn(move || loop {
+        let keyword = pos + find(&data[pos..], b"stream")?;
+        pos = keyword + b"stream".len();
+        if keyword > 0 && data[keyword - 1] == b'd' {
+            // The `endstream` keyword.
+            continue;
+        }
+
+        let dict_start = rfind(&data[..keyword], b"obj").unwrap_or(0);
+        let dict = &data[dict_start..keyword];
+        if find(dict, b"/Metadata").is_none() || find(dict, b"/FlateDecode").is_none() {
+            continue;
+        }
+
+        let rest = &data[pos..];
+        let body = rest
+            .strip_prefix(b"\r\n")
+            .or_else(|| rest.strip_prefix(b"\n"))
+            .unwrap_or(rest);
+        let start = data.len() - body.len();
+        let end = start + find(body, b"endstream").unwrap_or(body.len());
+        pos = end;
+        return Some((keyword, &data[start..end]));
+    })
+}
+
+/// Decompress zlib data, ignoring anything after its end.
+fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+    let mut out = vec![];
+    flate2::read::ZlibDecoder::new(data).read_to_end(&mut out).ok()?;
+    Some(out)
+}
+
+/// The position of the first occurrence of the needle.
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+/// The position of the last occurrence of the needle.
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|window| window == needle)
+}
+
+/// Indent the elements of a packet, one per line.
+///
+/// Elements that only contain text stay on one line and the text is kept
+/// verbatim, so that the values are not changed.
+pub fn pretty(packet: &str) -> String {
+    let tokens = tokenize(packet);
+    let mut out = String::with_capacity(packet.len() * 2);
+    let mut depth = 0usize;
+    let indent = |out: &mut String, depth: usize| {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        out.extend(std::iter::repeat_n("  ", depth));
+    };
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+
+        if !token.starts_with('<') {
+            if !token.trim().is_empty() {
+                indent(&mut out, depth);
+                out.push_str(token);
+            }
+        } else if token.starts_with("</") {
+            depth = depth.saturating_sub(1);
+            indent(&mut out, depth);
+            out.push_str(token);
+        } else if token.starts_with("<?")
+            || token.starts_with("<!")
+            || token.ends_with("/>")
+        {
+            indent(&mut out, depth);
+            out.push_str(token);
+        } else if let [text, end, ..] = tokens[i + 1..] {
+            indent(&mut out, depth);
+            out.push_str(token);
+            if !text.starts_with('<') && end.starts_with("</") {
+                out.push_str(text);
+                out.push_str(end);
+                i += 2;
+            } else if text.starts_with("</") {
+                out.push_str(text);
+                i += 1;
+            } else {
+                depth += 1;
+            }
+        } else {
+            indent(&mut out, depth);
+            out.push_str(token);
+            depth += 1;
+        }
+        i += 1;
+    }
+    out.push('\n');
+    out
+}
+
+/// Split XML into markup and text.
+fn tokenize(xml: &str) -> Vec<&str> {
+    let mut tokens = vec![];
+    let mut rest = xml;
+    while !rest.is_empty() {
+        let len = if rest.starts_with('<') {
+            markup_len(rest)
+        } else {
+            rest.find('<').unwrap_or(rest.len())
+        };
+        tokens.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+    tokens
+}
+
+/// The length of the markup at the start of the text.
+fn markup_len(text: &str) -> usize {
+    let delimited = |end: &str| text.find(end).map_or(text.len(), |i| i + end.len());
+    if text.starts_with("<!--") {
+        delimited("-->")
+    } else if text.starts_with("<![CDATA[") {
+        delimited("]]>")
+    } else if text.starts_with("<?") {
+        delimited("?>")
+    } else {
+        // Attribute values may contain `>`.
+        let mut quote = None;
+        for (i, c) in text.char_indices() {
+            match (quote, c) {
+                (None, '"' | '\'') => quote = Some(c),
+                (Some(q), c) if q == c => quote = None,
+                (None, '>') => return i + 1,
+                _ => {}
+            }
+        }
+        text.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::embed::embed;
+
+    const PACKET: &str = "<?xpacket begin=\"\"?><x:xmpmeta/><?xpacket end=\"r\"?>";
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn extract_embedded() {
+        let jpeg = b"\xff\xd8\xff\xe0\x00\x04ab\xff\xd9".to_vec();
+        let png = [PNG_SIGNATURE, b"\0\0\0\x0dIHDR", &[0; 17]].concat();
+        let tiff = b"II*\0\x08\0\0\0\0\0\0\0\0\0".to_vec();
+        let webp = b"RIFF\0\0\0\0WEBPVP8L\x05\0\0\0\x2f\0\0\0\0\0".to_vec();
+        for data in [jpeg, png, tiff, webp] {
+            let embedded = embed(&data, PACKET).unwrap();
+            assert_eq!(extract(&embedded).unwrap(), PACKET.as_bytes());
+        }
+    }
+
+    #[test]
+    fn extract_compressed_png() {
+        let mut chunk = b"iTXt".to_vec();
+        chunk.extend_from_slice(PNG_XMP_KEYWORD);
+        chunk.extend_from_slice(&[0, 1, 0, 0, 0]);
+        chunk.extend_from_slice(&deflate(PACKET.as_bytes()));
+
+        let mut data = PNG_SIGNATURE.to_vec();
+        data.extend_from_slice(&((chunk.len() - 4) as u32).to_be_bytes());
+        data.extend_from_slice(&chunk);
+        data.extend_from_slice(&[0; 4]);
+        assert_eq!(extract(&data).unwrap(), PACKET.as_bytes());
+    }
+
+    #[test]
+    fn extract_pdf() {
+        let old = PACKET.replace("xmpmeta", "old");
+        let mut data = b"%PDF-1.7\n1 0 obj\n<< /Type /Metadata >>\nstream\n".to_vec();
+        data.extend_from_slice(old.as_bytes());
+        data.extend_from_slice(b"\nendstream\nendobj\n");
+        assert_eq!(extract(&data).unwrap(), old.as_bytes());
+
+        // An incremental update with a compressed stream.
+        data.extend_from_slice(
+            b"2 0 obj\n<< /Type /Metadata /Filter /FlateDecode >>\nstream\r\n",
+        );
+        data.extend_from_slice(&deflate(PACKET.as_bytes()));
+        data.extend_from_slice(b"\r\nendstream\nendobj\n");
+        assert_eq!(extract(&data).unwrap(), PACKET.as_bytes());
+
+        assert!(extract(b"%PDF-1.7\n").is_err());
+    }
+
+    #[test]
+    fn extract_malformed() {
+        assert!(extract(b"\xff\xd8\xff\xe1\x00\x40").is_err());
+        assert!(extract(b"II*\0\xff\0\0\0").is_err());
+        assert!(extract(b"RIFF\0\0\0\0WEBPXMP \xff\0\0\0").is_err());
+    }
+
+    #[test]
+    fn pretty_keeps_text() {
+        let packet = "<a><b x=\"1>2\">text</b><c/></a>";
+        assert_eq!(pretty(packet), "<a>\n  <b x=\"1>2\">text</b>\n  <c/>\n</a>\n");
+    }
+}
@@ -0,0 +1,333 @@
+//! A command line interface for the XMP serializer.
+//!
+//! Requires the `cli` feature.
+
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use xmp_writer::{
+    CustomNamespace, Namespace, Severity, XmpMetadata, XmpPacket, XmpWriter,
+};
+
+mod embed;
+mod extract;
+
+const USAGE: &str = "\
+usage: xmp-writer <command> [options]
+
+commands:
+  build <description> [-o <output>] [--about <uri>]
+      Serialize a metadata description in JSON or TOML into an XMP packet.
+      The format is chosen by the file extension. Pass `-` to read JSON from
+      stdin. The packet is written to stdout unless an output file is given.
+
+  embed <target> <source> [-o <output>] [--about <uri>] [--sidecar]
+      Embed a packet into a JPEG, PNG, TIFF, or WebP file, replacing the
+      packet it contains. The source is a description as for `build` or,
+      for other extensions, a finished packet. The target is modified in
+      place unless an output file is given. With `--sidecar`, the packet is
+      written to a file next to the target with the extension `.xmp`
+      instead.
+
+  validate <packet> [--json]
+      Check that a packet is well-formed and lint it, including the PDF/A
+      rules if it declares a PDF/A part. Prints one finding per line, or a
+      JSON array of findings with `--json`. Fails if there are errors.
+
+  extract <file> [-o <output>] [--pretty]
+      Find the packet in a JPEG, PNG, TIFF, WebP, or PDF file and write it to
+      stdout unless an output file is given. With `--pretty`, the elements
+      are indented one per line.
+";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("build") => build(&args[1..]),
+        Some("embed") => embed(&args[1..]),
+        Some("validate") => validate(&args[1..]),
+        Some("extract") => extract(&args[1..]),
+        Some("-h" | "--help") => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Some(command) => Err(format!("unknown command `{command}`\n\n{USAGE}")),
+        None => Err(format!("no command given\n\n{USAGE}")),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// A declarative description of a packet.
+#[derive(serde::Deserialize)]
+struct Description {
+    /// The `rdf:about` value of the packet.
+    #[serde(default)]
+    about: Option<String>,
+    /// Properties that are not covered by [`XmpMetadata`].
+    #[serde(default)]
+    custom: Vec<CustomProperty>,
+    /// The common properties.
+    #[serde(flatten)]
+    metadata: XmpMetadata,
+}
+
+/// A property in a custom namespace.
+#[derive(serde::Deserialize)]
+struct CustomProperty {
+    /// The namespace URL.
+    namespace: String,
+    /// The namespace prefix.
+    prefix: String,
+    /// The local name of the property.
+    name: String,
+    /// The value of the property.
+    value: CustomValue,
+}
+
+/// The value of a custom property.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum CustomValue {
+    /// A text.
+    Text(String),
+    /// An ordered array of texts.
+    Seq(Vec<String>),
+}
+
+/// The parsed arguments of a command.
+struct Options<'a> {
+    positional: Vec<&'a str>,
+    named: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Options<'a> {
+    /// Split the arguments into positional arguments and options that take
+    /// one of the given names.
+    fn parse(args: &'a [String], names: &[&str]) -> Result<Self, String> {
+        let mut options = Self { positional: vec![], named: vec![] };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if names.contains(&arg.as_str()) {
+                let value =
+                    iter.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                options.named.push((arg, value));
+            } else if arg.starts_with('-') && arg != "-" {
+                return Err(format!("unknown option `{arg}`"));
+            } else {
+                options.positional.push(arg);
+            }
+        }
+        Ok(options)
+    }
+
+    /// The value of a named option.
+    fn get(&self, name: &str) -> Option<&'a str> {
+        self.named.iter().rev().find(|(n, _)| *n == name).map(|&(_, v)| v)
+    }
+
+    /// The single positional argument.
+    fn single(&self, what: &str) -> Result<&'a str, String> {
+        match self.positional.as_slice() {
+            [arg] => Ok(arg),
+            [] => Err(format!("no {what} given")),
+            _ => Err("too many arguments".into()),
+        }
+    }
+}
+
+/// Serialize a metadata description.
+fn build(args: &[String]) -> Result<(), String> {
+    let options = Options::parse(args, &["-o", "--output", "--about"])?;
+    let input = options.single("description")?;
+    let packet = build_packet(input, options.get("--about"))?;
+    write_output(options.get("-o").or(options.get("--output")), packet.as_bytes())
+}
+
+/// Embed a packet into a file or write it to a sidecar.
+fn embed(args: &[String]) -> Result<(), String> {
+    let (flags, args): (Vec<_>, Vec<_>) =
+        args.iter().cloned().partition(|arg| arg == "--sidecar");
+    let options = Options::parse(&args, &["-o", "--output", "--about"])?;
+    let [target, source] = options.positional[..] else {
+        return Err("expected a target and a source".into());
+    };
+
+    let is_description = source == "-"
+        || Path::new(source).extension().is_some_and(|ext| {
+            ext.eq_ignore_ascii_case("json") || ext.eq_ignore_ascii_case("toml")
+        });
+    let packet = if is_description {
+        build_packet(source, options.get("--about"))?
+    } else {
+        read_text(source)?
+    };
+
+    let output = options.get("-o").or(options.get("--output"));
+    if !flags.is_empty() {
+        let sidecar = Path::new(target).with_extension("xmp");
+        let path = output.map_or(sidecar, Into::into);
+        return write_file(&path, packet.as_bytes())
+            .map_err(|err| format!("failed to write {}: {err}", path.display()));
+    }
+
+    let data =
+        std::fs::read(target).map_err(|err| format!("failed to read {target}: {err}"))?;
+    let data = embed::embed(&data, &packet).map_err(|err| format!("{target}: {err}"))?;
+    write_output(Some(output.unwrap_or(target)), &data)
+}
+
+/// Check a packet and print the findings.
+fn validate(args: &[String]) -> Result<(), String> {
+    let (flags, args): (Vec<_>, Vec<_>) =
+        args.iter().cloned().partition(|arg| arg == "--json");
+    let options = Options::parse(&args, &[])?;
+    let input = options.single("packet")?;
+    let text = read_text(input)?;
+
+    let packet = XmpPacket::parse(&text)
+        .map_err(|err| format!("{input}: packet is not well-formed: {err}"))?;
+    let findings = packet.lint();
+
+    if flags.is_empty() {
+        for finding in &findings {
+            println!("{input}: {finding}");
+        }
+    } else {
+        let json: Vec<_> = findings
+            .iter()
+            .map(|finding| {
+                serde_json::json!({
+                    "severity": match finding.severity {
+                        Severity::Error => "error",
+                        Severity::Warning => "warning",
+                    },
+                    "rule": finding.rule,
+                    "property": finding.property,
+                    "message": finding.message,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::Value::Array(json));
+    }
+
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    match errors {
+        0 => Ok(()),
+        1 => Err(format!("{input}: found 1 error")),
+        n => Err(format!("{input}: found {n} errors")),
+    }
+}
+
+/// Find the packet in a file and print it.
+fn extract(args: &[String]) -> Result<(), String> {
+    let (flags, args): (Vec<_>, Vec<_>) =
+        args.iter().cloned().partition(|arg| arg == "--pretty");
+    let options = Options::parse(&args, &["-o", "--output"])?;
+    let input = options.single("file")?;
+
+    let data =
+        std::fs::read(input).map_err(|err| format!("failed to read {input}: {err}"))?;
+    let packet = extract::extract(&data).map_err(|err| format!("{input}: {err}"))?;
+    let output = options.get("-o").or(options.get("--output"));
+    if flags.is_empty() {
+        return write_output(output, &packet);
+    }
+
+    let packet = std::str::from_utf8(&packet)
+        .map_err(|_| format!("{input}: packet is not valid UTF-8"))?;
+    write_output(output, extract::pretty(packet).as_bytes())
+}
+
+/// Read a metadata description and serialize it.
+fn build_packet(input: &str, about: Option<&str>) -> Result<String, String> {
+    let description = read_description(input)?;
+
+    let mut writer = XmpWriter::new();
+    description.metadata.write(&mut writer);
+    for property in &description.custom {
+        let namespace = Namespace::Custom(Box::new(CustomNamespace::new(
+            &property.prefix,
+            &property.prefix,
+            &property.namespace,
+        )));
+        let element = writer
+            .try_element(&property.name, namespace)
+            .map_err(|err| err.to_string())?;
+        match &property.value {
+            CustomValue::Text(text) => element.value(text.as_str()),
+            CustomValue::Seq(items) => {
+                element.ordered_array(items.iter().map(String::as_str))
+            }
+        }
+    }
+
+    Ok(writer.finish(about.or(description.about.as_deref())))
+}
+
+/// Read and parse a description from a file or stdin.
+fn read_description(input: &str) -> Result<Description, String> {
+    let text = read_text(input)?;
+    let is_toml = Path::new(input)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+    if is_toml {
+        toml::from_str(&text).map_err(|err| format!("{input}: {err}"))
+    } else {
+        serde_json::from_str(&text).map_err(|err| format!("{input}: {err}"))
+    }
+}
+
+/// Read a text file, or stdin for `-`.
+fn read_text(input: &str) -> Result<String, String> {
+    if input == "-" {
+        let mut text = String::new();
+        std::io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|err| format!("failed to read stdin: {err}"))?;
+        Ok(text)
+    } else {
+        std::fs::read_to_string(input)
+            .map_err(|err| format!("failed to read {input}: {err}"))
+    }
+}
+
+/// Write data to a file, or stdout if no file is given.
+fn write_output(output: Option<&str>, data: &[u8]) -> Result<(), String> {
+    match output {
+        Some(path) => write_file(Path::new(path), data)
+            .map_err(|err| format!("failed to write {path}: {err}")),
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            stdout
+                .write_all(data)
+                .and_then(|()| stdout.flush())
+                .map_err(|err| format!("failed to write to stdout: {err}"))
+        }
+    }
+}
+
+/// Write data to a temporary file next to the path and then rename it over
+/// the path, so that the file is never left partially written.
+fn write_file(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".tmp");
+    let temp = path.with_file_name(name);
+
+    let result = std::fs::write(&temp, data).and_then(|()| std::fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
@@ -0,0 +1,563 @@
+//! Checks for PDF/A conformance of XMP packets.
+//!
+//! Enabled by the `pdfa` feature (enabled by default).
+
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use crate::{LintFinding, Namespace, RdfCollectionType, Severity, Value, XmpPacket};
+
+impl XmpPacket<'_> {
+    /// Check the packet against the metadata requirements of a part of the
+    /// PDF/A standard.
+    ///
+    /// This checks that the `pdfaid` identification is present and matches
+    /// the part, that properties of the predefined schemas have the right
+    /// types, that thumbnails are JPEG images, and, for parts 1 to 3, that
+    /// all other properties are described by a complete extension schema. It
+    /// does not replace a full validator like veraPDF. See
+    /// [`XmpPacket::lint`] for more detailed findings.
+    pub fn check_pdfa(&self, part: i32) -> Vec<PdfAViolation> {
+        let mut findings = vec![];
+        lint_pdfa(self, part, &mut findings);
+        findings
+            .into_iter()
+            .filter(|finding| finding.severity == Severity::Error)
+            .map(|finding| PdfAViolation::new(finding.property, finding.message))
+            .collect()
+    }
+}
+
+/// The clauses of a part of the PDF/A standard that concern metadata.
+struct Rules {
+    /// The PDF/A identification schema.
+    identification: &'static str,
+    /// The validity of the extension schema container.
+    container: &'static str,
+    /// Properties being predefined or described, and their types.
+    properties: &'static str,
+}
+
+impl Rules {
+    fn of(part: i32) -> Option<Self> {
+        Some(match part {
+            1 => Self {
+                identification: "6.7.11",
+                container: "6.7.8",
+                properties: "6.7.9",
+            },
+            2 | 3 => Self {
+                identification: "6.6.4",
+                container: "6.6.2.3.2",
+                properties: "6.6.2.3.1",
+            },
+            // PDF/A-4 does not require extension schemas anymore.
+            _ => return None,
+        })
+    }
+}
+
+/// Check the packet against the metadata rules of a PDF/A part.
+pub(crate) fn lint_pdfa(packet: &XmpPacket, part: i32, findings: &mut Vec<LintFinding>) {
+    let rules = Rules::of(part);
+    let identification = rules.as_ref().map_or("6.7.3", |rules| rules.identification);
+    let mut error = |rule: &'static str, property: &str, message: String| {
+        findings.push(LintFinding {
+            severity: Severity::Error,
+            rule: Some(rule),
+            property: property.into(),
+            message,
+        });
+    };
+
+    match packet.get(&Namespace::PdfAId, "part").and_then(Value::as_text) {
+        None => error(identification, "pdfaid:part", "is missing".into()),
+        Some(found) if found.trim().parse() != Ok(part) => error(
+            identification,
+            "pdfaid:part",
+            format!("is {found}, but the target is PDF/A-{part}"),
+        ),
+        Some(_) => {}
+    }
+
+    let levels: &[&str] = match part {
+        1 => &["A", "B"],
+        2 | 3 => &["A", "B", "U"],
+        _ => &["E", "F"],
+    };
+    match packet.get(&Namespace::PdfAId, "conformance").and_then(Value::as_text) {
+        None if part <= 3 => {
+            error(identification, "pdfaid:conformance", "is missing".into());
+        }
+        Some(level) if !levels.contains(&level) => error(
+            identification,
+            "pdfaid:conformance",
+            format!("{level:?} is not a conformance level of PDF/A-{part}"),
+        ),
+        _ => {}
+    }
+
+    if part >= 4 {
+        match packet.get(&Namespace::PdfAId, "rev").and_then(Value::as_text) {
+            None => error(identification, "pdfaid:rev", "is missing".into()),
+            Some(rev) if rev.len() != 4 || !rev.bytes().all(|b| b.is_ascii_digit()) => {
+                error(identification, "pdfaid:rev", format!("{rev:?} is not a year"))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let Some(rules) = rules else { return };
+    let schemas = ExtensionSchemas::read(packet, rules.container, &mut error);
+
+    for property in packet.properties() {
+        let qname = property.qname();
+        let kind = match predefined(&property.namespace, &property.name, part) {
+            Some(kind) => Some(kind),
+            None => match schemas.value_type(property.namespace.url(), &property.name) {
+                Some(value_type) => Kind::of_value_type(value_type),
+                None => {
+                    error(
+                        rules.properties,
+                        &qname,
+                        "is not predefined and not described by an extension schema"
+                            .into(),
+                    );
+                    continue;
+                }
+            },
+        };
+
+        if let Some(kind) = kind.filter(|kind| !kind.matches(&property.value)) {
+            error(rules.properties, &qname, format!("must be of type {}", kind.name()));
+        }
+    }
+
+    if let Some(Value::Array(_, thumbnails)) = packet.get(&Namespace::Xmp, "Thumbnails") {
+        for thumbnail in thumbnails {
+            let format = thumbnail
+                .field(&Namespace::XmpImage, "format")
+                .and_then(Value::as_text);
+            if format != Some("JPEG") {
+                error(
+                    rules.properties,
+                    "xmp:Thumbnails",
+                    "thumbnails must have the format JPEG".into(),
+                );
+            }
+        }
+    }
+}
+
+/// A violation of the PDF/A metadata requirements.
+///
+/// Returned by [`XmpPacket::check_pdfa`] and as part of
+/// [`FinishError::PdfA`](crate::FinishError::PdfA).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdfAViolation {
+    /// The qualified name of the affected property, e.g. `pdfaid:part`.
+    pub property: String,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl PdfAViolation {
+    fn new(property: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { property: property.into(), message: message.into() }
+    }
+}
+
+impl Display for PdfAViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.property, self.message)
+    }
+}
+
+/// The type of a predefined property.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Text,
+    Integer,
+    Real,
+    Boolean,
+    Date,
+    Array(RdfCollectionType),
+    LangAlt,
+    Struct,
+}
+
+impl Kind {
+    pub(crate) fn matches(self, value: &Value) -> bool {
+        let value = match value {
+            Value::Qualified(value, _) => value,
+            value => value,
+        };
+
+        match (self, value) {
+            (Self::Text, Value::Text(_)) => true,
+            (Self::Integer, Value::Text(text)) => {
+                let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+                !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+            }
+            (Self::Integer, Value::Integer(_)) => true,
+            (Self::Real, Value::Text(text)) => text.parse::<f64>().is_ok(),
+            (Self::Real, Value::Integer(_) | Value::Real(_)) => true,
+            (Self::Boolean, Value::Text(text)) => text == "True" || text == "False",
+            (Self::Boolean, Value::Bool(_)) => true,
+            (Self::Date, Value::Text(text)) => is_date(text),
+            (Self::Date, Value::Date(_)) => true,
+            (Self::Array(kind), Value::Array(found, _)) => kind == *found,
+            (Self::LangAlt, Value::LangAlt(_)) => true,
+            (Self::LangAlt, Value::Array(RdfCollectionType::Alt, items)) => {
+                items.is_empty()
+            }
+            (Self::Struct, Value::Struct(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// The kind of a value type of an extension schema, if it can be
+    /// checked.
+    fn of_value_type(value_type: &str) -> Option<Self> {
+        Some(match value_type {
+            "Text" => Self::Text,
+            "Integer" => Self::Integer,
+            "Real" => Self::Real,
+            "Boolean" => Self::Boolean,
+            "Date" => Self::Date,
+            "Lang Alt" => Self::LangAlt,
+            _ if value_type.starts_with("bag ") => Self::Array(RdfCollectionType::Bag),
+            _ if value_type.starts_with("seq ") => Self::Array(RdfCollectionType::Seq),
+            _ if value_type.starts_with("alt ") => Self::Array(RdfCollectionType::Alt),
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Text => "Text",
+            Self::Integer => "Integer",
+            Self::Real => "Real",
+            Self::Boolean => "Boolean",
+            Self::Date => "Date",
+            Self::Array(RdfCollectionType::Bag) => "bag",
+            Self::Array(RdfCollectionType::Seq) => "seq",
+            Self::Array(RdfCollectionType::Alt) => "alt",
+            Self::LangAlt => "Lang Alt",
+            Self::Struct => "struct",
+        }
+    }
+}
+
+/// The type of a property of the schemas that are predefined by the part of
+/// the PDF/A standard, or `None` if it needs an extension schema.
+pub(crate) fn predefined(namespace: &Namespace, name: &str, part: i32) -> Option<Kind> {
+    use Kind::*;
+    use RdfCollectionType::*;
+
+    // PDF/A-1 predefines the schemas of XMP 2004, later parts those of XMP
+    // 2005.
+    let xmp_2005 = part >= 2;
+    let known = Namespace::from_url(namespace.url())?;
+    Some(match (known, name) {
+        (Namespace::DublinCore, "contributor") => Array(Bag),
+        (Namespace::DublinCore, "coverage") => Text,
+        (Namespace::DublinCore, "creator") => Array(Seq),
+        (Namespace::DublinCore, "date") => Array(Seq),
+        (Namespace::DublinCore, "description") => LangAlt,
+        (Namespace::DublinCore, "format") => Text,
+        (Namespace::DublinCore, "identifier") => Text,
+        (Namespace::DublinCore, "language") => Array(Bag),
+        (Namespace::DublinCore, "publisher") => Array(Bag),
+        (Namespace::DublinCore, "relation") => Array(Bag),
+        (Namespace::DublinCore, "rights") => LangAlt,
+        (Namespace::DublinCore, "source") => Text,
+        (Namespace::DublinCore, "subject") => Array(Bag),
+        (Namespace::DublinCore, "title") => LangAlt,
+        (Namespace::DublinCore, "type") => Array(Bag),
+        (Namespace::Xmp, "Advisory") => Array(Bag),
+        (Namespace::Xmp, "BaseURL") => Text,
+        (Namespace::Xmp, "CreateDate") => Date,
+        (Namespace::Xmp, "CreatorTool") => Text,
+        (Namespace::Xmp, "Identifier") => Array(Bag),
+        (Namespace::Xmp, "Label") if xmp_2005 => Text,
+        (Namespace::Xmp, "MetadataDate") => Date,
+        (Namespace::Xmp, "ModifyDate") => Date,
+        (Namespace::Xmp, "Nickname") => Text,
+        (Namespace::Xmp, "Rating") if xmp_2005 => Real,
+        (Namespace::Xmp, "Thumbnails") => Array(Alt),
+        (Namespace::XmpRights, "Certificate") => Text,
+        (Namespace::XmpRights, "Marked") => Boolean,
+        (Namespace::XmpRights, "Owner") => Array(Bag),
+        (Namespace::XmpRights, "UsageTerms") => LangAlt,
+        (Namespace::XmpRights, "WebStatement") => Text,
+        (Namespace::XmpMedia, "DerivedFrom") => Struct,
+        (Namespace::XmpMedia, "DocumentID") => Text,
+        (Namespace::XmpMedia, "History") => Array(Seq),
+        (Namespace::XmpMedia, "InstanceID") if xmp_2005 => Text,
+        (Namespace::XmpMedia, "LastURL") => Text,
+        (Namespace::XmpMedia, "ManagedFrom") => Struct,
+        (Namespace::XmpMedia, "Manager") => Text,
+        (Namespace::XmpMedia, "ManageTo") => Text,
+        (Namespace::XmpMedia, "ManageUI") => Text,
+        (Namespace::XmpMedia, "ManagerVariant") => Text,
+        (Namespace::XmpMedia, "RenditionClass") => Text,
+        (Namespace::XmpMedia, "RenditionOf") => Struct,
+        (Namespace::XmpMedia, "RenditionParams") => Text,
+        (Namespace::XmpMedia, "SaveID") => Integer,
+        (Namespace::XmpMedia, "VersionID") => Text,
+        (Namespace::XmpMedia, "Versions") => Array(Seq),
+        (Namespace::XmpJobManagement, "JobRef") => Array(Bag),
+        (Namespace::XmpPaged, "Colorants") if xmp_2005 => Array(Seq),
+        (Namespace::XmpPaged, "Fonts") if xmp_2005 => Array(Bag),
+        (Namespace::XmpPaged, "MaxPageSize") => Struct,
+        (Namespace::XmpPaged, "NPages") => Integer,
+        (Namespace::XmpPaged, "PlateNames") if xmp_2005 => Array(Seq),
+        (Namespace::AdobePdf, "Keywords") => Text,
+        (Namespace::AdobePdf, "PDFVersion") => Text,
+        (Namespace::AdobePdf, "Producer") => Text,
+        (Namespace::AdobePdf, "Trapped") if xmp_2005 => Text,
+        (Namespace::PdfAId, "part") => Integer,
+        (Namespace::PdfAId, "amd") => Text,
+        (Namespace::PdfAId, "conformance") => Text,
+        (Namespace::PdfAId, "corr") if part >= 2 => Text,
+        (Namespace::PdfAId, "rev") if part >= 4 => Integer,
+        (Namespace::PdfAExtension, "schemas") => Array(Bag),
+        _ => return None,
+    })
+}
+
+/// The value types of all properties described by the extension schemas of a
+/// packet, keyed by namespace URL and property name.
+struct ExtensionSchemas<'a> {
+    properties: HashMap<(&'a str, &'a str), &'a str>,
+}
+
+/// The value types defined by the XMP specification.
+const XMP_TYPES: &[&str] = &[
+    "Boolean",
+    "Date",
+    "Integer",
+    "Real",
+    "Text",
+    "AgentName",
+    "Choice",
+    "Closed Choice",
+    "Colorant",
+    "Dimensions",
+    "Font",
+    "GPSCoordinate",
+    "GUID",
+    "Job",
+    "Lang Alt",
+    "Locale",
+    "MIMEType",
+    "Open Choice",
+    "Part",
+    "ProperName",
+    "Rational",
+    "RenditionClass",
+    "ResourceEvent",
+    "ResourceRef",
+    "Thumbnail",
+    "URI",
+    "URL",
+    "Version",
+    "XPath",
+];
+
+impl<'a> ExtensionSchemas<'a> {
+    /// Read the extension schemas, reporting incomplete descriptions.
+    fn read(
+        packet: &'a XmpPacket,
+        rule: &'static str,
+        error: &mut impl FnMut(&'static str, &str, String),
+    ) -> Self {
+        let mut properties = HashMap::new();
+        let Some(Value::Array(_, schemas)) =
+            packet.get(&Namespace::PdfAExtension, "schemas")
+        else {
+            return Self { properties };
+        };
+
+        let container = "pdfaExtension:schemas";
+        let text = |value: &'a Value, ns: &Namespace, name: &str| {
+            value.field(ns, name).and_then(Value::as_text)
+        };
+
+        for schema in schemas {
+            for field in ["schema", "namespaceURI", "prefix"] {
+                if text(schema, &Namespace::PdfASchema, field).is_none() {
+                    error(
+                        rule,
+                        container,
+                        format!("a schema is missing pdfaSchema:{field}"),
+                    );
+                }
+            }
+
+            // Custom value types that the properties of this schema may use.
+            let mut types = vec![];
+            if let Some(Value::Array(_, value_types)) =
+                schema.field(&Namespace::PdfASchema, "valueType")
+            {
+                for ty in value_types {
+                    for field in ["type", "namespaceURI", "prefix", "description"] {
+                        if text(ty, &Namespace::PdfAType, field).is_none() {
+                            error(
+                                rule,
+                                container,
+                                format!("a value type is missing pdfaType:{field}"),
+                            );
+                        }
+                    }
+                    types.extend(text(ty, &Namespace::PdfAType, "type"));
+                }
+
+                for ty in value_types {
+                    let Some(Value::Array(_, fields)) =
+                        ty.field(&Namespace::PdfAType, "field")
+                    else {
+                        continue;
+                    };
+                    for field in fields {
+                        for name in ["name", "valueType", "description"] {
+                            if text(field, &Namespace::PdfAField, name).is_none() {
+                                error(
+                                    rule,
+                                    container,
+                                    format!("a field is missing pdfaField:{name}"),
+                                );
+                            }
+                        }
+                        if let Some(value_type) =
+                            text(field, &Namespace::PdfAField, "valueType")
+                        {
+                            check_value_type(value_type, &types, rule, container, error);
+                        }
+                    }
+                }
+            }
+
+            let url = text(schema, &Namespace::PdfASchema, "namespaceURI");
+            let Some(Value::Array(_, props)) =
+                schema.field(&Namespace::PdfASchema, "property")
+            else {
+                continue;
+            };
+
+            for property in props {
+                for field in ["name", "valueType", "category", "description"] {
+                    if text(property, &Namespace::PdfAProperty, field).is_none() {
+                        error(
+                            rule,
+                            container,
+                            format!("a property is missing pdfaProperty:{field}"),
+                        );
+                    }
+                }
+
+                if let Some(category) =
+                    text(property, &Namespace::PdfAProperty, "category")
+                {
+                    if category != "internal" && category != "external" {
+                        error(
+                            rule,
+                            container,
+                            format!("{category:?} is not a property category"),
+                        );
+                    }
+                }
+
+                let name = text(property, &Namespace::PdfAProperty, "name");
+                let value_type = text(property, &Namespace::PdfAProperty, "valueType");
+                if let Some(value_type) = value_type {
+                    check_value_type(value_type, &types, rule, container, error);
+                }
+
+                if let (Some(url), Some(name), Some(value_type)) = (url, name, value_type)
+                {
+                    properties.insert((url, name), value_type);
+                }
+            }
+        }
+
+        Self { properties }
+    }
+
+    /// The value type of a described property.
+    fn value_type(&self, url: &str, name: &str) -> Option<&'a str> {
+        self.properties.get(&(url, name)).copied()
+    }
+}
+
+/// Report value types that are neither defined by XMP nor by the schema.
+fn check_value_type(
+    value_type: &str,
+    types: &[&str],
+    rule: &'static str,
+    container: &str,
+    error: &mut impl FnMut(&'static str, &str, String),
+) {
+    let base = ["bag ", "seq ", "alt ", "Closed Choice of ", "Open Choice of "]
+        .into_iter()
+        .find_map(|prefix| value_type.strip_prefix(prefix))
+        .unwrap_or(value_type);
+
+    if !XMP_TYPES.contains(&base) && !types.contains(&base) {
+        error(rule, container, format!("the value type {value_type:?} is not defined"));
+    }
+}
+
+/// Whether the text is a date in the format of the XMP specification, e.g.
+/// `2021-11-06T12:00:00.5+01:00`.
+fn is_date(text: &str) -> bool {
+    fn digits(s: &str, n: usize) -> Option<&str> {
+        let (head, tail) = s.split_at_checked(n)?;
+        head.bytes().all(|b| b.is_ascii_digit()).then_some(tail)
+    }
+
+    fn timezone(s: &str) -> bool {
+        if s.is_empty() || s == "Z" {
+            return true;
+        }
+        let Some(rest) = s.strip_prefix(['+', '-']) else { return false };
+        digits(rest, 2)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .and_then(|rest| digits(rest, 2))
+            .is_some_and(str::is_empty)
+    }
+
+    let Some(mut rest) = digits(text, 4) else { return false };
+    for _ in 0..2 {
+        match rest.strip_prefix('-') {
+            Some(next) => match digits(next, 2) {
+                Some(next) => rest = next,
+                None => return false,
+            },
+            None => return rest.is_empty(),
+        }
+    }
+
+    let Some(time) = rest.strip_prefix('T') else { return rest.is_empty() };
+    let Some(mut rest) = digits(time, 2)
+        .and_then(|rest| rest.strip_prefix(':'))
+        .and_then(|rest| digits(rest, 2))
+    else {
+        return false;
+    };
+
+    if let Some(seconds) = rest.strip_prefix(':') {
+        let Some(next) = digits(seconds, 2) else { return false };
+        rest = next;
+        if let Some(fraction) = rest.strip_prefix('.') {
+            let len = fraction.bytes().take_while(u8::is_ascii_digit).count();
+            if len == 0 {
+                return false;
+            }
+            rest = &fraction[len..];
+        }
+    }
+
+    timezone(rest)
+}
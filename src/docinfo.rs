@@ -0,0 +1,198 @@
+use std::fmt::Write;
+
+use crate::{DateTime, LangId, LocalizedText, Timezone, Trapped, XmpMetadata, XmpWriter};
+
+/// The entries of a classic PDF document information dictionary.
+///
+/// PDF/A requires these entries to be consistent with the XMP metadata. Use
+/// [`XmpWriter::doc_info`] to write the matching properties and
+/// [`DocInfo::from_metadata`] to derive the entries from existing metadata.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DocInfo {
+    /// The `Title` entry, mapped to `dc:title`.
+    pub title: Option<String>,
+    /// The `Author` entry, mapped to `dc:creator`.
+    pub author: Option<String>,
+    /// The `Subject` entry, mapped to `dc:description`.
+    pub subject: Option<String>,
+    /// The `Keywords` entry, mapped to `pdf:Keywords`.
+    pub keywords: Option<String>,
+    /// The `Creator` entry, mapped to `xmp:CreatorTool`.
+    pub creator: Option<String>,
+    /// The `Producer` entry, mapped to `pdf:Producer`.
+    pub producer: Option<String>,
+    /// The `CreationDate` entry, mapped to `xmp:CreateDate`.
+    pub creation_date: Option<DateTime>,
+    /// The `ModDate` entry, mapped to `xmp:ModifyDate`.
+    pub mod_date: Option<DateTime>,
+    /// The `Trapped` entry, mapped to `pdf:Trapped`.
+    pub trapped: Option<Trapped>,
+}
+
+impl DocInfo {
+    /// Derive the document information entries from metadata.
+    ///
+    /// The title and subject are taken from the `x-default` item of the
+    /// language alternative, or the first item if there is none. Multiple
+    /// creators are joined with commas, as the `Author` entry holds a single
+    /// text. If there are no `pdf:Keywords`, the subjects are joined instead.
+    pub fn from_metadata(metadata: &XmpMetadata) -> Self {
+        let default_text = |items: &[crate::LocalizedText]| {
+            items
+                .iter()
+                .find(|item| item.lang.as_deref().is_none_or(|lang| lang == "x-default"))
+                .or(items.first())
+                .map(|item| item.text.clone())
+        };
+
+        let join = |items: &[String]| {
+            if items.is_empty() {
+                None
+            } else {
+                Some(items.join(", "))
+            }
+        };
+
+        Self {
+            title: default_text(&metadata.title),
+            author: join(&metadata.creators),
+            subject: default_text(&metadata.description),
+            keywords: metadata.pdf_keywords.clone().or_else(|| join(&metadata.subject)),
+            creator: metadata.creator_tool.clone(),
+            producer: metadata.producer.clone(),
+            creation_date: metadata.create_date,
+            mod_date: metadata.modify_date,
+            trapped: None,
+        }
+    }
+}
+
+/// The basic information about a document that PDF generators write.
+///
+/// Write it with [`XmpWriter::document_info`]. Unlike [`DocInfo`], which
+/// mirrors the entries of a document information dictionary, the fields
+/// hold structured values.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct DocumentInfo {
+    /// The title, possibly in multiple languages.
+    pub title: Vec<LocalizedText>,
+    /// The authors of the document.
+    pub authors: Vec<String>,
+    /// A short description of the document.
+    pub subject: Option<String>,
+    /// Keywords describing the document.
+    pub keywords: Vec<String>,
+    /// When the document was created.
+    pub create_date: Option<DateTime>,
+    /// When the document was last modified.
+    pub modify_date: Option<DateTime>,
+    /// The application that created the original document.
+    pub creator_tool: Option<String>,
+    /// The application that produced the PDF.
+    pub producer: Option<String>,
+    /// The main language of the document as an RFC 3066 tag.
+    pub language: Option<String>,
+}
+
+/// PDF document information.
+impl XmpWriter<'_> {
+    /// Write the basic information about a document.
+    ///
+    /// Writes `dc:title`, `dc:creator`, `dc:description` with a single
+    /// `x-default` item, `dc:subject` and the matching comma-separated
+    /// `pdf:Keywords`, `xmp:CreateDate`, `xmp:ModifyDate`,
+    /// `xmp:CreatorTool`, `pdf:Producer`, and `dc:language` for all fields
+    /// that are present and non-empty.
+    pub fn document_info(&mut self, info: &DocumentInfo) -> &mut Self {
+        if !info.title.is_empty() {
+            self.title(info.title.iter().map(LocalizedText::as_pair));
+        }
+        if !info.authors.is_empty() {
+            self.creator(info.authors.iter().map(String::as_str));
+        }
+        if let Some(subject) = &info.subject {
+            self.description([(None, subject.as_str())]);
+        }
+        if !info.keywords.is_empty() {
+            self.subject(&info.keywords);
+            self.pdf_keywords(&info.keywords.join(", "));
+        }
+        if let Some(date) = info.create_date {
+            self.create_date(date);
+        }
+        if let Some(date) = info.modify_date {
+            self.modify_date(date);
+        }
+        if let Some(tool) = &info.creator_tool {
+            self.creator_tool(tool);
+        }
+        if let Some(producer) = &info.producer {
+            self.producer(producer);
+        }
+        if let Some(lang) = &info.language {
+            self.language([LangId(lang)]);
+        }
+        self
+    }
+
+    /// Write the XMP properties that correspond to the entries of a PDF
+    /// document information dictionary.
+    ///
+    /// Writes `dc:title` and `dc:description` with a single `x-default` item,
+    /// `dc:creator` with a single item, `pdf:Keywords`, `xmp:CreatorTool`,
+    /// `pdf:Producer`, `xmp:CreateDate`, `xmp:ModifyDate`, and `pdf:Trapped`
+    /// for all entries that are present.
+    pub fn doc_info(&mut self, info: &DocInfo) -> &mut Self {
+        if let Some(title) = &info.title {
+            self.title([(None, title.as_str())]);
+        }
+        if let Some(author) = &info.author {
+            self.creator([author.as_str()]);
+        }
+        if let Some(subject) = &info.subject {
+            self.description([(None, subject.as_str())]);
+        }
+        if let Some(keywords) = &info.keywords {
+            self.pdf_keywords(keywords);
+        }
+        if let Some(creator) = &info.creator {
+            self.creator_tool(creator);
+        }
+        if let Some(producer) = &info.producer {
+            self.producer(producer);
+        }
+        if let Some(date) = info.creation_date {
+            self.create_date(date);
+        }
+        if let Some(date) = info.mod_date {
+            self.modify_date(date);
+        }
+        if let Some(trapped) = info.trapped {
+            self.trapped(trapped);
+        }
+        self
+    }
+}
+
+impl DateTime {
+    /// Format the date as a PDF date string, e.g. `D:20211106120000+01'00'`,
+    /// for use in a document information dictionary.
+    pub fn pdf_date(&self) -> String {
+        let mut buf = String::from("D:");
+        (|| {
+            write!(buf, "{:04}", self.year).unwrap();
+            write!(buf, "{:02}", self.month?).unwrap();
+            write!(buf, "{:02}", self.day?).unwrap();
+            write!(buf, "{:02}{:02}", self.hour?, self.minute?).unwrap();
+            write!(buf, "{:02}", self.second?).unwrap();
+            match self.timezone? {
+                Timezone::Utc => buf.push('Z'),
+                timezone => timezone.write_offset(&mut buf, "'", "'"),
+            }
+            Some(())
+        })();
+        buf
+    }
+}
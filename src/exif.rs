@@ -0,0 +1,532 @@
+//! XMP properties from parsed EXIF fields.
+
+use exif::{Field, In, Tag, Value};
+
+use crate::{DateTime, Namespace, Timezone, XmpWriter};
+
+/// TIFF tags with an integer value.
+const TIFF_INTEGERS: &[(Tag, &str)] = &[
+    (Tag::ImageWidth, "ImageWidth"),
+    (Tag::ImageLength, "ImageLength"),
+    (Tag::Compression, "Compression"),
+    (Tag::PhotometricInterpretation, "PhotometricInterpretation"),
+    (Tag::Orientation, "Orientation"),
+    (Tag::SamplesPerPixel, "SamplesPerPixel"),
+    (Tag::PlanarConfiguration, "PlanarConfiguration"),
+    (Tag::YCbCrPositioning, "YCbCrPositioning"),
+    (Tag::ResolutionUnit, "ResolutionUnit"),
+];
+
+/// TIFF tags with a rational value.
+const TIFF_RATIONALS: &[(Tag, &str)] =
+    &[(Tag::XResolution, "XResolution"), (Tag::YResolution, "YResolution")];
+
+/// TIFF tags with a text value.
+const TIFF_TEXTS: &[(Tag, &str)] = &[(Tag::Make, "Make"), (Tag::Model, "Model")];
+
+/// TIFF tags with an ordered array of integers as value.
+const TIFF_SEQUENCES: &[(Tag, &str)] =
+    &[(Tag::BitsPerSample, "BitsPerSample"), (Tag::YCbCrSubSampling, "YCbCrSubSampling")];
+
+/// EXIF tags with an integer value.
+const EXIF_INTEGERS: &[(Tag, &str)] = &[
+    (Tag::ColorSpace, "ColorSpace"),
+    (Tag::PixelXDimension, "PixelXDimension"),
+    (Tag::PixelYDimension, "PixelYDimension"),
+    (Tag::ExposureProgram, "ExposureProgram"),
+    (Tag::MeteringMode, "MeteringMode"),
+    (Tag::LightSource, "LightSource"),
+    (Tag::FocalPlaneResolutionUnit, "FocalPlaneResolutionUnit"),
+    (Tag::SensingMethod, "SensingMethod"),
+    (Tag::CustomRendered, "CustomRendered"),
+    (Tag::ExposureMode, "ExposureMode"),
+    (Tag::WhiteBalance, "WhiteBalance"),
+    (Tag::FocalLengthIn35mmFilm, "FocalLengthIn35mmFilm"),
+    (Tag::SceneCaptureType, "SceneCaptureType"),
+    (Tag::GainControl, "GainControl"),
+    (Tag::Contrast, "Contrast"),
+    (Tag::Saturation, "Saturation"),
+    (Tag::Sharpness, "Sharpness"),
+    (Tag::SubjectDistanceRange, "SubjectDistanceRange"),
+    (Tag::GPSAltitudeRef, "GPSAltitudeRef"),
+    (Tag::GPSDifferential, "GPSDifferential"),
+];
+
+/// EXIF tags with a rational value.
+const EXIF_RATIONALS: &[(Tag, &str)] = &[
+    (Tag::CompressedBitsPerPixel, "CompressedBitsPerPixel"),
+    (Tag::ExposureTime, "ExposureTime"),
+    (Tag::FNumber, "FNumber"),
+    (Tag::ShutterSpeedValue, "ShutterSpeedValue"),
+    (Tag::ApertureValue, "ApertureValue"),
+    (Tag::BrightnessValue, "BrightnessValue"),
+    (Tag::ExposureBiasValue, "ExposureBiasValue"),
+    (Tag::MaxApertureValue, "MaxApertureValue"),
+    (Tag::SubjectDistance, "SubjectDistance"),
+    (Tag::FocalLength, "FocalLength"),
+    (Tag::FlashEnergy, "FlashEnergy"),
+    (Tag::FocalPlaneXResolution, "FocalPlaneXResolution"),
+    (Tag::FocalPlaneYResolution, "FocalPlaneYResolution"),
+    (Tag::ExposureIndex, "ExposureIndex"),
+    (Tag::DigitalZoomRatio, "DigitalZoomRatio"),
+    (Tag::GPSAltitude, "GPSAltitude"),
+    (Tag::GPSDOP, "GPSDOP"),
+    (Tag::GPSSpeed, "GPSSpeed"),
+    (Tag::GPSTrack, "GPSTrack"),
+    (Tag::GPSImgDirection, "GPSImgDirection"),
+];
+
+/// EXIF tags with a text value.
+const EXIF_TEXTS: &[(Tag, &str)] = &[
+    (Tag::SpectralSensitivity, "SpectralSensitivity"),
+    (Tag::RelatedSoundFile, "RelatedSoundFile"),
+    (Tag::ImageUniqueID, "ImageUniqueID"),
+    (Tag::GPSSatellites, "GPSSatellites"),
+    (Tag::GPSStatus, "GPSStatus"),
+    (Tag::GPSMeasureMode, "GPSMeasureMode"),
+    (Tag::GPSSpeedRef, "GPSSpeedRef"),
+    (Tag::GPSTrackRef, "GPSTrackRef"),
+    (Tag::GPSImgDirectionRef, "GPSImgDirectionRef"),
+    (Tag::GPSMapDatum, "GPSMapDatum"),
+];
+
+/// EXIF tags with an ordered array of integers as value.
+const EXIF_SEQUENCES: &[(Tag, &str)] = &[
+    (Tag::PhotographicSensitivity, "ISOSpeedRatings"),
+    (Tag::SubjectArea, "SubjectArea"),
+    (Tag::SubjectLocation, "SubjectLocation"),
+];
+
+/// EXIF tags with a version that is stored as four characters.
+const EXIF_VERSIONS: &[(Tag, &str)] =
+    &[(Tag::ExifVersion, "ExifVersion"), (Tag::FlashpixVersion, "FlashpixVersion")];
+
+/// EXIF dates with the tags of their fractional seconds and their offsets.
+const EXIF_DATES: &[(Tag, Tag, Tag, &str)] = &[
+    (
+        Tag::DateTimeOriginal,
+        Tag::SubSecTimeOriginal,
+        Tag::OffsetTimeOriginal,
+        "DateTimeOriginal",
+    ),
+    (
+        Tag::DateTimeDigitized,
+        Tag::SubSecTimeDigitized,
+        Tag::OffsetTimeDigitized,
+        "DateTimeDigitized",
+    ),
+];
+
+/// XMP properties from EXIF data.
+///
+/// Enabled by the `kamadak-exif` feature.
+impl XmpWriter<'_> {
+    /// Write the XMP properties that correspond to the EXIF fields of the
+    /// primary image, as parsed by the `kamadak-exif` crate.
+    ///
+    /// Writes the `tiff:` and `exif:` properties of the EXIF mapping in the
+    /// XMP specification, including GPS coordinates and the `exif:Flash`
+    /// struct. Properties that were already written, including those that XMP
+    /// maps TIFF fields onto, like `Artist` onto `dc:creator` and `DateTime`
+    /// onto `xmp:ModifyDate`, are kept. Fields of the thumbnail and fields
+    /// without an XMP counterpart are skipped.
+    pub fn exif<'f>(&mut self, fields: impl IntoIterator<Item = &'f Field>) -> &mut Self {
+        let fields: Vec<&Field> = fields
+            .into_iter()
+            .filter(|field| field.ifd_num == In::PRIMARY)
+            .collect();
+        let get = |tag: Tag| {
+            fields.iter().find(|field| field.tag == tag).map(|field| &field.value)
+        };
+
+        for &(tag, name) in TIFF_INTEGERS {
+            if self.has_property(Namespace::Tiff, name) {
+                continue;
+            }
+            if let Some(value) = get(tag).and_then(integer) {
+                self.element(name, Namespace::Tiff).value(value);
+            }
+        }
+        for &(tag, name) in TIFF_RATIONALS {
+            if self.has_property(Namespace::Tiff, name) {
+                continue;
+            }
+            if let Some(value) = get(tag).and_then(rational) {
+                self.element(name, Namespace::Tiff).value(value.as_str());
+            }
+        }
+        for &(tag, name) in TIFF_TEXTS {
+            if self.has_property(Namespace::Tiff, name) {
+                continue;
+            }
+            if let Some(value) = get(tag).and_then(text) {
+                self.element(name, Namespace::Tiff).value(value.as_str());
+            }
+        }
+        for &(tag, name) in TIFF_SEQUENCES {
+            if self.has_property(Namespace::Tiff, name) {
+                continue;
+            }
+            if let Some(values) = get(tag).map(integers).filter(|v| !v.is_empty()) {
+                self.element(name, Namespace::Tiff).ordered_array(values);
+            }
+        }
+
+        if let Some(artist) = get(Tag::Artist).and_then(text) {
+            if !self.has_property(Namespace::DublinCore, "creator") {
+                self.creator([artist.as_str()]);
+            }
+        }
+        if let Some(description) = get(Tag::ImageDescription).and_then(text) {
+            if !self.has_property(Namespace::DublinCore, "description") {
+                self.description([(None, description.as_str())]);
+            }
+        }
+        if let Some(copyright) = get(Tag::Copyright).and_then(text) {
+            if !self.has_property(Namespace::DublinCore, "rights") {
+                self.rights([(None, copyright.as_str())]);
+            }
+        }
+        if let Some(software) = get(Tag::Software).and_then(text) {
+            if !self.has_property(Namespace::Xmp, "CreatorTool") {
+                self.creator_tool(software.as_str());
+            }
+        }
+        if let Some(date) = get(Tag::DateTime)
+            .and_then(|value| date(value, get(Tag::SubSecTime), get(Tag::OffsetTime)))
+        {
+            if !self.has_property(Namespace::Xmp, "ModifyDate") {
+                self.modify_date(date);
+            }
+        }
+
+        for &(tag, name) in EXIF_INTEGERS {
+            if self.has_property(Namespace::Exif, name) {
+                continue;
+            }
+            if let Some(value) = get(tag).and_then(integer) {
+                self.element(name, Namespace::Exif).value(value);
+            }
+        }
+        for &(tag, name) in EXIF_RATIONALS {
+            if self.has_property(Namespace::Exif, name) {
+                continue;
+            }
+            if let Some(value) = get(tag).and_then(rational) {
+                self.element(name, Namespace::Exif).value(value.as_str());
+            }
+        }
+        for &(tag, name) in EXIF_TEXTS {
+            if self.has_property(Namespace::Exif, name) {
+                continue;
+            }
+            if let Some(value) = get(tag).and_then(text) {
+                self.element(name, Namespace::Exif).value(value.as_str());
+            }
+        }
+        for &(tag, name) in EXIF_SEQUENCES {
+            if self.has_property(Namespace::Exif, name) {
+                continue;
+            }
+            if let Some(values) = get(tag).map(integers).filter(|v| !v.is_empty()) {
+                self.element(name, Namespace::Exif).ordered_array(values);
+            }
+        }
+        for &(tag, name) in EXIF_VERSIONS {
+            if self.has_property(Namespace::Exif, name) {
+                continue;
+            }
+            if let Some(Value::Undefined(bytes, _)) = get(tag) {
+                self.element(name, Namespace::Exif)
+                    .value(&*String::from_utf8_lossy(bytes));
+            }
+        }
+        for &(tag, subsec, offset, name) in EXIF_DATES {
+            if self.has_property(Namespace::Exif, name) {
+                continue;
+            }
+            if let Some(date) =
+                get(tag).and_then(|value| date(value, get(subsec), get(offset)))
+            {
+                self.element(name, Namespace::Exif).value(date);
+            }
+        }
+
+        if let Some(flash) = get(Tag::Flash)
+            .and_then(integer)
+            .filter(|_| !self.has_property(Namespace::Exif, "Flash"))
+        {
+            self.element("Flash", Namespace::Exif)
+                .obj()
+                .field("Fired", Namespace::Exif, flash & 1 != 0)
+                .field("Return", Namespace::Exif, (flash >> 1) & 3)
+                .field("Mode", Namespace::Exif, (flash >> 3) & 3)
+                .field("Function", Namespace::Exif, flash & 0x20 != 0)
+                .field("RedEyeMode", Namespace::Exif, flash & 0x40 != 0);
+        }
+
+        if let Some(comment) = get(Tag::UserComment)
+            .and_then(user_comment)
+            .filter(|_| !self.has_property(Namespace::Exif, "UserComment"))
+        {
+            self.element("UserComment", Namespace::Exif)
+                .language_alternative([(None, comment.as_str())]);
+        }
+
+        if let Some(Value::Byte(version)) = get(Tag::GPSVersionID)
+            .filter(|_| !self.has_property(Namespace::Exif, "GPSVersionID"))
+        {
+            let version: Vec<_> = version.iter().map(u8::to_string).collect();
+            self.element("GPSVersionID", Namespace::Exif)
+                .value(version.join(".").as_str());
+        }
+        for (tag, reference, name) in [
+            (Tag::GPSLatitude, Tag::GPSLatitudeRef, "GPSLatitude"),
+            (Tag::GPSLongitude, Tag::GPSLongitudeRef, "GPSLongitude"),
+            (Tag::GPSDestLatitude, Tag::GPSDestLatitudeRef, "GPSDestLatitude"),
+            (Tag::GPSDestLongitude, Tag::GPSDestLongitudeRef, "GPSDestLongitude"),
+        ] {
+            if self.has_property(Namespace::Exif, name) {
+                continue;
+            }
+            if let Some(coordinate) = get(tag).and_then(|value| {
+                coordinate(value, get(reference).and_then(text).as_deref())
+            }) {
+                self.element(name, Namespace::Exif).value(coordinate.as_str());
+            }
+        }
+        if let Some(timestamp) = get(Tag::GPSTimeStamp)
+            .and_then(|time| gps_timestamp(get(Tag::GPSDateStamp)?, time))
+            .filter(|_| !self.has_property(Namespace::Exif, "GPSTimeStamp"))
+        {
+            self.element("GPSTimeStamp", Namespace::Exif).value(timestamp);
+        }
+
+        self
+    }
+}
+
+/// The first value of an integer field.
+fn integer(value: &Value) -> Option<i64> {
+    integers(value).first().copied()
+}
+
+/// The values of an integer field.
+fn integers(value: &Value) -> Vec<i64> {
+    match value {
+        Value::Byte(v) => v.iter().map(|&n| n.into()).collect(),
+        Value::Short(v) => v.iter().map(|&n| n.into()).collect(),
+        Value::Long(v) => v.iter().map(|&n| n.into()).collect(),
+        Value::SByte(v) => v.iter().map(|&n| n.into()).collect(),
+        Value::SShort(v) => v.iter().map(|&n| n.into()).collect(),
+        Value::SLong(v) => v.iter().map(|&n| n.into()).collect(),
+        _ => vec![],
+    }
+}
+
+/// The first value of a rational field in the XMP form `num/denom`.
+fn rational(value: &Value) -> Option<String> {
+    match value {
+        Value::Rational(v) => v.first().map(|r| format!("{}/{}", r.num, r.denom)),
+        Value::SRational(v) => v.first().map(|r| format!("{}/{}", r.num, r.denom)),
+        _ => None,
+    }
+}
+
+/// The text of an ASCII field, without padding.
+fn text(value: &Value) -> Option<String> {
+    let Value::Ascii(v) = value else { return None };
+    let text = String::from_utf8_lossy(v.first()?);
+    let text = text.trim_end_matches(['\0', ' ']);
+    (!text.is_empty()).then(|| text.into())
+}
+
+/// The text of a `UserComment` field, which starts with its character code.
+fn user_comment(value: &Value) -> Option<String> {
+    let Value::Undefined(bytes, _) = value else { return None };
+    let (code, data) = bytes.split_at_checked(8)?;
+    let text = match code {
+        b"UNICODE\0" => {
+            let units: Vec<u16> = data
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(data).into_owned(),
+    };
+    let text = text.trim_end_matches(['\0', ' ']);
+    (!text.is_empty()).then(|| text.into())
+}
+
+/// A date in the EXIF form `YYYY:MM:DD HH:MM:SS` with optional fractional
+/// seconds and offset.
+fn date(
+    value: &Value,
+    subsec: Option<&Value>,
+    offset: Option<&Value>,
+) -> Option<DateTime> {
+    let stamp = text(value)?;
+    let part =
+        |range: std::ops::Range<usize>| stamp.get(range)?.trim().parse::<u8>().ok();
+    let year = stamp.get(0..4)?.parse().ok()?;
+    let mut date = DateTime::local_time(
+        year,
+        part(5..7)?,
+        part(8..10)?,
+        part(11..13)?,
+        part(14..16)?,
+        part(17..19)?,
+    );
+    if date.year == 0 {
+        return None;
+    }
+
+    if let Some(digits) = subsec
+        .and_then(text)
+        .filter(|d| d.bytes().all(|b| b.is_ascii_digit()))
+    {
+        let digits = &digits[..digits.len().min(9)];
+        date.nanosecond =
+            Some(digits.parse::<u32>().ok()? * 10u32.pow(9 - digits.len() as u32));
+    }
+
+    if let Some(offset) = offset.and_then(text) {
+        let sign = match offset.get(..1)? {
+            "+" => 1,
+            "-" => -1,
+            _ => return Some(date),
+        };
+        let hours: i16 = offset.get(1..3)?.parse().ok()?;
+        let minutes: i16 = offset.get(4..6)?.parse().ok()?;
+        date.timezone = Timezone::from_offset_minutes(sign * (hours * 60 + minutes)).ok();
+    }
+
+    Some(date)
+}
+
+/// A GPS coordinate in the XMP form `DDD,MM.mmmmmmR` from degrees, minutes,
+/// and seconds and the direction reference.
+fn coordinate(value: &Value, reference: Option<&str>) -> Option<String> {
+    let Value::Rational(parts) = value else { return None };
+    let [degrees, minutes, seconds] = parts.as_slice() else { return None };
+    let reference = reference?.chars().next()?;
+    let total = degrees.to_f64() + minutes.to_f64() / 60.0 + seconds.to_f64() / 3600.0;
+    if !total.is_finite() {
+        return None;
+    }
+
+    let degrees = total.trunc();
+    let minutes = format!("{:.6}", (total - degrees) * 60.0);
+    let minutes = minutes.trim_end_matches('0').trim_end_matches('.');
+    Some(format!("{degrees},{minutes}{reference}"))
+}
+
+/// The UTC date and time of a GPS fix from the date in the form
+/// `YYYY:MM:DD` and the hours, minutes, and seconds.
+fn gps_timestamp(date: &Value, time: &Value) -> Option<DateTime> {
+    let date = text(date)?;
+    let part = |range: std::ops::Range<usize>| date.get(range)?.parse::<u8>().ok();
+    let Value::Rational(time) = time else { return None };
+    let [hour, minute, second] = time.as_slice() else { return None };
+    let second = second.to_f64();
+    if !second.is_finite() || second < 0.0 {
+        return None;
+    }
+
+    let mut timestamp = DateTime::new(
+        date.get(0..4)?.parse().ok()?,
+        part(5..7)?,
+        part(8..10)?,
+        hour.to_f64() as u8,
+        minute.to_f64() as u8,
+        second.trunc() as u8,
+        Timezone::Utc,
+    );
+    let nanosecond = (second.fract() * 1e9).round() as u32;
+    if nanosecond > 0 {
+        timestamp.nanosecond = Some(nanosecond);
+    }
+    Some(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use exif::{Field, In, Rational, Tag, Value};
+
+    use crate::{Namespace, XmpPacket, XmpWriter};
+
+    fn field(tag: Tag, value: Value) -> Field {
+        Field { tag, ifd_num: In::PRIMARY, value }
+    }
+
+    fn ascii(text: &str) -> Value {
+        Value::Ascii(vec![text.as_bytes().to_vec()])
+    }
+
+    fn rationals(parts: &[(u32, u32)]) -> Value {
+        Value::Rational(
+            parts.iter().map(|&(num, denom)| Rational { num, denom }).collect(),
+        )
+    }
+
+    fn fields() -> Vec<Field> {
+        vec![
+            field(Tag::Make, ascii("Camera Co\0")),
+            field(Tag::Orientation, Value::Short(vec![6])),
+            field(Tag::XResolution, rationals(&[(72, 1)])),
+            field(Tag::Artist, ascii("Jane Doe")),
+            field(Tag::DateTimeOriginal, ascii("2024:05:06 07:08:09")),
+            field(Tag::SubSecTimeOriginal, ascii("25")),
+            field(Tag::OffsetTimeOriginal, ascii("-00:30")),
+            field(Tag::Flash, Value::Short(vec![0x19])),
+            field(Tag::GPSLatitude, rationals(&[(52, 1), (30, 1), (36, 1)])),
+            field(Tag::GPSLatitudeRef, ascii("N")),
+            Field {
+                tag: Tag::Model,
+                ifd_num: In::THUMBNAIL,
+                value: ascii("Thumbnail"),
+            },
+        ]
+    }
+
+    #[test]
+    fn exif_maps_fields() {
+        let mut writer = XmpWriter::new();
+        writer.exif(&fields());
+        assert_eq!(writer.errors(), []);
+
+        let packet = writer.finish(None);
+        assert!(packet.contains("<tiff:Make>Camera Co</tiff:Make>"));
+        assert!(packet.contains("<tiff:Orientation>6</tiff:Orientation>"));
+        assert!(packet.contains("<tiff:XResolution>72/1</tiff:XResolution>"));
+        assert!(packet.contains(
+            "<exif:DateTimeOriginal>2024-05-06T07:08:09.25-00:30</exif:DateTimeOriginal>"
+        ));
+        assert!(packet.contains("<exif:GPSLatitude>52,30.6N</exif:GPSLatitude>"));
+        assert!(!packet.contains("Thumbnail"));
+
+        let parsed = XmpPacket::parse(&packet).unwrap();
+        assert!(parsed.get(&Namespace::DublinCore, "creator").is_some());
+        let flash = parsed.get(&Namespace::Exif, "Flash").unwrap();
+        assert!(flash.field(&Namespace::Exif, "Fired").is_some());
+        assert!(flash.field(&Namespace::Exif, "Mode").is_some());
+    }
+
+    #[test]
+    fn exif_keeps_written_properties() {
+        let mut writer = XmpWriter::new();
+        writer.creator(["John Doe"]);
+        writer.element("Make", Namespace::Tiff).value("Other Co");
+        writer.exif(&fields());
+        writer.exif(&fields());
+        assert_eq!(writer.errors(), []);
+
+        let packet = writer.finish(None);
+        assert!(packet.contains("Other Co"));
+        assert!(!packet.contains("Camera Co"));
+        assert!(!packet.contains("Jane Doe"));
+        assert_eq!(packet.matches("<exif:Flash").count(), 1);
+        assert_eq!(packet.matches("<tiff:Orientation>").count(), 1);
+    }
+}
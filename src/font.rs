@@ -0,0 +1,66 @@
+//! Font descriptions from parsed font faces.
+
+use ttf_parser::name_id;
+use ttf_parser::{Face, GlyphId};
+
+use crate::{FontType, FontWriter};
+
+/// Font descriptions from font faces.
+///
+/// Enabled by the `ttf-parser` feature.
+impl FontWriter<'_, '_> {
+    /// Write the properties of a font that can be read from its tables.
+    ///
+    /// Writes `stFnt:fontName`, `stFnt:fontFamily`, `stFnt:fontFace`,
+    /// `stFnt:fontType`, `stFnt:versionString`, `stFnt:composite`, and
+    /// `stFnt:fontFileName`. The typographic family and subfamily names are
+    /// preferred over the legacy ones, and US English names over others.
+    /// Fonts with CFF outlines are OpenType fonts and composite if they are
+    /// CID-keyed, fonts with TrueType outlines are TrueType fonts.
+    pub fn from_face(&mut self, face: &Face, file_name: &str) -> &mut Self {
+        if let Some(name) = name(face, name_id::POST_SCRIPT_NAME) {
+            self.font_name(&name);
+        }
+        if let Some(family) = name(face, name_id::TYPOGRAPHIC_FAMILY)
+            .or_else(|| name(face, name_id::FAMILY))
+        {
+            self.font_family(&family);
+        }
+        if let Some(subfamily) = name(face, name_id::TYPOGRAPHIC_SUBFAMILY)
+            .or_else(|| name(face, name_id::SUBFAMILY))
+        {
+            self.font_face(&subfamily);
+        }
+
+        let tables = face.tables();
+        let font_type = if tables.cff.is_some() || tables.cff2.is_some() {
+            FontType::OpenType
+        } else {
+            FontType::TrueType
+        };
+        self.font_type(font_type);
+
+        if let Some(version) = name(face, name_id::VERSION) {
+            self.version_string(&version);
+        }
+
+        let cid_keyed = tables.cff.is_some_and(|cff| cff.glyph_cid(GlyphId(0)).is_some());
+        self.composite(cid_keyed);
+        self.font_file(file_name);
+        self
+    }
+}
+
+/// The name with the ID, preferring the US English one.
+fn name(face: &Face, id: u16) -> Option<String> {
+    let names = face.names().into_iter().filter(|name| name.name_id == id);
+    let mut fallback = None;
+    for name in names {
+        let Some(text) = name.to_string() else { continue };
+        if name.language_id == 0x0409 {
+            return Some(text);
+        }
+        fallback.get_or_insert(text);
+    }
+    fallback
+}
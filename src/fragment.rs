@@ -0,0 +1,279 @@
+//! Metadata that is built separately and merged into a writer.
+
+use std::collections::btree_map::Entry;
+use std::ops::{Deref, DerefMut};
+
+use crate::{types, XmpError, XmpWriter, HEADER_CAPACITY};
+
+/// Properties that are written independently of an [`XmpWriter`] and
+/// appended to it later with [`XmpWriter::append`].
+///
+/// A fragment records its elements together with the namespaces they use, so
+/// independent parts of an application, e.g. for fonts, colorants, and the
+/// history, can build their metadata on separate threads. It dereferences to
+/// an [`XmpWriter`], so all of its methods can be used to write properties.
+///
+/// ```
+/// use xmp_writer::{XmpFragment, XmpWriter};
+///
+/// let fragment = std::thread::spawn(|| {
+///     let mut fragment = XmpFragment::new();
+///     fragment.creator_tool("xmp-writer");
+///     fragment
+/// });
+///
+/// let mut writer = XmpWriter::new();
+/// writer.title([(None, "Title")]);
+/// writer.append(fragment.join().unwrap());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct XmpFragment<'n> {
+    writer: XmpWriter<'n>,
+}
+
+impl<'n> XmpFragment<'n> {
+    /// Create a new, empty fragment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<'n> Deref for XmpFragment<'n> {
+    type Target = XmpWriter<'n>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.writer
+    }
+}
+
+impl DerefMut for XmpFragment<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.writer
+    }
+}
+
+impl<'n> XmpWriter<'n> {
+    /// Append the properties of a fragment to the metadata.
+    ///
+    /// The namespaces, prefixes, and registered namespaces of the fragment are
+    /// adopted by the writer, as are the errors and missing struct fields it
+    /// recorded and the checks enabled on it, e.g. with
+    /// [`XmpWriter::pdfx`]. A fragment that uses a different prefix
+    /// for a namespace than the writer is recorded as
+    /// [`XmpError::PrefixConflict`]. Properties that were already written are
+    /// treated as if they were written twice.
+    pub fn append(&mut self, fragment: XmpFragment<'n>) -> &mut Self {
+        let mut other = fragment.writer;
+        other.close_open_tag();
+        self.close_open_tag();
+
+        self.adopt_namespaces(&other);
+
+        let offset = self.buf.len();
+        for (key, start) in std::mem::take(&mut other.properties) {
+            match self.properties.entry(key) {
+                Entry::Vacant(entry) => {
+                    entry.insert(offset + start - HEADER_CAPACITY);
+                }
+                Entry::Occupied(entry) => {
+                    let (namespace, name) = entry.key();
+                    let prefix = types::prefix_of(&self.prefixes, namespace);
+                    self.duplicates.push(format!("{prefix}:{name}"));
+                }
+            }
+        }
+
+        self.buf.push_str(other.buf.body());
+        self.missing_fields.append(&mut other.missing_fields);
+        self.duplicates.append(&mut other.duplicates);
+        self.errors.append(&mut other.errors);
+        self.pdfx |= other.pdfx;
+        self.pdfua |= other.pdfua;
+        #[cfg(feature = "pdfa")]
+        {
+            self.pdfa_part = self.pdfa_part.or(other.pdfa_part);
+            self.pdfa_id = self.pdfa_id.or(other.pdfa_id);
+        }
+        if self.about.is_none() {
+            self.about = other.about;
+        }
+        self
+    }
+
+    /// Adopt the namespaces, prefixes, and registered namespaces of another
+    /// writer.
+    ///
+    /// Each namespace that the other writer uses must end up with the same
+    /// prefix in both writers. A prefix of the other writer is adopted if the
+    /// namespace is neither used nor bound to a prefix in this writer yet.
+    /// Otherwise, differing prefixes are recorded as
+    /// [`XmpError::PrefixConflict`] and prefixes that are bound to another
+    /// namespace as [`XmpError::DuplicatePrefix`].
+    pub(crate) fn adopt_namespaces(&mut self, other: &XmpWriter<'n>) {
+        let mut namespaces: Vec<_> = other
+            .prefixes
+            .keys()
+            .chain(other.namespaces.iter())
+            .chain(other.properties.keys().map(|(ns, _)| ns))
+            .collect();
+        namespaces.sort();
+        namespaces.dedup();
+
+        for namespace in namespaces {
+            // Prefixes of namespaces that the other writer does not use
+            // are adopted if possible, but cannot conflict.
+            let used = other.uses_namespace(namespace);
+            let prefix = types::prefix_of(&other.prefixes, namespace);
+            let ours = types::prefix_of(&self.prefixes, namespace);
+            if ours != prefix
+                && (self.uses_namespace(namespace)
+                    || self.prefixes.contains_key(namespace))
+            {
+                if used {
+                    self.errors.push(XmpError::PrefixConflict(namespace.url().into()));
+                }
+            } else if self.prefix_owner(prefix, namespace).is_some() {
+                if used {
+                    self.errors.push(XmpError::DuplicatePrefix(prefix.into()));
+                }
+            } else if ours != prefix {
+                self.prefixes.insert(namespace.clone(), prefix.into());
+            }
+        }
+
+        for namespace in other.namespaces.iter() {
+            self.namespaces.insert(namespace);
+        }
+
+        for namespace in &other.registered {
+            if !self.registered.contains(namespace) {
+                self.registered.push(namespace.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Namespace, XmpError, XmpFragment, XmpPacket, XmpWriter};
+
+    #[test]
+    fn append_adopts_prefixes() {
+        let mut fragment = XmpFragment::new();
+        fragment.set_prefix(Namespace::Xmp, "xap");
+        fragment.creator_tool("xmp-writer");
+
+        let mut writer = XmpWriter::new();
+        writer.pdfua(1);
+        writer.append(fragment);
+        assert_eq!(writer.errors(), []);
+
+        let packet = writer.finish(None);
+        assert!(packet.contains("<xap:CreatorTool>"));
+        let parsed = XmpPacket::parse(&packet).unwrap();
+        assert!(parsed.get(&Namespace::Xmp, "CreatorTool").is_some());
+    }
+
+    #[test]
+    fn append_records_duplicates() {
+        let mut fragment = XmpFragment::new();
+        fragment.creator_tool("fragment");
+
+        let mut writer = XmpWriter::new();
+        writer.creator_tool("writer");
+        writer.append(fragment);
+        assert_eq!(writer.duplicates(), ["xmp:CreatorTool"]);
+        assert!(writer.finish_with(&Default::default()).is_ok());
+
+        let mut fragment = XmpFragment::new();
+        fragment.creator_tool("fragment");
+
+        let mut writer = XmpWriter::new();
+        writer.set_write_once(true);
+        writer.creator_tool("writer");
+        writer.creator_tool("again");
+        writer.append(fragment);
+        assert_eq!(writer.duplicates(), ["xmp:CreatorTool", "xmp:CreatorTool"]);
+        assert!(matches!(
+            writer.finish_with(&Default::default()),
+            Err(crate::FinishError::DuplicateProperties(_))
+        ));
+    }
+
+    #[test]
+    fn append_reports_conflicting_prefixes() {
+        let mut fragment = XmpFragment::new();
+        fragment.set_prefix(Namespace::Xmp, "xap");
+        fragment.creator_tool("xmp-writer");
+
+        let mut writer = XmpWriter::new();
+        writer.create_date(crate::DateTime::year(2024));
+        writer.append(fragment);
+        assert_eq!(
+            writer.errors(),
+            [XmpError::PrefixConflict(Namespace::Xmp.url().into())],
+        );
+    }
+
+    #[test]
+    fn append_reports_taken_prefixes() {
+        let mut fragment = XmpFragment::new();
+        fragment.set_prefix(Namespace::Xmp, "dc");
+        fragment.creator_tool("xmp-writer");
+
+        let mut writer = XmpWriter::new();
+        writer.title([(None, "Title")]);
+        writer.append(fragment);
+        assert_eq!(writer.errors(), [XmpError::DuplicatePrefix("dc".into())]);
+    }
+
+    #[test]
+    fn append_keeps_checks() {
+        let mut fragment = XmpFragment::new();
+        fragment.pdfx(crate::PdfXVersion::X3_2003);
+        fragment.set_about("uuid:fragment");
+
+        let mut writer = XmpWriter::new();
+        writer.append(fragment);
+        assert!(writer.pdfx);
+        assert_eq!(writer.about.as_deref(), Some("uuid:fragment"));
+    }
+
+    #[test]
+    fn include_adopts_prefixes() {
+        let mut other = XmpWriter::new();
+        other.set_prefix(Namespace::Xmp, "xap");
+        other.creator_tool("xmp-writer");
+
+        let mut writer = XmpWriter::new();
+        writer.pantry().add_item().instance_id("uuid:item").include(&other);
+        assert_eq!(writer.errors(), []);
+
+        let packet = writer.finish(None);
+        let parsed = XmpPacket::parse(&packet).unwrap();
+        let Some(crate::Value::Array(_, items)) =
+            parsed.get(&Namespace::XmpMedia, "Pantry")
+        else {
+            panic!("missing pantry in {packet}");
+        };
+        let tool = items[0].field(&Namespace::Xmp, "CreatorTool");
+        assert_eq!(tool.and_then(|v| v.as_text()), Some("xmp-writer"));
+    }
+
+    #[test]
+    fn include_reports_conflicting_prefixes() {
+        let mut other = XmpWriter::new();
+        other.set_prefix(Namespace::Xmp, "xap");
+        other.creator_tool("xmp-writer");
+
+        let mut writer = XmpWriter::new();
+        writer.creator_tool("xmp-writer");
+        writer.pantry().add_item().instance_id("uuid:item").include(&other);
+        assert_eq!(
+            writer.errors(),
+            [XmpError::PrefixConflict(Namespace::Xmp.url().into())],
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! Generation of document and instance identifiers.
+
+use crate::XmpWriter;
+
+/// Identifier generation.
+///
+/// Enabled by the `uuid` feature.
+impl XmpWriter<'_> {
+    /// Generate a random UUID and write it as the `xmpMM:DocumentID`
+    /// property.
+    ///
+    /// Returns the written identifier, e.g. to reference it in a
+    /// [`ResourceRefWriter`](crate::ResourceRefWriter).
+    pub fn generate_document_id(&mut self, format: IdFormat) -> String {
+        let id = format.generate("xmp.did:");
+        self.document_id(&id);
+        id
+    }
+
+    /// Generate a random UUID and write it as the `xmpMM:InstanceID`
+    /// property.
+    ///
+    /// Returns the written identifier, e.g. to reference it in a
+    /// [`ResourceEventWriter`](crate::ResourceEventWriter).
+    pub fn generate_instance_id(&mut self, format: IdFormat) -> String {
+        let id = format.generate("xmp.iid:");
+        self.instance_id(&id);
+        id
+    }
+
+    /// Generate a random `uuid:` URI and use it as the `rdf:about` value of
+    /// the description. See [`XmpWriter::set_about`].
+    ///
+    /// Returns the generated URI, e.g. to also write it as the
+    /// `xmpMM:DocumentID` property, as some asset management tools expect.
+    pub fn generate_about(&mut self) -> String {
+        let about = IdFormat::Uuid.generate("");
+        self.set_about(about.clone());
+        about
+    }
+}
+
+/// How a generated identifier is formatted.
+///
+/// Both forms contain a random (version 4) UUID as specified by RFC 4122.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum IdFormat {
+    /// The form used by Adobe applications, e.g.
+    /// `xmp.did:3b2f5a1c-8d4e-4f6a-9b7c-2e1d0f3a4b5c` for document
+    /// identifiers and `xmp.iid:…` for instance identifiers.
+    #[default]
+    Xmp,
+    /// A UUID URN, e.g. `uuid:3b2f5a1c-8d4e-4f6a-9b7c-2e1d0f3a4b5c`.
+    Uuid,
+}
+
+impl IdFormat {
+    /// Generate an identifier, using the prefix for the `Xmp` form.
+    fn generate(self, prefix: &str) -> String {
+        let uuid = uuid::Uuid::new_v4().hyphenated();
+        match self {
+            Self::Xmp => format!("{prefix}{uuid}"),
+            Self::Uuid => format!("uuid:{uuid}"),
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Conversions from the types of other date and time crates.
+//!
+//! Years outside of the range 0 to 9999 result in a date that fails
+//! [`DateTime::validate`].
+
+use crate::{DateTime, Timezone};
+
+/// Convert a year of another crate, which may be negative or too large.
+fn year(year: i32) -> u16 {
+    u16::try_from(year).unwrap_or(u16::MAX)
+}
+
+/// Convert a timezone offset in seconds east of UTC.
+fn offset(seconds: i32) -> Timezone {
+    Timezone::from_minutes((seconds / 60) as i16)
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use chrono::{Datelike, Offset, TimeZone, Timelike};
+
+    use super::*;
+
+    impl From<chrono::NaiveDate> for DateTime {
+        fn from(date: chrono::NaiveDate) -> Self {
+            DateTime::date(year(date.year()), date.month() as u8, date.day() as u8)
+        }
+    }
+
+    impl From<chrono::NaiveDateTime> for DateTime {
+        fn from(datetime: chrono::NaiveDateTime) -> Self {
+            DateTime {
+                // Leap seconds are represented as a nanosecond beyond one
+                // second.
+                nanosecond: Some(datetime.nanosecond() % 1_000_000_000),
+                ..DateTime::local_time(
+                    year(datetime.year()),
+                    datetime.month() as u8,
+                    datetime.day() as u8,
+                    datetime.hour() as u8,
+                    datetime.minute() as u8,
+                    datetime.second() as u8,
+                )
+            }
+        }
+    }
+
+    /// Converts the date and time in its timezone. A zero offset, like that of
+    /// [`chrono::Utc`], becomes [`Timezone::Utc`].
+    impl<Tz: TimeZone> From<chrono::DateTime<Tz>> for DateTime {
+        fn from(datetime: chrono::DateTime<Tz>) -> Self {
+            let timezone = offset(datetime.offset().fix().local_minus_utc());
+            DateTime {
+                timezone: Some(timezone),
+                ..datetime.naive_local().into()
+            }
+        }
+    }
+}
+
+#[cfg(feature = "jiff")]
+mod jiff_impls {
+    use super::*;
+
+    impl From<jiff::civil::Date> for DateTime {
+        fn from(date: jiff::civil::Date) -> Self {
+            DateTime::date(year(date.year().into()), date.month() as u8, date.day() as u8)
+        }
+    }
+
+    impl From<jiff::civil::DateTime> for DateTime {
+        fn from(datetime: jiff::civil::DateTime) -> Self {
+            DateTime {
+                nanosecond: Some(datetime.subsec_nanosecond() as u32),
+                ..DateTime::local_time(
+                    year(datetime.year().into()),
+                    datetime.month() as u8,
+                    datetime.day() as u8,
+                    datetime.hour() as u8,
+                    datetime.minute() as u8,
+                    datetime.second() as u8,
+                )
+            }
+        }
+    }
+
+    /// Converts the date and time in its time zone, using the offset in
+    /// effect at that instant. A zero offset becomes [`Timezone::Utc`].
+    impl From<&jiff::Zoned> for DateTime {
+        fn from(zoned: &jiff::Zoned) -> Self {
+            DateTime {
+                timezone: Some(offset(zoned.offset().seconds())),
+                ..zoned.datetime().into()
+            }
+        }
+    }
+
+    /// Converts the date and time in its time zone, using the offset in
+    /// effect at that instant. A zero offset becomes [`Timezone::Utc`].
+    impl From<jiff::Zoned> for DateTime {
+        fn from(zoned: jiff::Zoned) -> Self {
+            (&zoned).into()
+        }
+    }
+
+    /// Converts the instant in UTC.
+    impl From<jiff::Timestamp> for DateTime {
+        fn from(timestamp: jiff::Timestamp) -> Self {
+            DateTime {
+                timezone: Some(Timezone::Utc),
+                ..jiff::tz::Offset::UTC.to_datetime(timestamp).into()
+            }
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod time_impls {
+    use super::*;
+
+    impl From<time::Date> for DateTime {
+        fn from(date: time::Date) -> Self {
+            DateTime::date(year(date.year()), date.month().into(), date.day())
+        }
+    }
+
+    impl From<time::PrimitiveDateTime> for DateTime {
+        fn from(datetime: time::PrimitiveDateTime) -> Self {
+            DateTime {
+                nanosecond: Some(datetime.nanosecond()),
+                ..DateTime::local_time(
+                    year(datetime.year()),
+                    datetime.month().into(),
+                    datetime.day(),
+                    datetime.hour(),
+                    datetime.minute(),
+                    datetime.second(),
+                )
+            }
+        }
+    }
+
+    /// Converts the date and time in its offset. A zero offset becomes
+    /// [`Timezone::Utc`].
+    impl From<time::OffsetDateTime> for DateTime {
+        fn from(datetime: time::OffsetDateTime) -> Self {
+            DateTime {
+                timezone: Some(offset(datetime.offset().whole_seconds())),
+                ..time::PrimitiveDateTime::new(datetime.date(), datetime.time()).into()
+            }
+        }
+    }
+}
+
+#[cfg(all(test, any(feature = "chrono", feature = "jiff", feature = "time")))]
+mod tests {
+    use crate::{DateTime, XmpType};
+
+    /// Serialize a date as it would appear in a packet.
+    fn iso(date: impl Into<DateTime>) -> String {
+        let mut buf = String::new();
+        date.into().write(&mut buf);
+        buf
+    }
+
+    #[test]
+    #[cfg(feature = "chrono")]
+    fn chrono_conversions() {
+        use chrono::{FixedOffset, NaiveDate, TimeZone, Utc};
+
+        let date = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap();
+        assert_eq!(iso(date), "2024-02-29");
+
+        let datetime = date.and_hms_nano_opt(13, 5, 9, 120_000_000).unwrap();
+        assert_eq!(iso(datetime), "2024-02-29T13:05:09.12");
+        assert_eq!(iso(Utc.from_utc_datetime(&datetime)), "2024-02-29T13:05:09.12Z");
+
+        let west = FixedOffset::west_opt(30 * 60).unwrap();
+        let local = west.from_local_datetime(&datetime).unwrap();
+        assert_eq!(iso(local), "2024-02-29T13:05:09.12-00:30");
+
+        let east = FixedOffset::east_opt(5 * 3600 + 45 * 60).unwrap();
+        let local = east.from_local_datetime(&datetime).unwrap();
+        assert_eq!(iso(local), "2024-02-29T13:05:09.12+05:45");
+
+        let leap = date.and_hms_nano_opt(23, 59, 59, 1_500_000_000).unwrap();
+        assert_eq!(iso(leap), "2024-02-29T23:59:59.5");
+
+        let ancient = DateTime::from(NaiveDate::from_ymd_opt(-1, 1, 1).unwrap());
+        assert!(ancient.validate().is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    fn time_conversions() {
+        use time::{Date, Month, PrimitiveDateTime, Time, UtcOffset};
+
+        let date = Date::from_calendar_date(2024, Month::February, 29).unwrap();
+        assert_eq!(iso(date), "2024-02-29");
+
+        let time = Time::from_hms_nano(13, 5, 9, 120_000_000).unwrap();
+        let datetime = PrimitiveDateTime::new(date, time);
+        assert_eq!(iso(datetime), "2024-02-29T13:05:09.12");
+        assert_eq!(iso(datetime.assume_utc()), "2024-02-29T13:05:09.12Z");
+
+        let west = UtcOffset::from_hms(0, -30, 0).unwrap();
+        assert_eq!(iso(datetime.assume_offset(west)), "2024-02-29T13:05:09.12-00:30");
+
+        let east = UtcOffset::from_hms(5, 45, 0).unwrap();
+        assert_eq!(iso(datetime.assume_offset(east)), "2024-02-29T13:05:09.12+05:45");
+
+        let seconds = UtcOffset::from_hms(-1, -30, -30).unwrap();
+        assert_eq!(iso(datetime.assume_offset(seconds)), "2024-02-29T13:05:09.12-01:30");
+    }
+
+    #[test]
+    #[cfg(feature = "jiff")]
+    fn jiff_conversions() {
+        use jiff::civil::date;
+        use jiff::tz::{Offset, TimeZone};
+        use jiff::Timestamp;
+
+        let day = date(2024, 2, 29);
+        assert_eq!(iso(day), "2024-02-29");
+
+        let datetime = day.at(13, 5, 9, 120_000_000);
+        assert_eq!(iso(datetime), "2024-02-29T13:05:09.12");
+
+        let west = TimeZone::fixed(Offset::from_seconds(-30 * 60).unwrap());
+        let zoned = datetime.to_zoned(west).unwrap();
+        assert_eq!(iso(&zoned), "2024-02-29T13:05:09.12-00:30");
+
+        let east = TimeZone::fixed(Offset::from_seconds(5 * 3600 + 45 * 60).unwrap());
+        assert_eq!(iso(datetime.to_zoned(east).unwrap()), "2024-02-29T13:05:09.12+05:45");
+
+        let timestamp = Timestamp::from_second(0).unwrap();
+        assert_eq!(iso(timestamp), "1970-01-01T00:00:00Z");
+
+        assert!(DateTime::from(date(-1, 1, 1)).validate().is_err());
+    }
+}
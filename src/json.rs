@@ -0,0 +1,321 @@
+//! Packets from JSON objects.
+
+use serde_json::Value as Json;
+
+use crate::{Namespace, Property, RdfCollectionType, Value, XmpWriter};
+
+/// Arrays of the predefined schemas that are unordered.
+const BAGS: &[(Namespace<'static>, &str)] = &[
+    (Namespace::DublinCore, "contributor"),
+    (Namespace::DublinCore, "language"),
+    (Namespace::DublinCore, "publisher"),
+    (Namespace::DublinCore, "relation"),
+    (Namespace::DublinCore, "subject"),
+    (Namespace::DublinCore, "type"),
+    (Namespace::Xmp, "Advisory"),
+    (Namespace::Xmp, "Identifier"),
+];
+
+/// The namespaces and array types used to map JSON objects onto properties.
+///
+/// The predefined namespaces are always known by their usual prefixes.
+/// Arrays are ordered unless they are declared otherwise with
+/// [`SchemaMap::array`]; the unordered arrays of the Dublin Core and XMP
+/// basic schemas are declared by [`SchemaMap::new`].
+///
+/// Enabled by the `serde_json` feature.
+#[derive(Debug, Clone)]
+pub struct SchemaMap<'n> {
+    namespaces: Vec<Namespace<'n>>,
+    arrays: Vec<(Namespace<'n>, String, RdfCollectionType)>,
+}
+
+impl<'n> SchemaMap<'n> {
+    /// Create a new map with the predefined namespaces.
+    pub fn new() -> Self {
+        Self {
+            namespaces: vec![],
+            arrays: BAGS
+                .iter()
+                .map(|(ns, name)| (ns.clone(), name.to_string(), RdfCollectionType::Bag))
+                .collect(),
+        }
+    }
+
+    /// Add a namespace under its prefix.
+    ///
+    /// Replaces a namespace that was added with the same prefix before and
+    /// takes precedence over a predefined namespace with the same prefix.
+    pub fn namespace(&mut self, namespace: Namespace<'n>) -> &mut Self {
+        self.namespaces.retain(|ns| ns.prefix() != namespace.prefix());
+        self.namespaces.push(namespace);
+        self
+    }
+
+    /// Declare the type of the arrays of a property.
+    ///
+    /// Use [`RdfCollectionType::Alt`] for alternatives other than language
+    /// alternatives, which are written from objects instead.
+    pub fn array(
+        &mut self,
+        namespace: Namespace<'n>,
+        name: impl Into<String>,
+        kind: RdfCollectionType,
+    ) -> &mut Self {
+        let name = name.into();
+        self.arrays
+            .retain(|(ns, n, _)| ns.url() != namespace.url() || *n != name);
+        self.arrays.push((namespace, name, kind));
+        self
+    }
+
+    /// Find the namespace with a prefix.
+    pub fn resolve(&self, prefix: &str) -> Option<Namespace<'n>> {
+        self.namespaces
+            .iter()
+            .find(|ns| ns.prefix() == prefix)
+            .cloned()
+            .or_else(|| Namespace::with_prefix(prefix))
+    }
+
+    /// The type of the arrays of a property.
+    fn array_kind(&self, namespace: &Namespace, name: &str) -> RdfCollectionType {
+        self.arrays
+            .iter()
+            .find(|(ns, n, _)| ns.url() == namespace.url() && n == name)
+            .map_or(RdfCollectionType::Seq, |&(_, _, kind)| kind)
+    }
+}
+
+impl Default for SchemaMap<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Properties from JSON.
+///
+/// Enabled by the `serde_json` feature.
+impl<'n> XmpWriter<'n> {
+    /// Create a writer with the properties of a JSON object.
+    ///
+    /// The keys of the object are prefixed names like `dc:title` whose
+    /// prefixes are resolved with the schema map. Values are mapped as
+    /// follows:
+    ///
+    /// - Strings, numbers, and booleans become simple values.
+    /// - Arrays become arrays of the type declared in the schema map.
+    /// - Objects whose keys are prefixed names become structs.
+    /// - Objects whose keys are language tags become language alternatives,
+    ///   with the `x-default` item first.
+    /// - `null` properties and fields are skipped.
+    ///
+    /// The whole object is checked before anything is written, so no
+    /// properties are written if it is invalid.
+    ///
+    /// ```
+    /// use xmp_writer::{SchemaMap, XmpWriter};
+    ///
+    /// let json = serde_json::json!({
+    ///     "dc:title": { "x-default": "Report", "de": "Bericht" },
+    ///     "dc:subject": ["metadata", "xmp"],
+    ///     "xmp:Rating": 4,
+    /// });
+    ///
+    /// let writer = XmpWriter::from_json(&json, &SchemaMap::new()).unwrap();
+    /// println!("{}", writer.finish(None));
+    /// ```
+    pub fn from_json(json: &Json, schemas: &SchemaMap<'n>) -> Result<Self, JsonError> {
+        let Json::Object(object) = json else {
+            return Err(JsonError::new("", "expected an object"));
+        };
+
+        let properties = fields(object, schemas, "")?;
+        let mut writer = Self::new();
+        for property in &properties {
+            property
+                .value
+                .write(writer.element(&property.name, property.namespace.clone()));
+        }
+        Ok(writer)
+    }
+}
+
+/// Map the prefixed keys of an object onto properties.
+fn fields<'n>(
+    object: &serde_json::Map<String, Json>,
+    schemas: &SchemaMap<'n>,
+    path: &str,
+) -> Result<Vec<Property<'n>>, JsonError> {
+    let mut properties = vec![];
+    for (key, json) in object {
+        let path = format!("{path}/{key}");
+        let Some((prefix, name)) = key.split_once(':') else {
+            return Err(JsonError::new(path, "expected a prefixed name"));
+        };
+        let Some(namespace) = schemas.resolve(prefix) else {
+            return Err(JsonError::new(
+                path,
+                format!("unknown namespace prefix `{prefix}`"),
+            ));
+        };
+        if json.is_null() {
+            continue;
+        }
+
+        let kind = schemas.array_kind(&namespace, name);
+        let value = value(json, kind, schemas, &path)?;
+        properties.push(Property::new(namespace, name, value));
+    }
+    Ok(properties)
+}
+
+/// Map a JSON value onto a property value.
+fn value<'n>(
+    json: &Json,
+    kind: RdfCollectionType,
+    schemas: &SchemaMap<'n>,
+    path: &str,
+) -> Result<Value<'n>, JsonError> {
+    Ok(match json {
+        Json::Null => return Err(JsonError::new(path, "unexpected null")),
+        Json::Bool(b) => Value::Bool(*b),
+        Json::Number(number) => match (number.as_i64(), number.as_f64()) {
+            (Some(int), _) => Value::Integer(int),
+            (None, Some(real)) if real.is_finite() => Value::Real(real),
+            _ => return Err(JsonError::new(path, "number cannot be represented")),
+        },
+        Json::String(text) => Value::Text(text.clone()),
+        Json::Array(items) => Value::Array(
+            kind,
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    value(item, RdfCollectionType::Seq, schemas, &format!("{path}/{i}"))
+                })
+                .collect::<Result<_, _>>()?,
+        ),
+        Json::Object(object) if object.keys().any(|key| key.contains(':')) => {
+            Value::Struct(fields(object, schemas, path)?)
+        }
+        Json::Object(object) if !object.is_empty() => {
+            let mut items = vec![];
+            for (lang, text) in object {
+                let Json::String(text) = text else {
+                    let path = format!("{path}/{lang}");
+                    return Err(JsonError::new(path, "expected a string"));
+                };
+                let item = (lang.clone(), text.clone());
+                if lang == "x-default" {
+                    items.insert(0, item);
+                } else {
+                    items.push(item);
+                }
+            }
+            Value::LangAlt(items)
+        }
+        Json::Object(_) => Value::Struct(vec![]),
+    })
+}
+
+/// An error in a JSON object passed to [`XmpWriter::from_json`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    /// The location of the error as a slash-separated path of keys and
+    /// indices, e.g. `/dc:creator/0`.
+    pub path: String,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl JsonError {
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { path: path.into(), message: message.into() }
+    }
+}
+
+impl std::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{} (at {})", self.message, self.path)
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::XmpPacket;
+
+    fn packet(json: Json) -> String {
+        let writer = XmpWriter::from_json(&json, &SchemaMap::new()).unwrap();
+        assert_eq!(writer.errors(), []);
+        writer.finish(None)
+    }
+
+    #[test]
+    fn objects_become_structs_or_language_alternatives() {
+        let packet = packet(json!({
+            "dc:title": { "de": "Bericht", "x-default": "Report" },
+            "xmpTPg:MaxPageSize": { "stDim:w": 210, "stDim:unit": "mm" },
+        }));
+        let parsed = XmpPacket::parse(&packet).unwrap();
+
+        let Some(Value::LangAlt(items)) = parsed.get(&Namespace::DublinCore, "title")
+        else {
+            panic!("expected a language alternative");
+        };
+        assert_eq!(items[0], ("x-default".into(), "Report".into()));
+        assert_eq!(items[1], ("de".into(), "Bericht".into()));
+
+        let size = parsed.get(&Namespace::XmpPaged, "MaxPageSize").unwrap();
+        assert!(size.field(&Namespace::XmpDimensions, "unit").is_some());
+    }
+
+    #[test]
+    fn arrays_follow_the_schema_map() {
+        let packet = packet(json!({
+            "dc:subject": ["metadata", "xmp"],
+            "dc:creator": ["Jane Doe"],
+        }));
+        assert!(packet.contains("<dc:subject><rdf:Bag>"));
+        assert!(packet.contains("<dc:creator><rdf:Seq>"));
+
+        let mut schemas = SchemaMap::new();
+        schemas.array(Namespace::DublinCore, "creator", RdfCollectionType::Bag);
+        let json = json!({ "dc:creator": ["Jane Doe"] });
+        let writer = XmpWriter::from_json(&json, &schemas).unwrap();
+        assert!(writer.finish(None).contains("<dc:creator><rdf:Bag>"));
+    }
+
+    #[test]
+    fn nulls_are_skipped_in_objects() {
+        let packet = packet(json!({
+            "xmp:Rating": null,
+            "xmpTPg:MaxPageSize": { "stDim:w": 210, "stDim:h": null },
+        }));
+        assert!(!packet.contains("Rating"));
+        assert!(!packet.contains("stDim:h"));
+
+        let json = json!({ "dc:subject": ["xmp", null] });
+        let err = XmpWriter::from_json(&json, &SchemaMap::new()).unwrap_err();
+        assert_eq!(err, JsonError::new("/dc:subject/1", "unexpected null"));
+    }
+
+    #[test]
+    fn invalid_objects_are_rejected() {
+        let schemas = SchemaMap::new();
+        let err = XmpWriter::from_json(&json!({ "foo:bar": 1 }), &schemas).unwrap_err();
+        assert_eq!(err.path, "/foo:bar");
+        let err = XmpWriter::from_json(&json!({ "dc:title": { "de": 1 } }), &schemas)
+            .unwrap_err();
+        assert_eq!(err.path, "/dc:title/de");
+    }
+}
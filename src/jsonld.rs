@@ -0,0 +1,228 @@
+//! Export of packets as JSON-LD.
+
+use std::collections::btree_map::{BTreeMap, Entry};
+use std::fmt::Write;
+
+use crate::{Namespace, Property, RdfCollectionType, Value, XmpPacket, XmpType};
+
+impl XmpPacket<'_> {
+    /// Render the properties of the packet as a JSON-LD document.
+    ///
+    /// The context maps the prefixes of all used namespaces to their URLs.
+    /// Ordered arrays become `@list` objects, language alternatives become
+    /// language-tagged values, and dates are typed as `xsd:gYear`,
+    /// `xsd:gYearMonth`, `xsd:date`, or `xsd:dateTime` depending on their
+    /// precision. The `about` URI becomes the `@id` of the document.
+    ///
+    /// Returns an error if the same prefix is used for different namespaces,
+    /// which cannot be expressed in a single context.
+    pub fn to_json_ld(&self, about: Option<&str>) -> Result<String, PrefixCollision> {
+        let mut context = BTreeMap::new();
+        context.insert("xsd", "http://www.w3.org/2001/XMLSchema#");
+        collect_namespaces(self.properties(), &mut context)?;
+
+        let mut buf = String::from("{\"@context\":{");
+        for (i, (prefix, url)) in context.iter().enumerate() {
+            if i > 0 {
+                buf.push(',');
+            }
+            write_string(&mut buf, prefix);
+            buf.push(':');
+            write_string(&mut buf, url);
+        }
+        buf.push('}');
+
+        if let Some(about) = about {
+            buf.push_str(",\"@id\":");
+            write_string(&mut buf, about);
+        }
+
+        for property in self.properties() {
+            buf.push(',');
+            write_property(&mut buf, property);
+        }
+
+        buf.push('}');
+        Ok(buf)
+    }
+}
+
+/// A prefix that is used for two different namespaces when calling
+/// [`XmpPacket::to_json_ld`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixCollision {
+    /// The prefix that is used twice.
+    pub prefix: String,
+    /// The URL the prefix was first used for.
+    pub first: String,
+    /// The URL the prefix was then used for.
+    pub second: String,
+}
+
+impl std::fmt::Display for PrefixCollision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "prefix {} is used for both {} and {}",
+            self.prefix, self.first, self.second
+        )
+    }
+}
+
+impl std::error::Error for PrefixCollision {}
+
+/// Collect the prefixes and URLs of all namespaces used by the properties.
+fn collect_namespaces<'a>(
+    properties: impl IntoIterator<Item = &'a Property<'a>>,
+    context: &mut BTreeMap<&'a str, &'a str>,
+) -> Result<(), PrefixCollision> {
+    for property in properties {
+        insert_namespace(&property.namespace, context)?;
+        collect_value_namespaces(&property.value, context)?;
+    }
+    Ok(())
+}
+
+fn collect_value_namespaces<'a>(
+    value: &'a Value<'a>,
+    context: &mut BTreeMap<&'a str, &'a str>,
+) -> Result<(), PrefixCollision> {
+    match value {
+        Value::Array(_, items) => {
+            for item in items {
+                collect_value_namespaces(item, context)?;
+            }
+        }
+        Value::Struct(fields) => collect_namespaces(fields, context)?,
+        Value::Qualified(value, qualifiers) => {
+            insert_namespace(&Namespace::Rdf, context)?;
+            collect_value_namespaces(value, context)?;
+            collect_namespaces(qualifiers, context)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Add a namespace to the context unless its prefix is taken by another one.
+fn insert_namespace<'a>(
+    namespace: &'a Namespace<'_>,
+    context: &mut BTreeMap<&'a str, &'a str>,
+) -> Result<(), PrefixCollision> {
+    let (prefix, url) = (namespace.prefix(), namespace.url());
+    match context.entry(prefix) {
+        Entry::Vacant(entry) => {
+            entry.insert(url);
+        }
+        Entry::Occupied(entry) if *entry.get() != url => {
+            return Err(PrefixCollision {
+                prefix: prefix.into(),
+                first: entry.get().to_string(),
+                second: url.into(),
+            });
+        }
+        Entry::Occupied(_) => {}
+    }
+    Ok(())
+}
+
+fn write_property(buf: &mut String, property: &Property) {
+    write_string(buf, &property.qname());
+    buf.push(':');
+    write_value(buf, &property.value);
+}
+
+fn write_value(buf: &mut String, value: &Value) {
+    match value {
+        Value::Text(text) => write_string(buf, text),
+        Value::Integer(int) => write!(buf, "{int}").unwrap(),
+        Value::Real(real) if real.is_finite() => write!(buf, "{real}").unwrap(),
+        Value::Real(real) => write_string(buf, &real.to_string()),
+        Value::Bool(b) => write!(buf, "{b}").unwrap(),
+        Value::Date(date) => {
+            let mut text = String::new();
+            date.write(&mut text);
+            let ty = match (date.month, date.day, date.hour) {
+                (None, _, _) => "gYear",
+                (Some(_), None, _) => "gYearMonth",
+                (Some(_), Some(_), None) => "date",
+                (Some(_), Some(_), Some(_)) => "dateTime",
+            };
+            buf.push_str("{\"@value\":");
+            write_string(buf, &text);
+            write!(buf, ",\"@type\":\"xsd:{ty}\"}}").unwrap();
+        }
+        Value::Array(kind, items) => {
+            let ordered = *kind == RdfCollectionType::Seq;
+            if ordered {
+                buf.push_str("{\"@list\":");
+            }
+            buf.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    buf.push(',');
+                }
+                write_value(buf, item);
+            }
+            buf.push(']');
+            if ordered {
+                buf.push('}');
+            }
+        }
+        Value::LangAlt(items) => {
+            buf.push('[');
+            for (i, (lang, text)) in items.iter().enumerate() {
+                if i > 0 {
+                    buf.push(',');
+                }
+                buf.push_str("{\"@value\":");
+                write_string(buf, text);
+                // `x-default` is not a valid language tag.
+                if lang != "x-default" {
+                    buf.push_str(",\"@language\":");
+                    write_string(buf, lang);
+                }
+                buf.push('}');
+            }
+            buf.push(']');
+        }
+        Value::Struct(fields) => write_object(buf, fields),
+        Value::Qualified(value, qualifiers) => {
+            buf.push_str("{\"rdf:value\":");
+            write_value(buf, value);
+            for qualifier in qualifiers {
+                buf.push(',');
+                write_property(buf, qualifier);
+            }
+            buf.push('}');
+        }
+    }
+}
+
+fn write_object(buf: &mut String, fields: &[Property]) {
+    buf.push('{');
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            buf.push(',');
+        }
+        write_property(buf, field);
+    }
+    buf.push('}');
+}
+
+/// Write a JSON string literal.
+fn write_string(buf: &mut String, s: &str) {
+    buf.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if c.is_control() => write!(buf, "\\u{:04x}", c as u32).unwrap(),
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
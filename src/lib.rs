@@ -42,17 +42,63 @@ println!("{}", writer.finish(None));
 
 #![deny(missing_docs)]
 
+#[cfg(feature = "id3")]
+mod audio;
+#[cfg(feature = "pdfa")]
+mod conformance;
+mod docinfo;
+#[cfg(feature = "kamadak-exif")]
+mod exif;
+#[cfg(feature = "ttf-parser")]
+mod font;
+mod fragment;
+#[cfg(feature = "uuid")]
+mod ids;
+#[cfg(any(feature = "chrono", feature = "jiff", feature = "time"))]
+mod interop;
+#[cfg(feature = "serde_json")]
+mod json;
+mod jsonld;
+mod lint;
+mod metadata;
+mod packet;
+mod parse;
+mod path;
 #[cfg(feature = "pdfa")]
 pub mod pdfa;
+mod report;
+#[cfg(feature = "testing")]
+pub mod testing;
 mod types;
+mod tz;
+#[cfg(feature = "wasm-bindgen")]
+mod wasm;
 
-use std::collections::BTreeSet;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fmt::Write;
 
 #[cfg(feature = "pdfa")]
 use pdfa::PdfAExtSchemasWriter;
 
+#[cfg(feature = "pdfa")]
+pub use conformance::PdfAViolation;
+pub use docinfo::*;
+pub use fragment::XmpFragment;
+#[cfg(feature = "uuid")]
+pub use ids::IdFormat;
+#[cfg(feature = "serde_json")]
+pub use json::{JsonError, SchemaMap};
+pub use jsonld::PrefixCollision;
+pub use lint::*;
+pub use metadata::*;
+pub use packet::*;
+pub use parse::ParseError;
+pub use path::PathError;
+pub use report::*;
 pub use types::*;
+#[cfg(feature = "wasm-bindgen")]
+pub use wasm::XmpBuilder;
 
 /// Implements `Deref` and `DerefMut` by delegating to a field of a struct.
 macro_rules! deref {
@@ -80,10 +126,73 @@ pub(crate) use deref;
 ///
 /// Use [`XmpWriter::new`] to create a new instance and get the resulting XMP
 /// metadata by calling [`XmpWriter::finish`].
-#[derive(Default)]
+///
+/// A writer can be cloned to reuse properties that are shared by many
+/// documents, e.g. the rights and the creator tool, as a template.
+///
+/// The properties are kept in memory until the packet is finished, as the
+/// namespace declarations that precede them, [`XmpWriter::rollback`], and
+/// the checks of [`XmpWriter::finish_with`] depend on all of them. To send a
+/// packet to a file or a socket, write the finished string to it.
+#[derive(Default, Clone)]
 pub struct XmpWriter<'a> {
-    pub(crate) buf: String,
-    namespaces: BTreeSet<Namespace<'a>>,
+    /// Identifies the namespace handles created by this writer.
+    pub(crate) id: WriterId,
+    pub(crate) buf: PacketBuf,
+    pub(crate) namespaces: NamespaceSet<'a>,
+    pub(crate) struct_form: StructForm,
+    /// Whether the buffer ends with the unclosed start tag of a compact
+    /// struct.
+    pub(crate) open_tag: bool,
+    /// The serialized attributes of the element whose start tag is deferred.
+    /// At most one element is deferred at a time, so the buffer is reused.
+    pub(crate) attrs: String,
+    /// The top-level properties written so far and the offsets in the buffer
+    /// at which they start.
+    pub(crate) properties: BTreeMap<(Namespace<'a>, String), usize>,
+    /// Whether language alternatives are fixed to start with `x-default`.
+    pub(crate) fix_x_default: bool,
+    /// Required struct fields that were not written.
+    pub(crate) missing_fields: Vec<MissingField>,
+    /// Whether writing a property twice makes finishing fail.
+    pub(crate) write_once: bool,
+    /// The qualified names of properties that were written more than once.
+    pub(crate) duplicates: Vec<String>,
+    /// Invalid names and values that were written.
+    pub(crate) errors: Vec<XmpError>,
+    /// The namespaces registered with [`XmpWriter::register_namespace`].
+    pub(crate) registered: Vec<CustomNamespace<'a>>,
+    /// Prefixes that replace the default prefixes of namespaces.
+    pub(crate) prefixes: BTreeMap<Namespace<'a>, String>,
+    /// Whether the properties required by PDF/X are checked when finishing.
+    pub(crate) pdfx: bool,
+    /// Whether the properties required by PDF/UA are checked when finishing.
+    pub(crate) pdfua: bool,
+    /// The PDF/A part the packet is checked against when finishing.
+    #[cfg(feature = "pdfa")]
+    pub(crate) pdfa_part: Option<i32>,
+    /// The PDF/A part written with [`XmpWriter::pdfa_part`].
+    #[cfg(feature = "pdfa")]
+    pub(crate) pdfa_id: Option<i32>,
+    /// The descriptions provided with [`XmpWriter::describe_property`], as
+    /// the namespace URL, the name, and the description.
+    #[cfg(feature = "pdfa")]
+    pub(crate) descriptions: Vec<(String, String, String)>,
+    /// The URLs of the namespaces described in `pdfaExtension:schemas`.
+    #[cfg(feature = "pdfa")]
+    pub(crate) described_schemas: Vec<String>,
+    /// The `rdf:about` value used if none is passed when finishing.
+    pub(crate) about: Option<String>,
+}
+
+impl std::fmt::Debug for XmpWriter<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("XmpWriter")
+            .field("buf", &self.buf.body())
+            .field("namespaces", &self.namespaces)
+            .field("registered", &self.registered)
+            .finish_non_exhaustive()
+    }
 }
 
 impl<'n> XmpWriter<'n> {
@@ -92,41 +201,867 @@ impl<'n> XmpWriter<'n> {
         Self::default()
     }
 
+    /// Create a new XMP writer whose buffer can hold at least `capacity`
+    /// bytes of properties without reallocating.
+    pub fn with_capacity(capacity: usize) -> XmpWriter<'n> {
+        Self {
+            buf: PacketBuf::with_capacity(capacity),
+            ..Self::default()
+        }
+    }
+
+    /// Discard everything written so far to start a new packet.
+    ///
+    /// Keeps the allocated buffer and the settings, i.e. the struct form,
+    /// [`XmpWriter::set_fix_x_default`], [`XmpWriter::set_write_once`], the
+    /// registered namespaces and prefixes, whether the packet is checked
+    /// against PDF/A with [`XmpWriter::strict_pdfa`], and the property
+    /// descriptions provided with [`XmpWriter::describe_property`], as they
+    /// apply to the properties of any packet. Everything else, including the
+    /// `rdf:about` value and the checks enabled by writing identification
+    /// schemas like [`XmpWriter::pdfua`], is reset. Cheaper than creating a
+    /// new writer for each of many packets.
+    pub fn reset(&mut self) {
+        self.buf.truncate(HEADER_CAPACITY);
+        self.namespaces.clear();
+        self.open_tag = false;
+        self.attrs.clear();
+        self.properties.clear();
+        self.missing_fields.clear();
+        self.duplicates.clear();
+        self.errors.clear();
+        self.pdfx = false;
+        self.pdfua = false;
+        #[cfg(feature = "pdfa")]
+        {
+            self.pdfa_id = None;
+            self.described_schemas.clear();
+        }
+        self.about = None;
+    }
+
+    /// The number of bytes of the properties written so far.
+    ///
+    /// The packet returned by [`XmpWriter::finish`] is larger, as it also
+    /// contains the packet wrapper and the namespace declarations. Can be
+    /// used to monitor the size against limits like the 64 KB of a JPEG
+    /// segment.
+    pub fn len(&self) -> usize {
+        self.buf.body().len()
+    }
+
+    /// The length of the packet [`XmpWriter::finish`] would return now.
+    ///
+    /// Includes the packet wrapper and the namespace declarations, but not
+    /// an `rdf:about` passed to `finish` or padding. Can be used to decide
+    /// early whether the packet must be split for a JPEG segment or to
+    /// allocate downstream buffers.
+    pub fn estimated_final_len(&self) -> usize {
+        let mut header = String::with_capacity(HEADER_CAPACITY);
+        self.write_header(&mut header, None);
+        header.len().max(HEADER_CAPACITY)
+            + self.len()
+            + PACKET_FOOTER.len()
+            + PACKET_TRAILER_LEN
+    }
+
+    /// Reserve capacity for at least `additional` more bytes of properties.
+    pub fn reserve(&mut self, additional: usize) -> &mut Self {
+        self.buf.reserve(additional);
+        self
+    }
+
+    /// Whether nothing was written so far, i.e. whether [`XmpWriter::len`] is
+    /// zero.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether any top-level properties were written so far.
+    ///
+    /// Unlike [`XmpWriter::is_empty`], comments and other content that is not
+    /// a property are not taken into account.
+    pub fn has_properties(&self) -> bool {
+        !self.properties.is_empty()
+    }
+
+    /// The number of top-level properties written so far.
+    pub fn property_count(&self) -> usize {
+        self.properties.len()
+    }
+
+    /// Set how struct values are serialized.
+    ///
+    /// Applies to all structs started after this call. Defaults to
+    /// [`StructForm::ParseTypeResource`].
+    pub fn set_struct_form(&mut self, form: StructForm) -> &mut Self {
+        self.struct_form = form;
+        self
+    }
+
+    /// Declare a namespace in the packet, if it is not declared yet.
+    pub(crate) fn declare(&mut self, namespace: &Namespace<'n>) {
+        self.namespaces.insert(namespace);
+    }
+
+    /// Close the start tag of a compact struct, if any.
+    pub(crate) fn close_open_tag(&mut self) {
+        if self.open_tag {
+            self.buf.push('>');
+            self.open_tag = false;
+        }
+    }
+
+    /// Add an XML comment to the XMP metadata.
+    ///
+    /// Consumers ignore comments, but they can be useful to annotate generated
+    /// packets. Sequences of hyphens, which are not allowed in comments, are
+    /// separated by spaces.
+    pub fn comment(&mut self, text: &str) -> &mut Self {
+        self.close_open_tag();
+        self.buf.push_str("<!--");
+        let mut prev = ' ';
+        for c in text.chars() {
+            if c == '-' && prev == '-' {
+                self.buf.push(' ');
+            }
+            self.buf.push(c);
+            prev = c;
+        }
+        if prev == '-' {
+            self.buf.push(' ');
+        }
+        self.buf.push_str("-->");
+        self
+    }
+
+    /// Set whether language alternatives are fixed to start with an
+    /// `x-default` item.
+    ///
+    /// Many consumers only show the `x-default` item or expect it first.
+    /// Applies to [`Element::language_alternative`] and all methods that use
+    /// it. Defaults to `false`.
+    pub fn set_fix_x_default(&mut self, fix: bool) -> &mut Self {
+        self.fix_x_default = fix;
+        self
+    }
+
+    /// Set whether writing a property twice is an error.
+    ///
+    /// Writing a top-level property twice, e.g. by calling
+    /// [`XmpWriter::title`] from two code paths, produces invalid XMP. Such
+    /// properties are always recorded in [`XmpWriter::duplicates`]. If this
+    /// is enabled, [`XmpWriter::finish_with`] also returns
+    /// [`FinishError::DuplicateProperties`] in this case. Defaults to `false`.
+    pub fn set_write_once(&mut self, write_once: bool) -> &mut Self {
+        self.write_once = write_once;
+        self
+    }
+
     /// Add a custom element to the XMP metadata.
+    ///
+    /// Each property may only be written once. Writing a property twice
+    /// produces invalid XMP, see [`XmpWriter::set_write_once`]. Use an
+    /// [`XmpPacket`] if properties need to be replaced.
+    ///
+    /// If the name or the namespace prefix is not a valid XML name, an
+    /// [`XmpError::InvalidName`] is recorded and reported by
+    /// [`XmpWriter::finish_with`].
     #[inline]
     pub fn element<'a>(
         &'a mut self,
         name: &'a str,
-        namespace: Namespace<'n>,
+        namespace: impl ToNamespace<'n>,
     ) -> Element<'a, 'n> {
+        let namespace = namespace.to_namespace(self);
         Element::start(self, name, namespace)
     }
 
+    /// Add a custom element to the XMP metadata, checking that the name and
+    /// the namespace prefix are valid XML names.
+    ///
+    /// Unlike [`XmpWriter::element`], which records invalid names to be
+    /// reported when finishing, this returns the error right away. Use this
+    /// method for names that are not known at compile time.
+    pub fn try_element<'a>(
+        &'a mut self,
+        name: &'a str,
+        namespace: impl ToNamespace<'n>,
+    ) -> Result<Element<'a, 'n>, NameError> {
+        let namespace = namespace.to_namespace(self);
+        for name in [name, types::prefix_of(&self.prefixes, &namespace)] {
+            if !types::is_ncname(name) {
+                return Err(NameError { name: name.into() });
+            }
+        }
+        Ok(Element::start_unchecked(self, name, namespace))
+    }
+
+    /// Record the offset at which a top-level property starts.
+    pub(crate) fn record_property(&mut self, name: &str, namespace: &Namespace<'n>) {
+        let len = self.buf.len();
+        let start = *self
+            .properties
+            .entry((namespace.clone(), name.to_string()))
+            .or_insert(len);
+        if start != len {
+            let prefix = types::prefix_of(&self.prefixes, namespace);
+            self.duplicates.push(format!("{prefix}:{name}"));
+        }
+    }
+
+    /// The required fields that are missing from structs written so far.
+    ///
+    /// Typed struct writers like [`ThumbnailWriter`] record the fields that
+    /// the XMP specification requires when they are dropped. If any are
+    /// missing, [`XmpWriter::finish_with`] returns
+    /// [`FinishError::MissingFields`].
+    pub fn missing_fields(&self) -> &[MissingField] {
+        &self.missing_fields
+    }
+
+    /// The qualified names of the top-level properties that were written more
+    /// than once so far, once for each repetition.
+    ///
+    /// The repetitions are written anyway and make the packet invalid. See
+    /// [`XmpWriter::set_write_once`].
+    pub fn duplicates(&self) -> &[String] {
+        &self.duplicates
+    }
+
+    /// The errors recorded while writing so far.
+    ///
+    /// Invalid names and values, e.g. a [`DateTime`] that fails
+    /// [`DateTime::validate`], are written anyway to keep the writing methods
+    /// infallible. Non-finite numbers are the exception: they cannot be
+    /// represented, so nothing is written for them. If there are any errors,
+    /// [`XmpWriter::finish_with`] returns [`FinishError::Invalid`].
+    pub fn errors(&self) -> &[XmpError] {
+        &self.errors
+    }
+
+    /// Whether a top-level property was already written.
+    ///
+    /// Can be used to write defaults only for properties that were not
+    /// provided otherwise.
+    pub fn has_property(&self, namespace: impl ToNamespace<'n>, name: &str) -> bool {
+        let namespace = namespace.to_namespace(self);
+        self.properties.contains_key(&(namespace, name.to_string()))
+    }
+
+    /// The namespaces used by the properties written so far, in the order
+    /// in which they are declared in the packet.
+    ///
+    /// The RDF namespace is not included. Can be used to decide which
+    /// extension schemas need to be described for PDF/A.
+    pub fn namespaces(&self) -> impl Iterator<Item = &Namespace<'n>> {
+        self.namespaces.iter().filter(|&ns| &Namespace::Rdf != ns)
+    }
+
+    /// Register a custom namespace with a human-readable name, a prefix, and
+    /// the namespace URL, and return a handle for it.
+    ///
+    /// The handle can be passed to [`XmpWriter::element`] and the other
+    /// methods that take a namespace instead of constructing a
+    /// [`Namespace::Custom`] for each property. Registering the same
+    /// namespace twice returns the same handle.
+    ///
+    /// The strings can be borrowed or owned, e.g. when the namespace is only
+    /// known at runtime.
+    ///
+    /// # Panics
+    /// Handles must only be used with the writer that created them or its
+    /// clones. Using a handle that another writer created panics.
+    pub fn register_namespace(
+        &mut self,
+        name: impl Into<Cow<'n, str>>,
+        prefix: impl Into<Cow<'n, str>>,
+        url: impl Into<Cow<'n, str>>,
+    ) -> NamespaceId {
+        let namespace = CustomNamespace::from_strings(name, prefix, url);
+        let index = match self.registered.iter().position(|ns| *ns == namespace) {
+            Some(index) => index,
+            None => {
+                self.registered.push(namespace);
+                self.registered.len() - 1
+            }
+        };
+        NamespaceId { writer: self.id, index }
+    }
+
+    /// Use a different prefix for a namespace.
+    ///
+    /// The URL of the namespace stays the same, so consumers that resolve
+    /// namespaces by URL are unaffected. This helps with consumers that
+    /// expect a particular prefix, e.g. `pdfaExtension` instead of
+    /// `pdfaExt`. Must be called before any property in the namespace is
+    /// written. An invalid prefix, a namespace that is already used, and a
+    /// prefix that is already bound to another namespace are recorded in
+    /// [`XmpWriter::errors`] and leave the prefix unchanged.
+    pub fn set_prefix(
+        &mut self,
+        namespace: impl ToNamespace<'n>,
+        prefix: impl Into<String>,
+    ) -> &mut Self {
+        let namespace = namespace.to_namespace(self);
+        let prefix = prefix.into();
+        if types::prefix_of(&self.prefixes, &namespace) == prefix {
+            return self;
+        }
+
+        if !types::is_ncname(&prefix) {
+            self.errors.push(XmpError::InvalidName(NameError { name: prefix }));
+        } else if self.uses_namespace(&namespace) {
+            self.errors.push(XmpError::PrefixAfterUse(namespace.url().into()));
+        } else if self.prefix_owner(&prefix, &namespace).is_some() {
+            self.errors.push(XmpError::DuplicatePrefix(prefix));
+        } else {
+            self.prefixes.insert(namespace, prefix);
+        }
+        self
+    }
+
+    /// Whether a property in the namespace was written so far.
+    pub(crate) fn uses_namespace(&self, namespace: &Namespace) -> bool {
+        self.namespaces.contains(namespace)
+            || self.properties.keys().any(|(ns, _)| ns == namespace)
+    }
+
+    /// Find a namespace with a different URL than the given one that is used
+    /// or bound to the prefix.
+    pub(crate) fn prefix_owner(
+        &self,
+        prefix: &str,
+        namespace: &Namespace,
+    ) -> Option<&Namespace<'n>> {
+        self.prefixes
+            .keys()
+            .chain(self.namespaces.iter())
+            .chain(self.properties.keys().map(|(ns, _)| ns))
+            .find(|ns| {
+                ns.url() != namespace.url()
+                    && types::prefix_of(&self.prefixes, ns) == prefix
+            })
+    }
+
+    /// Remember the current state of the writer.
+    ///
+    /// Pass the returned checkpoint to [`XmpWriter::rollback`] to discard
+    /// everything written after this call, for example when it turns out
+    /// midway through a complex property that its data is unavailable.
+    pub fn checkpoint(&self) -> Checkpoint<'n> {
+        Checkpoint {
+            len: self.buf.len(),
+            namespaces: self.namespaces.clone(),
+            missing_fields: self.missing_fields.len(),
+            duplicates: self.duplicates.len(),
+            errors: self.errors.len(),
+            #[cfg(feature = "pdfa")]
+            described_schemas: self.described_schemas.len(),
+        }
+    }
+
+    /// Discard everything written since the checkpoint was taken.
+    ///
+    /// Properties, namespace declarations, and missing required fields that
+    /// were recorded after the checkpoint are removed, so the discarded
+    /// properties can be written again. Rolling back to a checkpoint of
+    /// another writer leaves the packet in an unspecified state.
+    pub fn rollback(&mut self, checkpoint: Checkpoint<'n>) -> &mut Self {
+        self.buf.truncate(checkpoint.len);
+        self.namespaces = checkpoint.namespaces;
+        self.properties.retain(|_, &mut start| start < checkpoint.len);
+        self.missing_fields.truncate(checkpoint.missing_fields);
+        self.duplicates.truncate(checkpoint.duplicates);
+        self.errors.truncate(checkpoint.errors);
+        #[cfg(feature = "pdfa")]
+        self.described_schemas.truncate(checkpoint.described_schemas);
+        self.open_tag = false;
+        self
+    }
+
+    /// Check that the metadata written so far forms a well-formed packet.
+    ///
+    /// Runs the packet through an XML and RDF parser and reports structural
+    /// problems like unclosed elements or malformed attributes. These are
+    /// typically caused by leaking a child writer with [`std::mem::forget`]
+    /// or by writing raw values. The offset of the error refers to the packet
+    /// that [`XmpWriter::finish`] would produce without an `about` URI.
+    ///
+    /// Enabled by the `validate` feature.
+    #[cfg(feature = "validate")]
+    pub fn validate(&self) -> Result<(), ParseError> {
+        let mut packet = String::new();
+        self.write_packet(&mut packet, None);
+        packet.push_str("<?xpacket end=\"r\"?>");
+        XmpPacket::parse(&packet).map(|_| ())
+    }
+
+    /// Check the packet against the metadata requirements of a part of the
+    /// PDF/A standard when finishing it.
+    ///
+    /// If the packet violates them, [`XmpWriter::finish_with`] returns
+    /// [`FinishError::PdfA`] instead of the packet, or
+    /// [`FinishError::Malformed`] if it cannot be parsed to check them. See
+    /// [`XmpPacket::check_pdfa`] for the checks that are performed.
+    #[cfg(feature = "pdfa")]
+    pub fn strict_pdfa(&mut self, part: i32) -> &mut Self {
+        self.pdfa_part = Some(part);
+        self
+    }
+
+    /// Set the value of the `rdf:about` attribute of the description.
+    ///
+    /// Used when finishing unless a different value is passed to
+    /// [`XmpWriter::finish`] or [`XmpWriter::finish_with`].
+    pub fn set_about(&mut self, about: impl Into<String>) -> &mut Self {
+        self.about = Some(about.into());
+        self
+    }
+
     /// Finish the XMP metadata and return it as a byte vector.
     pub fn finish(self, about: Option<&str>) -> String {
-        let mut buf = String::with_capacity(280 + self.buf.len());
+        let mut buf = self.into_packet(about, 0);
+        buf.push_str("<?xpacket end=\"r\"?>");
+        buf
+    }
+
+    /// Finish the XMP metadata with additional options.
+    ///
+    /// Returns an error if the packet cannot satisfy the options, e.g. if it
+    /// is already larger than the requested padded size, if invalid names or
+    /// values were written, if required struct
+    /// fields are missing, if properties were written twice with
+    /// [`XmpWriter::set_write_once`], or if it violates the requirements
+    /// enabled with [`XmpWriter::strict_pdfa`].
+    pub fn finish_with(self, options: &FinishOptions) -> Result<String, FinishError> {
+        if !self.errors.is_empty() {
+            return Err(FinishError::Invalid(self.errors));
+        }
+
+        if self.write_once && !self.duplicates.is_empty() {
+            return Err(FinishError::DuplicateProperties(self.duplicates));
+        }
+
+        if !self.missing_fields.is_empty() {
+            return Err(FinishError::MissingFields(self.missing_fields));
+        }
+
+        let mut required = vec![];
+        if self.pdfx {
+            required.extend([
+                (Namespace::DublinCore, "title"),
+                (Namespace::Xmp, "CreateDate"),
+                (Namespace::Xmp, "ModifyDate"),
+                (Namespace::AdobePdf, "Trapped"),
+            ]);
+        }
+        if self.pdfua && !self.pdfx {
+            required.push((Namespace::DublinCore, "title"));
+        }
+        let missing: Vec<_> = required
+            .into_iter()
+            .filter(|(ns, name)| !self.has_property(ns.clone(), name))
+            .map(|(ns, name)| {
+                format!("{}:{}", types::prefix_of(&self.prefixes, &ns), name)
+            })
+            .collect();
+        if !missing.is_empty() {
+            return Err(FinishError::MissingProperties(missing));
+        }
+
+        #[cfg(feature = "pdfa")]
+        let pdfa_part = self.pdfa_part;
+
+        let min = options.padded_size.unwrap_or(0);
+        let mut buf = self.into_packet(options.about, min);
+
+        #[cfg(feature = "pdfa")]
+        if let Some(part) = pdfa_part {
+            let violations = XmpPacket::parse(&buf)
+                .map_err(FinishError::Malformed)?
+                .check_pdfa(part);
+            if !violations.is_empty() {
+                return Err(FinishError::PdfA(violations));
+            }
+        }
+
+        let trailer = if options.padded_size.is_some() {
+            "<?xpacket end=\"w\"?>"
+        } else {
+            "<?xpacket end=\"r\"?>"
+        };
+
+        let len = buf.len() + trailer.len();
+        if let Some(size) = options.padded_size {
+            if len > size {
+                return Err(FinishError::ExceedsPaddedSize { len, size });
+            }
+
+            // The XMP specification recommends to insert a newline about every
+            // 100 characters of padding.
+            for i in 0..size - len {
+                buf.push(if i % 100 == 99 { '\n' } else { ' ' });
+            }
+        }
+
+        buf.push_str(trailer);
+
+        if let Some(max) = options.max_size {
+            if buf.len() > max {
+                return Err(FinishError::ExceedsMaxSize { len: buf.len(), max });
+            }
+        }
+
+        Ok(buf)
+    }
+
+    /// Write everything but the trailing processing instruction.
+    pub(crate) fn write_packet(&self, buf: &mut String, about: Option<&str>) {
+        self.write_header(buf, about);
+        buf.push_str(self.buf.body());
+        buf.push_str(PACKET_FOOTER);
+    }
+
+    /// Turn the writer into the packet without its trailer.
+    ///
+    /// The header is written into the room reserved at the front of the
+    /// buffer, so the properties stay where they are. Only a header longer
+    /// than [`HEADER_CAPACITY`] moves them. The returned string has room
+    /// for the trailer and for at least `min` bytes in total.
+    fn into_packet(self, about: Option<&str>, min: usize) -> String {
+        let mut header = String::with_capacity(HEADER_CAPACITY);
+        self.write_header(&mut header, about);
+        if let Some(slack) = HEADER_CAPACITY.checked_sub(header.len()) {
+            // Whitespace is allowed before the end of the start tag.
+            header.pop();
+            header.extend(std::iter::repeat_n(' ', slack));
+            header.push('>');
+        }
+
+        let mut buf = self.buf.0;
+        buf.replace_range(..HEADER_CAPACITY, &header);
+        let len = buf.len() + PACKET_FOOTER.len() + PACKET_TRAILER_LEN;
+        buf.reserve(len.max(min) - buf.len());
+        buf.push_str(PACKET_FOOTER);
+        buf
+    }
+
+    /// Write everything that precedes the properties in the packet.
+    fn write_header(&self, buf: &mut String, about: Option<&str>) {
         buf.push_str("<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>");
 
         write!(
-            &mut buf,
+            buf,
             "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\" x:xmptk=\"xmp-writer\"><rdf:RDF xmlns:rdf=\"{}\"><rdf:Description rdf:about=\"{}\"",
             Namespace::Rdf.url(),
-            about.unwrap_or(""),
+            types::escape_attr(about.or(self.about.as_deref()).unwrap_or("")),
         )
         .unwrap();
 
-        for namespace in self.namespaces.into_iter().filter(|ns| &Namespace::Rdf != ns) {
-            write!(&mut buf, " xmlns:{}=\"{}\" ", namespace.prefix(), namespace.url())
-                .unwrap();
+        self.write_namespaces(buf);
+        buf.push('>');
+    }
+
+    /// Write the declarations of the namespaces used so far.
+    pub(crate) fn write_namespaces(&self, buf: &mut String) {
+        for namespace in self.namespaces() {
+            let prefix = types::prefix_of(&self.prefixes, namespace);
+            let url = types::escape_attr(namespace.url());
+            write!(buf, " xmlns:{}=\"{}\" ", prefix, url).unwrap();
         }
+    }
+}
 
-        buf.push('>');
-        buf.push_str(&self.buf);
-        buf.push_str("</rdf:Description></rdf:RDF></x:xmpmeta><?xpacket end=\"r\"?>");
-        buf
+/// Options for [`XmpWriter::finish_with`].
+#[derive(Debug, Default, Clone)]
+pub struct FinishOptions<'a> {
+    /// The value of the `rdf:about` attribute of the description. Overrides
+    /// the value set with [`XmpWriter::set_about`].
+    pub about: Option<&'a str>,
+    /// Pad the packet with whitespace so that it is exactly this many bytes
+    /// long, including the `xpacket` processing instructions.
+    ///
+    /// This is needed to overwrite an existing metadata region in place, e.g.
+    /// in a JPEG, PSD, or in an incremental PDF update. Padded packets are
+    /// marked as writable.
+    pub padded_size: Option<usize>,
+    /// Fail instead of producing a packet longer than this many bytes.
+    ///
+    /// Use [`JPEG_MAX_PACKET_SIZE`] for packets that will be embedded into a
+    /// single JPEG `APP1` segment.
+    pub max_size: Option<usize>,
+}
+
+/// The maximum size of an XMP packet that fits into a single JPEG `APP1`
+/// segment.
+///
+/// This is the maximum segment length of 65535 bytes minus the two length bytes
+/// and the 29-byte XMP namespace signature. Larger packets must be split using
+/// the extended XMP mechanism.
+pub const JPEG_MAX_PACKET_SIZE: usize = 65504;
+
+/// Everything that follows the properties in the packet, except the trailer.
+const PACKET_FOOTER: &str = "</rdf:Description></rdf:RDF></x:xmpmeta>";
+
+/// The room reserved for the packet header, which covers the packet wrapper
+/// and the declarations of a few namespaces.
+pub(crate) const HEADER_CAPACITY: usize = 512;
+
+/// The buffer of a writer.
+///
+/// Starts with [`HEADER_CAPACITY`] bytes reserved for the packet header, as
+/// the header is only known when finishing. Offsets into the buffer, like the
+/// starts of properties, include the reserved bytes.
+#[derive(Clone)]
+pub(crate) struct PacketBuf(String);
+
+impl PacketBuf {
+    fn with_capacity(capacity: usize) -> Self {
+        let mut buf = String::with_capacity(HEADER_CAPACITY + capacity);
+        buf.extend(std::iter::repeat_n(' ', HEADER_CAPACITY));
+        Self(buf)
+    }
+
+    /// The properties written so far.
+    pub(crate) fn body(&self) -> &str {
+        &self.0[HEADER_CAPACITY..]
+    }
+}
+
+impl Default for PacketBuf {
+    fn default() -> Self {
+        Self::with_capacity(0)
     }
 }
 
+impl std::ops::Deref for PacketBuf {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for PacketBuf {
+    fn deref_mut(&mut self) -> &mut String {
+        &mut self.0
+    }
+}
+
+/// The length of the `<?xpacket end?>` trailer.
+const PACKET_TRAILER_LEN: usize = "<?xpacket end=\"r\"?>".len();
+
+/// An error that occurred while finishing an XMP packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FinishError {
+    /// The packet is longer than the requested padded size.
+    ExceedsPaddedSize {
+        /// The length of the packet without padding.
+        len: usize,
+        /// The requested padded size.
+        size: usize,
+    },
+    /// The packet is longer than the maximum size.
+    ExceedsMaxSize {
+        /// The length of the packet.
+        len: usize,
+        /// The maximum size.
+        max: usize,
+    },
+    /// Properties required by the PDF/X or PDF/UA standard selected with
+    /// [`XmpWriter::pdfx`] or [`XmpWriter::pdfua`] are missing. Contains
+    /// their qualified names.
+    MissingProperties(Vec<String>),
+    /// Structs are missing required fields. See
+    /// [`XmpWriter::missing_fields`].
+    MissingFields(Vec<MissingField>),
+    /// Properties were written more than once while
+    /// [`XmpWriter::set_write_once`] was enabled. Contains their qualified
+    /// names.
+    DuplicateProperties(Vec<String>),
+    /// Invalid names or values were written. See [`XmpWriter::errors`].
+    Invalid(Vec<XmpError>),
+    /// The packet violates the PDF/A requirements enabled with
+    /// [`XmpWriter::strict_pdfa`].
+    #[cfg(feature = "pdfa")]
+    PdfA(Vec<PdfAViolation>),
+    /// The packet could not be parsed to check it against the PDF/A
+    /// requirements enabled with [`XmpWriter::strict_pdfa`], e.g. because
+    /// raw values broke its structure.
+    #[cfg(feature = "pdfa")]
+    Malformed(ParseError),
+}
+
+impl std::fmt::Display for FinishError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ExceedsPaddedSize { len, size } => write!(
+                f,
+                "packet is {len} bytes long, which exceeds the padded size of {size} bytes"
+            ),
+            Self::ExceedsMaxSize { len, max } => write!(
+                f,
+                "packet is {len} bytes long, which exceeds the maximum size of {max} bytes"
+            ),
+            Self::MissingProperties(names) => {
+                write!(f, "packet is missing required properties: {}", names.join(", "))
+            }
+            Self::MissingFields(fields) => {
+                f.write_str("structs are missing required fields")?;
+                for (i, field) in fields.iter().enumerate() {
+                    f.write_str(if i == 0 { ": " } else { ", " })?;
+                    write!(f, "{field}")?;
+                }
+                Ok(())
+            }
+            Self::Invalid(errors) => {
+                f.write_str("packet contains invalid data")?;
+                for (i, error) in errors.iter().enumerate() {
+                    f.write_str(if i == 0 { ": " } else { "; " })?;
+                    write!(f, "{error}")?;
+                }
+                Ok(())
+            }
+            Self::DuplicateProperties(names) => {
+                write!(f, "properties were written more than once: {}", names.join(", "))
+            }
+            #[cfg(feature = "pdfa")]
+            Self::PdfA(violations) => {
+                f.write_str("packet violates PDF/A")?;
+                for (i, violation) in violations.iter().enumerate() {
+                    f.write_str(if i == 0 { ": " } else { "; " })?;
+                    write!(f, "{violation}")?;
+                }
+                Ok(())
+            }
+            #[cfg(feature = "pdfa")]
+            Self::Malformed(err) => write!(f, "packet is malformed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for FinishError {}
+
+/// An invalid name or value that was written. See [`XmpWriter::errors`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum XmpError {
+    /// A property name or namespace prefix is not a valid XML name.
+    InvalidName(NameError),
+    /// A date is out of range or has inconsistent precision.
+    InvalidDateTime(DateTimeError),
+    /// A text is not a valid [`MimeType`].
+    InvalidMimeType(String),
+    /// A real number is NaN or infinite.
+    NonFiniteNumber,
+    /// A colorant has a component that does not belong to its mode, e.g.
+    /// `xmpG:cyan` for an `RGB` colorant.
+    ColorantMismatch {
+        /// The mode of the colorant.
+        mode: ColorantMode,
+        /// The qualified name of the component.
+        field: String,
+    },
+    /// An appended [`XmpFragment`] or an included writer uses a different
+    /// prefix for the namespace with this URL than the writer.
+    PrefixConflict(String),
+    /// The prefix of the namespace with this URL was changed with
+    /// [`XmpWriter::set_prefix`] after a property in it was written.
+    PrefixAfterUse(String),
+    /// The prefix is bound to two namespaces with different URLs.
+    DuplicatePrefix(String),
+    /// The properties written so far could not be parsed, e.g. because raw
+    /// values broke their structure.
+    Malformed(ParseError),
+    /// A property or struct field with this qualified name was described
+    /// automatically in a PDF/A extension schema, but no description was
+    /// provided with [`XmpWriter::describe_property`].
+    #[cfg(feature = "pdfa")]
+    MissingDescription(String),
+    /// The namespace with this URL is not predefined by PDF/A, but
+    /// `pdfaExtension:schemas` was already written without describing it.
+    #[cfg(feature = "pdfa")]
+    UndescribedSchema(String),
+}
+
+impl std::fmt::Display for XmpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidName(err) => err.fmt(f),
+            Self::InvalidDateTime(err) => err.fmt(f),
+            Self::InvalidMimeType(mime) => write!(f, "`{mime}` is not a valid MIME type"),
+            Self::NonFiniteNumber => f.write_str("number is NaN or infinite"),
+            Self::ColorantMismatch { mode, field } => {
+                write!(f, "{field} is not a component of {mode:?} colorants")
+            }
+            Self::PrefixConflict(url) => {
+                write!(f, "namespace {url} is bound to different prefixes")
+            }
+            Self::PrefixAfterUse(url) => {
+                write!(f, "prefix of namespace {url} was changed after it was used")
+            }
+            Self::DuplicatePrefix(prefix) => {
+                write!(f, "prefix `{prefix}` is bound to different namespaces")
+            }
+            Self::Malformed(err) => write!(f, "properties are malformed: {err}"),
+            #[cfg(feature = "pdfa")]
+            Self::MissingDescription(name) => {
+                write!(f, "{name} has no description for its extension schema")
+            }
+            #[cfg(feature = "pdfa")]
+            Self::UndescribedSchema(url) => {
+                write!(f, "namespace {url} is not described by the extension schemas")
+            }
+        }
+    }
+}
+
+impl std::error::Error for XmpError {}
+
+impl From<NameError> for XmpError {
+    fn from(err: NameError) -> Self {
+        Self::InvalidName(err)
+    }
+}
+
+impl From<DateTimeError> for XmpError {
+    fn from(err: DateTimeError) -> Self {
+        Self::InvalidDateTime(err)
+    }
+}
+
+/// A required field that was not written to a struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingField {
+    /// The name of the struct's value type, e.g. `Thumbnail`.
+    pub value_type: &'static str,
+    /// The qualified name of the missing field, e.g. `xmpGImg:format`.
+    pub field: String,
+}
+
+impl std::fmt::Display for MissingField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} of {}", self.field, self.value_type)
+    }
+}
+
+/// The state of an [`XmpWriter`] at some point.
+///
+/// Created by [`XmpWriter::checkpoint`] and consumed by
+/// [`XmpWriter::rollback`].
+#[derive(Debug, Clone)]
+pub struct Checkpoint<'n> {
+    len: usize,
+    namespaces: NamespaceSet<'n>,
+    missing_fields: usize,
+    duplicates: usize,
+    errors: usize,
+    #[cfg(feature = "pdfa")]
+    described_schemas: usize,
+}
+
 /// XMP Dublin Core Schema.
 impl XmpWriter<'_> {
     /// Write the `dc:contributor` property.
@@ -135,10 +1070,10 @@ impl XmpWriter<'_> {
     /// listed in [`XmpWriter::creator`].
     pub fn contributor<'a>(
         &mut self,
-        contributor: impl IntoIterator<Item = &'a str>,
+        contributor: impl IntoIterator<Item = impl Into<ProperName<'a>>>,
     ) -> &mut Self {
         self.element("contributor", Namespace::DublinCore)
-            .unordered_array(contributor);
+            .unordered_array(contributor.into_iter().map(Into::into));
         self
     }
 
@@ -155,9 +1090,10 @@ impl XmpWriter<'_> {
     /// An entity primarily responsible for making the resource.
     pub fn creator<'a>(
         &mut self,
-        creator: impl IntoIterator<Item = &'a str>,
+        creator: impl IntoIterator<Item = impl Into<ProperName<'a>>>,
     ) -> &mut Self {
-        self.element("creator", Namespace::DublinCore).ordered_array(creator);
+        self.element("creator", Namespace::DublinCore)
+            .ordered_array(creator.into_iter().map(Into::into));
         self
     }
 
@@ -184,8 +1120,8 @@ impl XmpWriter<'_> {
     /// Write the `dc:format` property.
     ///
     /// The mime type of the resource.
-    pub fn format(&mut self, mime: &str) -> &mut Self {
-        self.element("format", Namespace::DublinCore).value(mime);
+    pub fn format<'a>(&mut self, mime: impl Into<MimeType<'a>>) -> &mut Self {
+        self.element("format", Namespace::DublinCore).value(mime.into());
         self
     }
 
@@ -213,22 +1149,22 @@ impl XmpWriter<'_> {
     /// Publishers of the resource.
     pub fn publisher<'a>(
         &mut self,
-        publisher: impl IntoIterator<Item = &'a str>,
+        publisher: impl IntoIterator<Item = impl Into<ProperName<'a>>>,
     ) -> &mut Self {
         self.element("publisher", Namespace::DublinCore)
-            .unordered_array(publisher);
+            .unordered_array(publisher.into_iter().map(Into::into));
         self
     }
 
     /// Write the `dc:relation` property.
     ///
     /// List of related resources.
-    pub fn relation<'a>(
+    pub fn relation(
         &mut self,
-        relation: impl IntoIterator<Item = &'a str>,
+        relation: impl IntoIterator<Item = impl AsRef<str>>,
     ) -> &mut Self {
         self.element("relation", Namespace::DublinCore)
-            .unordered_array(relation);
+            .unordered_array(relation.into_iter().map(Text));
         self
     }
 
@@ -255,12 +1191,12 @@ impl XmpWriter<'_> {
     /// Write the `dc:subject` property.
     ///    
     /// A list of phrases or keywords that specify the topic of the resource.
-    pub fn subject<'a>(
+    pub fn subject(
         &mut self,
-        subject: impl IntoIterator<Item = &'a str>,
+        subject: impl IntoIterator<Item = impl AsRef<str>>,
     ) -> &mut Self {
         self.element("subject", Namespace::DublinCore)
-            .unordered_array(subject);
+            .unordered_array(subject.into_iter().map(Text));
         self
     }
 
@@ -280,8 +1216,12 @@ impl XmpWriter<'_> {
     ///
     /// The nature or genre of the resource. Please use [`XmpWriter::format`] to
     /// specify the mime type.
-    pub fn type_<'a>(&mut self, kind: impl IntoIterator<Item = &'a str>) -> &mut Self {
-        self.element("type", Namespace::DublinCore).unordered_array(kind);
+    pub fn type_(
+        &mut self,
+        kind: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> &mut Self {
+        self.element("type", Namespace::DublinCore)
+            .unordered_array(kind.into_iter().map(Text));
         self
     }
 }
@@ -291,8 +1231,8 @@ impl<'n> XmpWriter<'n> {
     /// Write the `xmp:BaseURL` property.
     ///
     /// The base URL for relative URLs in the document.
-    pub fn base_url(&mut self, url: &str) -> &mut Self {
-        self.element("BaseURL", Namespace::Xmp).value(url);
+    pub fn base_url<'a>(&mut self, url: impl Into<Uri<'a>>) -> &mut Self {
+        self.element("BaseURL", Namespace::Xmp).value(url.into());
         self
     }
 
@@ -307,20 +1247,43 @@ impl<'n> XmpWriter<'n> {
     /// Write the `xmp:CreatorTool` property.
     ///
     /// The name of the application used to create the resource.
-    pub fn creator_tool(&mut self, tool: &str) -> &mut Self {
-        self.element("CreatorTool", Namespace::Xmp).value(tool);
+    pub fn creator_tool<'a>(&mut self, tool: impl Into<AgentName<'a>>) -> &mut Self {
+        self.element("CreatorTool", Namespace::Xmp).value(tool.into());
         self
     }
 
     /// Write the `xmp:Identifier` property.
     ///
-    /// Unordered array of text strings that identify the resource. The
-    /// [`XmpWriter::idq_scheme`] method can be used to specify the scheme.
-    pub fn xmp_identifier<'a>(
+    /// Unordered array of text strings that identify the resource. Use
+    /// [`XmpWriter::xmp_identifier_with_schemes`] to specify the scheme of
+    /// each identifier.
+    pub fn xmp_identifier(
+        &mut self,
+        id: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> &mut Self {
+        self.element("Identifier", Namespace::Xmp)
+            .unordered_array(id.into_iter().map(Text));
+        self
+    }
+
+    /// Write the `xmp:Identifier` property with an `xmpidq:Scheme` qualifier
+    /// on each item.
+    ///
+    /// Takes pairs of the scheme (e.g. `"ISBN"`) and the identifier.
+    pub fn xmp_identifier_with_schemes<'a>(
         &mut self,
-        id: impl IntoIterator<Item = &'a str>,
+        ids: impl IntoIterator<Item = (&'a str, &'a str)>,
     ) -> &mut Self {
-        self.element("Identifier", Namespace::Xmp).unordered_array(id);
+        let mut array = self
+            .element("Identifier", Namespace::Xmp)
+            .array(RdfCollectionType::Bag);
+        for (scheme, id) in ids {
+            array
+                .qualified_element(id)
+                .element("Scheme", Namespace::XmpIdq)
+                .value(scheme);
+        }
+        drop(array);
         self
     }
 
@@ -348,6 +1311,22 @@ impl<'n> XmpWriter<'n> {
         self
     }
 
+    /// Write the `xmp:CreateDate`, `xmp:ModifyDate`, `xmp:MetadataDate`, and
+    /// `dc:date` properties consistently.
+    ///
+    /// The modification and metadata dates are set to `modified`, or to
+    /// `created` if the resource was not modified since its creation. The
+    /// `dc:date` sequence holds the creation date. Validators may reject
+    /// packets in which these properties diverge.
+    pub fn dates(&mut self, created: DateTime, modified: Option<DateTime>) -> &mut Self {
+        let modified = modified.unwrap_or(created);
+        self.create_date(created);
+        self.modify_date(modified);
+        self.metadata_date(modified);
+        self.date([created]);
+        self
+    }
+
     /// Write the `xmp:Nickname` property.
     ///
     /// A short informal name for the resource.
@@ -359,11 +1338,33 @@ impl<'n> XmpWriter<'n> {
     /// Write the `xmp:Rating` property.
     ///
     /// A user-assigned rating of the resource.
-    pub fn rating(&mut self, rating: i64) -> &mut Self {
+    pub fn rating(&mut self, rating: Rating) -> &mut Self {
+        self.element("Rating", Namespace::Xmp).value(rating);
+        self
+    }
+
+    /// Write the `xmp:Rating` property from a raw integer.
+    ///
+    /// Unlike [`XmpWriter::rating`], the value is not checked. Valid ratings
+    /// are -1 (rejected) to 5 stars, with 0 for unrated resources.
+    pub fn rating_raw(&mut self, rating: i64) -> &mut Self {
         self.element("Rating", Namespace::Xmp).value(rating);
         self
     }
 
+    /// Write the `xmp:Rating` property with a fractional value.
+    ///
+    /// The rating must be between -1 (rejected) and 5 stars. Returns an
+    /// error without writing the property otherwise.
+    pub fn try_rating(&mut self, rating: f64) -> Result<&mut Self, RatingError> {
+        if !(-1.0..=5.0).contains(&rating) {
+            return Err(RatingError { value: rating });
+        }
+
+        self.element("Rating", Namespace::Xmp).value(rating);
+        Ok(self)
+    }
+
     /// Start writing the `xmp:Thumbnails` property.
     ///
     /// A thumbnail image of the resource.
@@ -397,8 +1398,12 @@ impl XmpWriter<'_> {
     /// Write the `xmpRights:Owner` property.
     ///
     /// A list of people or organizations owning the resource.
-    pub fn owner<'a>(&mut self, owner: impl IntoIterator<Item = &'a str>) -> &mut Self {
-        self.element("Owner", Namespace::XmpRights).unordered_array(owner);
+    pub fn owner<'a>(
+        &mut self,
+        owner: impl IntoIterator<Item = impl Into<ProperName<'a>>>,
+    ) -> &mut Self {
+        self.element("Owner", Namespace::XmpRights)
+            .unordered_array(owner.into_iter().map(Into::into));
         self
     }
 
@@ -417,8 +1422,9 @@ impl XmpWriter<'_> {
     /// Write the `xmpRights:WebStatement` property.
     ///
     /// A URL with a rights management statement.
-    pub fn web_statement(&mut self, statement: &str) -> &mut Self {
-        self.element("WebStatement", Namespace::XmpRights).value(statement);
+    pub fn web_statement<'a>(&mut self, statement: impl Into<Uri<'a>>) -> &mut Self {
+        self.element("WebStatement", Namespace::XmpRights)
+            .value(statement.into());
         self
     }
 }
@@ -488,16 +1494,16 @@ impl<'n> XmpWriter<'n> {
     /// Write the `xmpMM:ManageTo` property.
     ///
     /// The URI of the document in the management system.
-    pub fn manage_to(&mut self, uri: &str) -> &mut Self {
-        self.element("ManageTo", Namespace::XmpMedia).value(uri);
+    pub fn manage_to<'a>(&mut self, uri: impl Into<Uri<'a>>) -> &mut Self {
+        self.element("ManageTo", Namespace::XmpMedia).value(uri.into());
         self
     }
 
     /// Write the `xmpMM:ManageUI` property.
     ///
     /// A web page that allows the user to manage the document.
-    pub fn manage_ui(&mut self, uri: &str) -> &mut Self {
-        self.element("ManageUI", Namespace::XmpMedia).value(uri);
+    pub fn manage_ui<'a>(&mut self, uri: impl Into<Uri<'a>>) -> &mut Self {
+        self.element("ManageUI", Namespace::XmpMedia).value(uri.into());
         self
     }
 
@@ -617,11 +1623,12 @@ impl<'n> XmpWriter<'n> {
     /// Write the `xmpTPg:PlateNames` property.
     ///
     /// The names of the plates needed to print the document.
-    pub fn plate_names<'a>(
+    pub fn plate_names(
         &mut self,
-        names: impl IntoIterator<Item = &'a str>,
+        names: impl IntoIterator<Item = impl AsRef<str>>,
     ) -> &mut Self {
-        self.element("PlateNames", Namespace::XmpPaged).ordered_array(names);
+        self.element("PlateNames", Namespace::XmpPaged)
+            .ordered_array(names.into_iter().map(Text));
         self
     }
 }
@@ -630,9 +1637,13 @@ impl<'n> XmpWriter<'n> {
 
 /// XMPIDQ.
 impl XmpWriter<'_> {
-    /// Write the `xmpidq:GImg` property.
+    /// Write the `xmpidq:Scheme` property.
     ///
     /// Identifies the scheme of the [`XmpWriter::xmp_identifier`] property.
+    /// The qualifier belongs on the individual identifiers instead of the
+    /// top level, so this produces metadata that consumers will not associate
+    /// with the identifiers.
+    #[deprecated = "use `XmpWriter::xmp_identifier_with_schemes` instead"]
     pub fn idq_scheme(&mut self, scheme: &str) -> &mut Self {
         self.element("Scheme", Namespace::XmpIdq).value(scheme);
         self
@@ -651,9 +1662,16 @@ impl XmpWriter<'_> {
 
     /// Write the `pdf:PDFVersion` property.
     ///
-    /// The version of the PDF specification to which the document conforms
-    /// (e.g. `"1.0", "1.7"`)
-    pub fn pdf_version(&mut self, version: &str) -> &mut Self {
+    /// The version of the PDF specification to which the document conforms.
+    pub fn pdf_version(&mut self, version: PdfVersion) -> &mut Self {
+        self.element("PDFVersion", Namespace::AdobePdf).value(version);
+        self
+    }
+
+    /// Write the `pdf:PDFVersion` property from a string, e.g. `"1.7"`.
+    ///
+    /// Unlike [`XmpWriter::pdf_version`], the version is not checked.
+    pub fn pdf_version_str(&mut self, version: &str) -> &mut Self {
         self.element("PDFVersion", Namespace::AdobePdf).value(version);
         self
     }
@@ -661,16 +1679,17 @@ impl XmpWriter<'_> {
     /// Write the `pdf:Producer` property.
     ///
     /// The name of the application that created the PDF document.
-    pub fn producer(&mut self, producer: &str) -> &mut Self {
-        self.element("Producer", Namespace::AdobePdf).value(producer);
+    pub fn producer<'a>(&mut self, producer: impl Into<AgentName<'a>>) -> &mut Self {
+        self.element("Producer", Namespace::AdobePdf).value(producer.into());
         self
     }
 
     /// Write the `pdf:Trapped` property.
     ///
-    /// Whether the document has been trapped.
-    pub fn trapped(&mut self, trapped: bool) -> &mut Self {
-        self.element("Trapped", Namespace::AdobePdf).value(trapped);
+    /// Whether the document has been trapped. Accepts a [`Trapped`] value or
+    /// a `bool`.
+    pub fn trapped(&mut self, trapped: impl Into<Trapped>) -> &mut Self {
+        self.element("Trapped", Namespace::AdobePdf).value(trapped.into());
         self
     }
 }
@@ -684,6 +1703,29 @@ impl<'n> XmpWriter<'n> {
     #[cfg(feature = "pdfa")]
     pub fn pdfa_part(&mut self, part: i32) -> &mut Self {
         self.element("part", Namespace::PdfAId).value(part);
+        self.pdfa_id = Some(part);
+        self
+    }
+
+    /// Write the identification of a part of the PDF/A standard.
+    ///
+    /// Writes `pdfaid:part`, `pdfaid:conformance` if a level is given, and
+    /// `pdfaid:rev` for PDF/A-4 and later. For parts 1 to 3, properties
+    /// written so far that the part does not predefine are then described
+    /// in `pdfaExtension:schemas` like with
+    /// [`XmpWriter::write_extension_schemas_auto`], unless that property was
+    /// already written. Call this after all other properties.
+    #[cfg(feature = "pdfa")]
+    pub fn pdfa(&mut self, part: i32, conformance: Option<&str>) -> &mut Self {
+        self.pdfa_part(part);
+        if let Some(conformance) = conformance {
+            self.pdfa_conformance(conformance);
+        }
+        if part >= 4 {
+            self.element("rev", Namespace::PdfAId).value(2020);
+        } else {
+            self.describe_extension_schemas(part);
+        }
         self
     }
 
@@ -735,6 +1777,55 @@ impl<'n> XmpWriter<'n> {
         self
     }
 
+    /// Write the identification of a PDF/X standard.
+    ///
+    /// Writes the `GTS_PDFXVersion` property and, for PDF/X-1a, the
+    /// `GTS_PDFXConformance` property. Also makes [`XmpWriter::finish_with`]
+    /// fail with [`FinishError::MissingProperties`] unless `dc:title`,
+    /// `xmp:CreateDate`, `xmp:ModifyDate`, and `pdf:Trapped` are written, as
+    /// PDF/X requires.
+    pub fn pdfx(&mut self, version: PdfXVersion) -> &mut Self {
+        self.element("GTS_PDFXVersion", version.namespace())
+            .value(version.version());
+        if let Some(conformance) = version.conformance() {
+            self.element("GTS_PDFXConformance", version.namespace())
+                .value(conformance);
+        }
+        self.pdfx = true;
+        self
+    }
+
+    /// Write the identification of a part of the PDF/UA standard.
+    ///
+    /// Writes the `pdfuaid:part` property. Also makes
+    /// [`XmpWriter::finish_with`] fail with [`FinishError::MissingProperties`]
+    /// unless `dc:title` is written, as PDF/UA requires.
+    ///
+    /// PDF/A-1 to PDF/A-3 don't predefine the `pdfuaid` schema. If one of
+    /// these parts was already written with [`XmpWriter::pdfa_part`], the
+    /// schema is described in `pdfaExtension:schemas`. If that property was
+    /// already written without describing the schema,
+    /// [`XmpError::UndescribedSchema`] is recorded. If no part was written
+    /// yet, call [`XmpWriter::pdfa`] after this method to describe it.
+    pub fn pdfua(&mut self, part: i32) -> &mut Self {
+        self.pdfua_part(part);
+        self.pdfua = true;
+
+        #[cfg(feature = "pdfa")]
+        if self.pdfa_id.is_some_and(|part| part <= 3)
+            && !self.is_described(&Namespace::PdfUAId)
+        {
+            if self.has_property(Namespace::PdfAExtension, "schemas") {
+                let url = Namespace::PdfUAId.url().into();
+                self.errors.push(XmpError::UndescribedSchema(url));
+            } else {
+                self.extension_schemas().pdfuaid();
+            }
+        }
+
+        self
+    }
+
     /// Write the `pdfuaid:part` property.
     ///
     /// The part of the PDF/UA standard to which the document conforms (e.g.
@@ -765,6 +1856,7 @@ impl<'n> XmpWriter<'n> {
 /// A self-contained thumbnail image.
 ///
 /// Created by [`ThumbnailsWriter::add_thumbnail`].
+#[derive(Debug)]
 pub struct ThumbnailWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
 }
@@ -805,13 +1897,40 @@ impl<'a, 'n: 'a> ThumbnailWriter<'a, 'n> {
         self.stc.element("image", Namespace::XmpImage).value(image);
         self
     }
+
+    /// Write the `xmpGImg:image` property from the bytes of a JPEG image.
+    ///
+    /// The bytes are base64-encoded directly into the packet, see [`Base64`].
+    /// Also writes the `xmpGImg:format` property with the value "JPEG" unless
+    /// it was already written. Requires the `base64` feature.
+    #[cfg(feature = "base64")]
+    pub fn image_data(&mut self, jpeg: &[u8]) -> &mut Self {
+        if !self.stc.has_field(&Namespace::XmpImage, "format") {
+            self.format_jpeg();
+        }
+        self.stc
+            .element("image", Namespace::XmpImage)
+            .value(Base64::new(jpeg));
+        self
+    }
 }
 
 deref!('a, 'n, ThumbnailWriter<'a, 'n> => Struct<'a, 'n>, stc);
 
+impl Drop for ThumbnailWriter<'_, '_> {
+    fn drop(&mut self) {
+        self.stc.require(
+            "Thumbnail",
+            Namespace::XmpImage,
+            &["format", "width", "height", "image"],
+        );
+    }
+}
+
 /// Write a set of thumbnails.
 ///
 /// Created by [`XmpWriter::thumbnails`].
+#[derive(Debug)]
 pub struct ThumbnailsWriter<'a, 'n: 'a> {
     array: Array<'a, 'n>,
 }
@@ -832,6 +1951,7 @@ deref!('a, 'n, ThumbnailsWriter<'a, 'n> => Array<'a, 'n>, array);
 /// Writer for a reference to a resource.
 ///
 /// Created by [`XmpWriter::derived_from`], [`XmpWriter::managed_from`], or [`ResourceRefsWriter::add_ref`].
+#[derive(Debug)]
 pub struct ResourceRefWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
 }
@@ -844,13 +1964,13 @@ impl<'a, 'n: 'a> ResourceRefWriter<'a, 'n> {
     /// Write the `stRef:alternatePaths` property.
     ///
     /// Fallback paths to the resource.
-    pub fn alternate_paths<'b>(
+    pub fn alternate_paths(
         &mut self,
-        paths: impl IntoIterator<Item = &'b str>,
+        paths: impl IntoIterator<Item = impl AsRef<str>>,
     ) -> &mut Self {
         self.stc
             .element("alternatePaths", Namespace::XmpResourceRef)
-            .ordered_array(paths);
+            .ordered_array(paths.into_iter().map(Text));
         self
     }
 
@@ -909,16 +2029,20 @@ impl<'a, 'n: 'a> ResourceRefWriter<'a, 'n> {
     /// Write the `stRef:manageTo` property.
     ///
     /// The URI of the resource prior to being managed. See [`XmpWriter::manage_to`].
-    pub fn manage_to(&mut self, uri: &str) -> &mut Self {
-        self.stc.element("manageTo", Namespace::XmpResourceRef).value(uri);
+    pub fn manage_to<'b>(&mut self, uri: impl Into<Uri<'b>>) -> &mut Self {
+        self.stc
+            .element("manageTo", Namespace::XmpResourceRef)
+            .value(uri.into());
         self
     }
 
     /// Write the `stRef:manageUI` property.
     ///
     /// An URI to the user interface of the application that manages the resource. See [`XmpWriter::manage_ui`].
-    pub fn manage_ui(&mut self, uri: &str) -> &mut Self {
-        self.stc.element("manageTo", Namespace::XmpResourceRef).value(uri);
+    pub fn manage_ui<'b>(&mut self, uri: impl Into<Uri<'b>>) -> &mut Self {
+        self.stc
+            .element("manageUI", Namespace::XmpResourceRef)
+            .value(uri.into());
         self
     }
 
@@ -987,6 +2111,7 @@ deref!('a, 'n, ResourceRefWriter<'a, 'n> => Struct<'a, 'n>, stc);
 /// Writer for a resource reference array.
 ///
 /// Created by [`XmpWriter::ingredients`].
+#[derive(Debug)]
 pub struct ResourceRefsWriter<'a, 'n: 'a> {
     array: Array<'a, 'n>,
 }
@@ -1000,6 +2125,14 @@ impl<'a, 'n: 'a> ResourceRefsWriter<'a, 'n> {
     pub fn add_ref(&mut self) -> ResourceRefWriter<'_, 'n> {
         ResourceRefWriter::start(self.array.element().obj())
     }
+
+    /// Add a reference with an `rdf:nodeID` to the array.
+    ///
+    /// Other properties like `xmpMM:DerivedFrom` can then point to the same
+    /// reference using [`Element::node_ref`].
+    pub fn add_ref_with_node_id(&mut self, id: &str) -> ResourceRefWriter<'_, 'n> {
+        ResourceRefWriter::start(self.array.element().obj_with_node_id(id))
+    }
 }
 
 deref!('a, 'n, ResourceRefsWriter<'a, 'n> => Array<'a, 'n>, array);
@@ -1007,6 +2140,7 @@ deref!('a, 'n, ResourceRefsWriter<'a, 'n> => Array<'a, 'n>, array);
 /// Writer for an event that occurred to a resource.
 ///
 /// Created by [`VersionWriter::event`] and [`ResourceEventsWriter::add_event`].
+#[derive(Debug)]
 pub struct ResourceEventWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
 }
@@ -1052,10 +2186,10 @@ impl<'a, 'n: 'a> ResourceEventWriter<'a, 'n> {
     /// Write the `stEvt:softwareAgent` property.
     ///
     /// The name of the software agent that performed the action.
-    pub fn software_agent(&mut self, agent: &str) -> &mut Self {
+    pub fn software_agent<'b>(&mut self, agent: impl Into<AgentName<'b>>) -> &mut Self {
         self.stc
             .element("softwareAgent", Namespace::XmpResourceEvent)
-            .value(agent);
+            .value(agent.into());
         self
     }
 
@@ -1070,9 +2204,17 @@ impl<'a, 'n: 'a> ResourceEventWriter<'a, 'n> {
 
 deref!('a, 'n, ResourceEventWriter<'a, 'n> => Struct<'a, 'n>, stc);
 
+impl Drop for ResourceEventWriter<'_, '_> {
+    fn drop(&mut self) {
+        self.stc
+            .require("ResourceEvent", Namespace::XmpResourceEvent, &["action"]);
+    }
+}
+
 /// Writer for a resource event array.
 ///
 /// Created by [`XmpWriter::history`].
+#[derive(Debug)]
 pub struct ResourceEventsWriter<'a, 'n: 'a> {
     array: Array<'a, 'n>,
 }
@@ -1086,6 +2228,57 @@ impl<'a, 'n: 'a> ResourceEventsWriter<'a, 'n> {
     pub fn add_event(&mut self) -> ResourceEventWriter<'_, 'n> {
         ResourceEventWriter::start(self.array.element().obj())
     }
+
+    /// Add a `created` event with the agent that created the resource, the
+    /// date, and the instance ID of the created resource.
+    pub fn add_created<'b>(
+        &mut self,
+        agent: impl Into<AgentName<'b>>,
+        when: DateTime,
+        instance_id: &str,
+    ) -> &mut Self {
+        self.add_action(ResourceEventAction::Created, agent, when, instance_id)
+    }
+
+    /// Add a `converted` event with the agent that converted the resource,
+    /// the date, and the instance ID of the converted resource.
+    ///
+    /// Use [`Self::add_event`] to also describe the conversion with
+    /// [`ResourceEventWriter::parameters`].
+    pub fn add_converted<'b>(
+        &mut self,
+        agent: impl Into<AgentName<'b>>,
+        when: DateTime,
+        instance_id: &str,
+    ) -> &mut Self {
+        self.add_action(ResourceEventAction::Converted, agent, when, instance_id)
+    }
+
+    /// Add a `saved` event with the agent that saved the resource, the date,
+    /// and the instance ID of the saved resource.
+    pub fn add_saved<'b>(
+        &mut self,
+        agent: impl Into<AgentName<'b>>,
+        when: DateTime,
+        instance_id: &str,
+    ) -> &mut Self {
+        self.add_action(ResourceEventAction::Saved, agent, when, instance_id)
+    }
+
+    fn add_action<'b>(
+        &mut self,
+        action: ResourceEventAction,
+        agent: impl Into<AgentName<'b>>,
+        when: DateTime,
+        instance_id: &str,
+    ) -> &mut Self {
+        self.add_event()
+            .action(action)
+            .instance_id(instance_id)
+            .software_agent(agent)
+            .when(when);
+        self
+    }
 }
 
 deref!('a, 'n, ResourceEventsWriter<'a, 'n> => Array<'a, 'n>, array);
@@ -1094,6 +2287,7 @@ deref!('a, 'n, ResourceEventsWriter<'a, 'n> => Array<'a, 'n>, array);
 ///
 /// Use the `Deref` impl to access the underlying [`Struct`] and add properties.
 /// Created by [`PantryWriter::add_item`].
+#[derive(Debug)]
 pub struct PantryItemWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
 }
@@ -1108,11 +2302,30 @@ impl<'a, 'n: 'a> PantryItemWriter<'a, 'n> {
         self.stc.element("instanceID", Namespace::XmpMedia).value(id);
         self
     }
+
+    /// Copy all properties of another writer into the item.
+    ///
+    /// A Pantry item holds the complete metadata of an ingredient, so this
+    /// is the easiest way to write it when the ingredient's metadata was
+    /// built with a separate [`XmpWriter`] or [`XmpFragment`]. The
+    /// namespaces, errors, and missing struct fields of the other writer are
+    /// adopted like with [`XmpWriter::append`].
+    pub fn include(&mut self, other: &XmpWriter<'n>) -> &mut Self {
+        self.stc.include(other);
+        self
+    }
 }
 
 deref!('a, 'n, PantryItemWriter<'a, 'n> => Struct<'a, 'n>, stc);
 
+impl Drop for PantryItemWriter<'_, '_> {
+    fn drop(&mut self) {
+        self.stc.require("PantryItem", Namespace::XmpMedia, &["instanceID"]);
+    }
+}
+
 /// Writer for a Pantry array.
+#[derive(Debug)]
 pub struct PantryWriter<'a, 'n: 'a> {
     array: Array<'a, 'n>,
 }
@@ -1133,6 +2346,7 @@ deref!('a, 'n, PantryWriter<'a, 'n> => Array<'a, 'n>, array);
 /// Writer for a version struct.
 ///
 /// Created by [`VersionsWriter::add_version`].
+#[derive(Debug)]
 pub struct VersionWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
 }
@@ -1187,6 +2401,7 @@ deref!('a, 'n, VersionWriter<'a, 'n> => Struct<'a, 'n>, stc);
 /// Writer for a versions array.
 ///
 /// Created by [`XmpWriter::version_ref`].
+#[derive(Debug)]
 pub struct VersionsWriter<'a, 'n: 'a> {
     array: Array<'a, 'n>,
 }
@@ -1200,6 +2415,48 @@ impl<'a, 'n: 'a> VersionsWriter<'a, 'n> {
     pub fn add_version(&mut self) -> VersionWriter<'_, 'n> {
         VersionWriter::start(self.array.element().obj())
     }
+
+    /// Add a version with its number, the person or organization that
+    /// created it, the date it was created, and optional comments.
+    pub fn add(
+        &mut self,
+        version: &str,
+        modifier: &str,
+        date: DateTime,
+        comments: Option<&str>,
+    ) -> &mut Self {
+        self.add_fields(version, modifier, date, comments);
+        self
+    }
+
+    /// Add a version like [`Self::add`] and describe the event that created
+    /// it in `stVer:event` with the given closure.
+    pub fn add_with_event(
+        &mut self,
+        version: &str,
+        modifier: &str,
+        date: DateTime,
+        comments: Option<&str>,
+        event: impl FnOnce(&mut ResourceEventWriter<'_, 'n>),
+    ) -> &mut Self {
+        event(&mut self.add_fields(version, modifier, date, comments).event());
+        self
+    }
+
+    fn add_fields(
+        &mut self,
+        version: &str,
+        modifier: &str,
+        date: DateTime,
+        comments: Option<&str>,
+    ) -> VersionWriter<'_, 'n> {
+        let mut writer = self.add_version();
+        writer.version(version).modifier(modifier).modify_date(date);
+        if let Some(comments) = comments {
+            writer.comments(comments);
+        }
+        writer
+    }
 }
 
 deref!('a, 'n, VersionsWriter<'a, 'n> => Array<'a, 'n>, array);
@@ -1207,6 +2464,7 @@ deref!('a, 'n, VersionsWriter<'a, 'n> => Array<'a, 'n>, array);
 /// Writer for a job struct.
 ///
 /// Created by [`JobsWriter::add_job`].
+#[derive(Debug)]
 pub struct JobWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
 }
@@ -1246,6 +2504,7 @@ deref!('a, 'n, JobWriter<'a, 'n> => Struct<'a, 'n>, stc);
 /// Writer for a job array.
 ///
 /// Created by [`XmpWriter::jobs`].
+#[derive(Debug)]
 pub struct JobsWriter<'a, 'n: 'a> {
     array: Array<'a, 'n>,
 }
@@ -1259,6 +2518,13 @@ impl<'a, 'n: 'a> JobsWriter<'a, 'n> {
     pub fn add_job(&mut self) -> JobWriter<'_, 'n> {
         JobWriter::start(self.array.element().obj())
     }
+
+    /// Add a job with its unique identifier, its name, and a reference to
+    /// an external job management file.
+    pub fn add(&mut self, id: &str, name: &str, url: &str) -> &mut Self {
+        self.add_job().id(id).name(name).url(url);
+        self
+    }
 }
 
 deref!('a, 'n, JobsWriter<'a, 'n> => Array<'a, 'n>, array);
@@ -1266,13 +2532,15 @@ deref!('a, 'n, JobsWriter<'a, 'n> => Array<'a, 'n>, array);
 /// A writer for colorant structs.
 ///
 /// Created by [`ColorantsWriter::add_colorant`].
+#[derive(Debug)]
 pub struct ColorantWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
+    mode: Option<ColorantMode>,
 }
 
 impl<'a, 'n: 'a> ColorantWriter<'a, 'n> {
     fn start(stc: Struct<'a, 'n>) -> Self {
-        Self { stc }
+        Self { stc, mode: None }
     }
 
     /// Write the `xmpG:type` property.
@@ -1293,8 +2561,10 @@ impl<'a, 'n: 'a> ColorantWriter<'a, 'n> {
 
     /// Write the `xmpG:colorantMode` property.
     ///
-    /// In which color space this colorant is defined.
+    /// In which color space this colorant is defined. Components of other
+    /// color spaces are recorded as errors, see [`XmpWriter::errors`].
     pub fn colorant_mode(&mut self, mode: ColorantMode) -> &mut Self {
+        self.mode = Some(mode);
         self.stc.element("colorantMode", Namespace::XmpColorant).value(mode);
         self
     }
@@ -1382,9 +2652,29 @@ impl<'a, 'n: 'a> ColorantWriter<'a, 'n> {
 
 deref!('a, 'n, ColorantWriter<'a, 'n> => Struct<'a, 'n>, stc);
 
+impl Drop for ColorantWriter<'_, '_> {
+    fn drop(&mut self) {
+        let Some(mode) = self.mode else { return };
+        for other in [ColorantMode::CMYK, ColorantMode::RGB, ColorantMode::Lab] {
+            if other == mode {
+                continue;
+            }
+            for field in other.components() {
+                if self.stc.has_field(&Namespace::XmpColorant, field) {
+                    self.stc.error(XmpError::ColorantMismatch {
+                        mode,
+                        field: format!("xmpG:{field}"),
+                    });
+                }
+            }
+        }
+    }
+}
+
 /// Writer for an array of colorants.
 ///
 /// Created by [`XmpWriter::colorants`].
+#[derive(Debug)]
 pub struct ColorantsWriter<'a, 'n: 'a> {
     array: Array<'a, 'n>,
 }
@@ -1398,6 +2688,28 @@ impl<'a, 'n> ColorantsWriter<'a, 'n> {
     pub fn add_colorant(&mut self) -> ColorantWriter<'_, 'n> {
         ColorantWriter::start(self.array.element().obj())
     }
+
+    /// Add a colorant from its data.
+    pub fn add(&mut self, colorant: &Colorant) -> &mut Self {
+        let mut writer = self.add_colorant();
+        writer
+            .swatch_name(&colorant.swatch_name)
+            .type_(colorant.kind)
+            .colorant_mode(colorant.components.mode());
+        match colorant.components {
+            ColorantComponents::Cmyk { cyan, magenta, yellow, black } => {
+                writer.cyan(cyan).magenta(magenta).yellow(yellow).black(black);
+            }
+            ColorantComponents::Rgb { red, green, blue } => {
+                writer.red(red).green(green).blue(blue);
+            }
+            ColorantComponents::Lab { l, a, b } => {
+                writer.l(l).a(a).b(b);
+            }
+        }
+        drop(writer);
+        self
+    }
 }
 
 deref!('a, 'n, ColorantsWriter<'a, 'n> => Array<'a, 'n>, array);
@@ -1405,6 +2717,7 @@ deref!('a, 'n, ColorantsWriter<'a, 'n> => Array<'a, 'n>, array);
 /// Writer for a dimensions struct.
 ///
 /// Created by [`XmpWriter::max_page_size`].
+#[derive(Debug)]
 pub struct DimensionsWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
 }
@@ -1437,6 +2750,13 @@ impl<'a, 'n> DimensionsWriter<'a, 'n> {
         self.stc.element("unit", Namespace::XmpDimensions).value(unit);
         self
     }
+
+    /// Write the `stDim:w`, `stDim:h`, and `stDim:unit` properties.
+    pub fn dimensions(&mut self, dimensions: &Dimensions) -> &mut Self {
+        self.width(dimensions.width)
+            .height(dimensions.height)
+            .unit(dimensions.unit)
+    }
 }
 
 deref!('a, 'n, DimensionsWriter<'a, 'n> => Struct<'a, 'n>, stc);
@@ -1444,6 +2764,7 @@ deref!('a, 'n, DimensionsWriter<'a, 'n> => Struct<'a, 'n>, stc);
 /// Writer for a font struct.
 ///
 /// Created by [`XmpWriter::fonts`].
+#[derive(Debug)]
 pub struct FontWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
 }
@@ -1456,13 +2777,13 @@ impl<'a, 'n: 'a> FontWriter<'a, 'n> {
     /// Write the `stFnt:childFontFiles` property.
     ///
     /// An array of font files that make up this font.
-    pub fn child_font_files<'b>(
+    pub fn child_font_files(
         &mut self,
-        files: impl IntoIterator<Item = &'b str>,
+        files: impl IntoIterator<Item = impl AsRef<str>>,
     ) -> &mut Self {
         self.stc
             .element("childFontFiles", Namespace::XmpFont)
-            .ordered_array(files);
+            .ordered_array(files.into_iter().map(Text));
         self
     }
 
@@ -1533,6 +2854,7 @@ deref!('a, 'n, FontWriter<'a, 'n> => Struct<'a, 'n>, stc);
 /// Writer for an array of fonts.
 ///
 /// Created by [`XmpWriter::fonts`].
+#[derive(Debug)]
 pub struct FontsWriter<'a, 'n: 'a> {
     array: Array<'a, 'n>,
 }
@@ -1549,3 +2871,148 @@ impl<'a, 'n: 'a> FontsWriter<'a, 'n> {
 }
 
 deref!('a, 'n, FontsWriter<'a, 'n> => Array<'a, 'n>, array);
+
+#[cfg(test)]
+mod tests {
+    use crate::{Namespace, StructForm, XmpPacket, XmpWriter};
+
+    #[test]
+    fn reset_discards_the_packet() {
+        let mut writer = XmpWriter::new();
+        writer.set_struct_form(StructForm::Compact).set_write_once(true);
+        let id = writer.register_namespace("Example", "ex", "http://example.com/ns/");
+        writer.set_about("uuid:first").pdfua(1).label("first").label("again");
+        writer.element("bad name", id).value("value");
+        writer.thumbnails().add_thumbnail();
+
+        writer.reset();
+        assert!(writer.is_empty());
+        assert!(!writer.has_properties());
+        assert_eq!(writer.property_count(), 0);
+        assert_eq!(writer.errors(), []);
+        assert!(writer.duplicates().is_empty());
+        assert!(writer.missing_fields().is_empty());
+        assert_eq!(writer.namespaces().count(), 0);
+
+        writer.element("prop", id).obj().field("field", id, "value");
+        let packet = writer.finish_with(&Default::default()).unwrap();
+        assert!(packet.contains(r#"rdf:about="""#));
+        assert!(packet.contains(r#"<ex:prop><rdf:Description ex:field="value"/>"#));
+    }
+
+    #[test]
+    fn about_is_escaped() {
+        let mut writer = XmpWriter::new();
+        writer.set_about("uuid:\"a\"&<b>");
+        let packet = writer.clone().finish(None);
+        assert!(packet.contains(r#"rdf:about="uuid:&quot;a&quot;&amp;&lt;b>""#));
+        assert!(XmpPacket::parse(&packet).is_ok());
+
+        let packet = writer.finish(Some("x\ny"));
+        assert!(packet.contains(r#"rdf:about="x&#xA;y""#));
+    }
+
+    #[test]
+    fn finish_keeps_properties_in_place() {
+        let mut writer = XmpWriter::with_capacity(1024);
+        writer.creator_tool("tool").label("label");
+        let estimate = writer.estimated_final_len();
+        let ptr = writer.buf.as_ptr();
+        let packet = writer.finish(None);
+        assert_eq!(packet.as_ptr(), ptr);
+        assert_eq!(packet.len(), estimate);
+        let packet = XmpPacket::parse(&packet).unwrap();
+        assert_eq!(packet.get(&Namespace::Xmp, "Label"), Some(&"label".into()));
+
+        let mut writer = XmpWriter::new();
+        for i in 0..20 {
+            let url = format!("http://example.com/long/namespace/{i}/");
+            let id = writer.register_namespace("Long", format!("ns{i}"), url);
+            writer.element("prop", id).value("value");
+        }
+        let estimate = writer.estimated_final_len();
+        let packet = writer.finish(None);
+        assert_eq!(packet.len(), estimate);
+        assert_eq!(XmpPacket::parse(&packet).unwrap().len(), 20);
+    }
+
+    #[test]
+    fn unused_elements_are_not_properties() {
+        let mut writer = XmpWriter::new();
+        writer.set_write_once(true);
+        drop(writer.element("Label", Namespace::Xmp));
+        writer.element("Nickname", Namespace::Xmp).opt_value(None::<&str>);
+        assert!(!writer.has_property(Namespace::Xmp, "Label"));
+        assert!(!writer.has_property(Namespace::Xmp, "Nickname"));
+        assert_eq!(writer.property_count(), 0);
+        assert!(writer.is_empty());
+
+        writer.label("label");
+        writer.element("Nickname", Namespace::Xmp).opt_value(Some("nickname"));
+        assert_eq!(writer.property_count(), 2);
+        assert!(writer.duplicates().is_empty());
+        assert!(writer.finish_with(&Default::default()).is_ok());
+    }
+
+    #[test]
+    fn emptiness() {
+        let mut writer = XmpWriter::new();
+        assert!(writer.is_empty());
+        assert!(!writer.has_properties());
+
+        writer.comment("note");
+        assert!(!writer.is_empty());
+        assert!(!writer.has_properties());
+
+        writer.label("label");
+        assert!(writer.has_properties());
+        assert_eq!(writer.property_count(), 1);
+    }
+
+    #[test]
+    fn rollback_after_nested_struct() {
+        for form in [StructForm::ParseTypeResource, StructForm::Compact] {
+            let mut writer = XmpWriter::new();
+            writer.set_struct_form(form);
+            writer.label("kept");
+            let checkpoint = writer.checkpoint();
+
+            let mut stc = writer.element("DerivedFrom", Namespace::XmpMedia).obj();
+            stc.field("documentID", Namespace::XmpResourceRef, "discarded");
+            stc.element("nested", Namespace::XmpResourceRef).obj().field(
+                "inner",
+                Namespace::XmpResourceRef,
+                "discarded",
+            );
+            drop(stc);
+            writer.thumbnails().add_thumbnail();
+            writer.element("bad name", Namespace::Xmp).value("discarded");
+            assert!(!writer.missing_fields().is_empty());
+            assert!(!writer.errors().is_empty());
+
+            writer.rollback(checkpoint);
+            assert!(writer.has_property(Namespace::Xmp, "Label"));
+            assert!(!writer.has_property(Namespace::XmpMedia, "DerivedFrom"));
+            assert!(writer.missing_fields().is_empty());
+            assert_eq!(writer.errors(), []);
+            assert!(writer.namespaces().eq([&Namespace::Xmp]));
+
+            writer.element("DerivedFrom", Namespace::XmpMedia).obj().field(
+                "documentID",
+                Namespace::XmpResourceRef,
+                "written",
+            );
+            assert!(writer.duplicates().is_empty());
+
+            let packet = writer.finish(None);
+            assert!(!packet.contains("discarded"));
+            let parsed = XmpPacket::parse(&packet).unwrap();
+            let derived = parsed.get(&Namespace::XmpMedia, "DerivedFrom").unwrap();
+            assert_eq!(
+                derived.field(&Namespace::XmpResourceRef, "documentID"),
+                Some(&"written".into())
+            );
+            assert_eq!(parsed.len(), 2);
+        }
+    }
+}
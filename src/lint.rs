@@ -0,0 +1,133 @@
+//! Lints for XMP packets.
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::{Value, XmpPacket, XmpWriter};
+
+impl XmpPacket<'_> {
+    /// Check the packet for problems and return structured findings.
+    ///
+    /// If the packet declares a PDF/A part with `pdfaid:part`, it is checked
+    /// against the metadata rules of that part as veraPDF would: the
+    /// identification, the types of the predefined properties, and the
+    /// completeness of the extension schemas. Requires the `pdfa` feature for
+    /// these checks.
+    ///
+    /// Independently of PDF/A, language alternatives without a leading
+    /// `x-default` item are reported as warnings.
+    pub fn lint(&self) -> Vec<LintFinding> {
+        let mut findings = vec![];
+        for property in self.properties() {
+            lint_x_default(&property.qname(), &property.value, &mut findings);
+        }
+
+        #[cfg(feature = "pdfa")]
+        if let Some(part) = self
+            .get(&crate::Namespace::PdfAId, "part")
+            .and_then(crate::Value::as_text)
+            .and_then(|part| part.trim().parse().ok())
+        {
+            crate::conformance::lint_pdfa(self, part, &mut findings);
+        }
+
+        findings
+    }
+}
+
+impl XmpWriter<'_> {
+    /// Check the metadata written so far for problems. See
+    /// [`XmpPacket::lint`].
+    ///
+    /// If the written metadata cannot be parsed, the only finding is an
+    /// error without a property that describes the problem.
+    pub fn lint(&self) -> Vec<LintFinding> {
+        let mut packet = String::new();
+        self.write_packet(&mut packet, None);
+        packet.push_str("<?xpacket end=\"r\"?>");
+        match XmpPacket::parse(&packet) {
+            Ok(packet) => packet.lint(),
+            Err(err) => vec![LintFinding {
+                severity: Severity::Error,
+                rule: None,
+                property: String::new(),
+                message: format!("packet is malformed: {err}"),
+            }],
+        }
+    }
+}
+
+/// Report language alternatives in the value that don't start with an
+/// `x-default` item.
+fn lint_x_default(qname: &str, value: &Value, findings: &mut Vec<LintFinding>) {
+    let mut warn = |message: &str| {
+        findings.push(LintFinding {
+            severity: Severity::Warning,
+            rule: None,
+            property: qname.into(),
+            message: message.into(),
+        })
+    };
+
+    match value {
+        Value::LangAlt(items) => {
+            match items.iter().position(|(lang, _)| lang == "x-default") {
+                Some(0) => {}
+                Some(_) => warn("has an x-default item that is not first"),
+                None => warn("has no x-default item"),
+            }
+        }
+        Value::Array(_, items) => {
+            for item in items {
+                lint_x_default(qname, item, findings);
+            }
+        }
+        Value::Struct(fields) => {
+            for field in fields {
+                lint_x_default(&field.qname(), &field.value, findings);
+            }
+        }
+        Value::Qualified(value, _) => lint_x_default(qname, value, findings),
+        _ => {}
+    }
+}
+
+/// A problem found by [`XmpPacket::lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    /// How severe the problem is.
+    pub severity: Severity,
+    /// The clause of the standard that is violated, e.g. `6.6.2.3.1` of
+    /// PDF/A-2, if any. Matches the rule numbering of veraPDF.
+    pub rule: Option<&'static str>,
+    /// The qualified name of the affected property, e.g. `pdfaid:part`, or
+    /// an empty string if the finding concerns the whole packet.
+    pub property: String,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl Display for LintFinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Error => f.write_str("error")?,
+            Severity::Warning => f.write_str("warning")?,
+        }
+        if let Some(rule) = self.rule {
+            write!(f, " [{rule}]")?;
+        }
+        if self.property.is_empty() {
+            write!(f, ": {}", self.message)
+        } else {
+            write!(f, ": {} {}", self.property, self.message)
+        }
+    }
+}
+
+/// The severity of a [`LintFinding`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// The packet violates a requirement.
+    Error,
+    /// The packet is valid, but likely not interpreted as intended.
+    Warning,
+}
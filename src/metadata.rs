@@ -0,0 +1,242 @@
+use crate::{DateTime, LangId, PdfVersion, XmpWriter};
+
+/// Plain metadata about a document.
+///
+/// This is a data-only representation of the most common properties that can
+/// be stored in configuration files or databases and turned into a packet with
+/// a single call. With the `serde` feature, it implements `Serialize` and
+/// `Deserialize`. All fields are optional and only non-empty fields are
+/// written.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct XmpMetadata {
+    /// The title, possibly in multiple languages (`dc:title`).
+    pub title: Vec<LocalizedText>,
+    /// The entities primarily responsible for the resource (`dc:creator`).
+    pub creators: Vec<String>,
+    /// A description, possibly in multiple languages (`dc:description`).
+    pub description: Vec<LocalizedText>,
+    /// Keywords or phrases describing the topic (`dc:subject`).
+    pub subject: Vec<String>,
+    /// Publishers of the resource (`dc:publisher`).
+    pub publishers: Vec<String>,
+    /// Informal rights statements (`dc:rights`).
+    pub rights: Vec<LocalizedText>,
+    /// Languages used in the resource as RFC 3066 tags (`dc:language`).
+    pub languages: Vec<String>,
+    /// An unambiguous reference to the resource (`dc:identifier`).
+    pub identifier: Option<String>,
+    /// Identifiers of the resource (`xmp:Identifier`).
+    pub identifiers: Vec<String>,
+    /// The creation date (`xmp:CreateDate`).
+    pub create_date: Option<DateTime>,
+    /// The last modification date (`xmp:ModifyDate`).
+    pub modify_date: Option<DateTime>,
+    /// The date the metadata was last changed (`xmp:MetadataDate`).
+    pub metadata_date: Option<DateTime>,
+    /// The application that created the resource (`xmp:CreatorTool`).
+    pub creator_tool: Option<String>,
+    /// The document ID (`xmpMM:DocumentID`).
+    pub document_id: Option<String>,
+    /// The instance ID (`xmpMM:InstanceID`).
+    pub instance_id: Option<String>,
+    /// Keywords as a single string (`pdf:Keywords`).
+    pub pdf_keywords: Option<String>,
+    /// The application that produced the PDF (`pdf:Producer`).
+    pub producer: Option<String>,
+    /// The PDF version (`pdf:PDFVersion`), e.g. `"1.7"` in serialized form.
+    pub pdf_version: Option<PdfVersion>,
+    /// The PDF/A identification (`pdfaid:part` and friends).
+    #[cfg(feature = "pdfa")]
+    pub pdfa: Option<PdfAIdentification>,
+    /// The PDF/UA part (`pdfuaid:part`).
+    pub pdfua_part: Option<i32>,
+}
+
+impl XmpMetadata {
+    /// Write all properties to a writer.
+    pub fn write(&self, writer: &mut XmpWriter) {
+        if !self.title.is_empty() {
+            writer.title(self.title.iter().map(LocalizedText::as_pair));
+        }
+        if !self.creators.is_empty() {
+            writer.creator(self.creators.iter().map(String::as_str));
+        }
+        if !self.description.is_empty() {
+            writer.description(self.description.iter().map(LocalizedText::as_pair));
+        }
+        if !self.subject.is_empty() {
+            writer.subject(self.subject.iter().map(String::as_str));
+        }
+        if !self.publishers.is_empty() {
+            writer.publisher(self.publishers.iter().map(String::as_str));
+        }
+        if !self.rights.is_empty() {
+            writer.rights(self.rights.iter().map(LocalizedText::as_pair));
+        }
+        if !self.languages.is_empty() {
+            writer.language(self.languages.iter().map(|lang| LangId(lang)));
+        }
+        if let Some(identifier) = &self.identifier {
+            writer.identifier(identifier);
+        }
+        if !self.identifiers.is_empty() {
+            writer.xmp_identifier(self.identifiers.iter().map(String::as_str));
+        }
+        if let Some(date) = self.create_date {
+            writer.create_date(date);
+        }
+        if let Some(date) = self.modify_date {
+            writer.modify_date(date);
+        }
+        if let Some(date) = self.metadata_date {
+            writer.metadata_date(date);
+        }
+        if let Some(tool) = &self.creator_tool {
+            writer.creator_tool(tool);
+        }
+        if let Some(id) = &self.document_id {
+            writer.document_id(id);
+        }
+        if let Some(id) = &self.instance_id {
+            writer.instance_id(id);
+        }
+        if let Some(keywords) = &self.pdf_keywords {
+            writer.pdf_keywords(keywords);
+        }
+        if let Some(producer) = &self.producer {
+            writer.producer(producer);
+        }
+        if let Some(version) = self.pdf_version {
+            writer.pdf_version(version);
+        }
+        #[cfg(feature = "pdfa")]
+        if let Some(pdfa) = &self.pdfa {
+            writer.pdfa_part(pdfa.part);
+            if let Some(conformance) = &pdfa.conformance {
+                writer.pdfa_conformance(conformance);
+            }
+            if let Some(amd) = &pdfa.amd {
+                writer.pdfa_amd(amd);
+            }
+            if let Some(corr) = &pdfa.corr {
+                writer.pdfa_corr(corr);
+            }
+        }
+        if let Some(part) = self.pdfua_part {
+            writer.pdfua_part(part);
+        }
+    }
+
+    /// Write the metadata into a new packet. See [`XmpWriter::finish`].
+    pub fn serialize(&self, about: Option<&str>) -> String {
+        let mut writer = XmpWriter::new();
+        self.write(&mut writer);
+        writer.finish(about)
+    }
+}
+
+/// A text in a specific language.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocalizedText {
+    /// The RFC 3066 language tag. `None` stands for `x-default`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub lang: Option<String>,
+    /// The text.
+    pub text: String,
+}
+
+impl LocalizedText {
+    /// Create a new text in the default language.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { lang: None, text: text.into() }
+    }
+
+    /// Create a new text in a specific language.
+    pub fn with_lang(lang: impl Into<String>, text: impl Into<String>) -> Self {
+        Self { lang: Some(lang.into()), text: text.into() }
+    }
+
+    pub(crate) fn as_pair(&self) -> (Option<LangId<'_>>, &str) {
+        (self.lang.as_deref().map(LangId), &self.text)
+    }
+}
+
+/// Identification of the PDF/A standard a document conforms to.
+#[cfg(feature = "pdfa")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PdfAIdentification {
+    /// The part of the standard (`pdfaid:part`).
+    pub part: i32,
+    /// The conformance level, e.g. `"B"` (`pdfaid:conformance`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub conformance: Option<String>,
+    /// The amendment (`pdfaid:amd`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub amd: Option<String>,
+    /// The corrigendum (`pdfaid:corr`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub corr: Option<String>,
+}
+
+/// Accessibility metadata in the schema.org vocabulary.
+///
+/// This mirrors the accessibility properties of EPUB publications so that
+/// they can be carried over into the packet of a PDF rendition. The
+/// properties are written to the `schema` namespace, which is registered
+/// with [`XmpWriter::register_namespace`]. Only non-empty fields are written.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Accessibility {
+    /// The senses needed to perceive the content, e.g. `"textual"` or
+    /// `"visual"` (`schema:accessMode`).
+    pub access_modes: Vec<String>,
+    /// Sets of access modes that each suffice to consume the content, as
+    /// comma-separated lists, e.g. `"textual,visual"`
+    /// (`schema:accessModeSufficient`).
+    pub access_modes_sufficient: Vec<String>,
+    /// Accessibility features of the content, e.g. `"alternativeText"` or
+    /// `"structuralNavigation"` (`schema:accessibilityFeature`).
+    pub features: Vec<String>,
+    /// Characteristics of the content that can be hazardous, e.g.
+    /// `"none"` or `"flashing"` (`schema:accessibilityHazard`).
+    pub hazards: Vec<String>,
+    /// A human-readable summary of the accessibility of the content
+    /// (`schema:accessibilitySummary`).
+    pub summary: Option<String>,
+}
+
+impl Accessibility {
+    /// The human-readable name of the schema.org namespace.
+    pub const NAME: &'static str = "Schema.org";
+
+    /// The prefix of the schema.org namespace.
+    pub const PREFIX: &'static str = "schema";
+
+    /// The URL of the schema.org namespace, as used by EPUB.
+    pub const URL: &'static str = "http://schema.org/";
+
+    /// Write all properties to a writer.
+    pub fn write(&self, writer: &mut XmpWriter) {
+        let schema = writer.register_namespace(Self::NAME, Self::PREFIX, Self::URL);
+        for (name, values) in [
+            ("accessMode", &self.access_modes),
+            ("accessModeSufficient", &self.access_modes_sufficient),
+            ("accessibilityFeature", &self.features),
+            ("accessibilityHazard", &self.hazards),
+        ] {
+            if !values.is_empty() {
+                writer
+                    .element(name, schema)
+                    .unordered_array(values.iter().map(String::as_str));
+            }
+        }
+        if let Some(summary) = &self.summary {
+            writer.element("accessibilitySummary", schema).value(summary.as_str());
+        }
+    }
+}
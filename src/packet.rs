@@ -0,0 +1,451 @@
+use crate::parse::XML;
+use crate::{
+    CustomNamespace, DateTime, Element, LangId, Namespace, RdfCollectionType, XmpWriter,
+};
+
+/// A retained XMP packet.
+///
+/// Unlike [`XmpWriter`], which is append-only, the properties of a packet can
+/// be queried, replaced, and removed until it is serialized with
+/// [`XmpPacket::serialize`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct XmpPacket<'n> {
+    properties: Vec<Property<'n>>,
+}
+
+impl<'n> XmpPacket<'n> {
+    /// Create a new, empty packet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of top-level properties in the packet.
+    pub fn len(&self) -> usize {
+        self.properties.len()
+    }
+
+    /// Whether the packet has no properties.
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+
+    /// Iterate over the top-level properties in the order they were set.
+    pub fn properties(&self) -> impl Iterator<Item = &Property<'n>> {
+        self.properties.iter()
+    }
+
+    /// Get the value of a property.
+    ///
+    /// Namespaces are compared by their URL.
+    pub fn get(&self, namespace: &Namespace, name: &str) -> Option<&Value<'n>> {
+        self.position(namespace, name).map(|i| &self.properties[i].value)
+    }
+
+    /// Get a mutable reference to the value of a property.
+    pub fn get_mut(
+        &mut self,
+        namespace: &Namespace,
+        name: &str,
+    ) -> Option<&mut Value<'n>> {
+        self.position(namespace, name).map(|i| &mut self.properties[i].value)
+    }
+
+    /// Set a property, replacing and returning its previous value.
+    ///
+    /// New properties are serialized after the existing ones.
+    pub fn set(
+        &mut self,
+        namespace: Namespace<'n>,
+        name: impl Into<String>,
+        value: impl Into<Value<'n>>,
+    ) -> Option<Value<'n>> {
+        let name = name.into();
+        let value = value.into();
+        match self.position(&namespace, &name) {
+            Some(i) => Some(std::mem::replace(&mut self.properties[i].value, value)),
+            None => {
+                self.properties.push(Property { namespace, name, value });
+                None
+            }
+        }
+    }
+
+    /// Remove a property and return its value.
+    pub fn remove(&mut self, namespace: &Namespace, name: &str) -> Option<Value<'n>> {
+        self.position(namespace, name)
+            .map(|i| self.properties.remove(i).value)
+    }
+
+    /// Merge the properties of another packet into this one.
+    ///
+    /// Properties that are only set in `other` are appended. Properties that
+    /// are set in both packets are resolved according to the policy. With
+    /// [`MergePolicy::Error`], the packet is left unchanged if there is a
+    /// conflict.
+    pub fn merge(
+        &mut self,
+        other: XmpPacket<'n>,
+        policy: MergePolicy,
+    ) -> Result<(), MergeError<'n>> {
+        if policy == MergePolicy::Error {
+            if let Some(p) = other
+                .properties
+                .iter()
+                .find(|p| self.position(&p.namespace, &p.name).is_some())
+            {
+                return Err(MergeError {
+                    namespace: p.namespace.clone(),
+                    name: p.name.clone(),
+                });
+            }
+        }
+
+        for property in other.properties {
+            match self.position(&property.namespace, &property.name) {
+                Some(i) if policy == MergePolicy::KeepSecond => {
+                    self.properties[i].value = property.value;
+                }
+                Some(_) => {}
+                None => self.properties.push(property),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write all properties to a writer.
+    pub fn write(&self, writer: &mut XmpWriter<'n>) {
+        for property in &self.properties {
+            property
+                .value
+                .write(writer.element(&property.name, property.namespace.clone()));
+        }
+    }
+
+    /// Serialize the packet. See [`XmpWriter::finish`].
+    pub fn serialize(&self, about: Option<&str>) -> String {
+        let mut writer = XmpWriter::new();
+        self.write(&mut writer);
+        writer.finish(about)
+    }
+
+    fn position(&self, namespace: &Namespace, name: &str) -> Option<usize> {
+        self.properties.iter().position(|p| p.is(namespace, name))
+    }
+}
+
+/// Dynamic properties.
+impl<'n> XmpWriter<'n> {
+    /// Write properties from a list of namespaces, names, and values.
+    ///
+    /// This is useful for metadata that is only known at runtime, e.g. a map
+    /// supplied by the user. The properties are written in order.
+    pub fn extend_from_pairs<N: AsRef<str>>(
+        &mut self,
+        pairs: impl IntoIterator<Item = (Namespace<'n>, N, Value<'n>)>,
+    ) -> &mut Self {
+        for (namespace, name, value) in pairs {
+            value.write(self.element(name.as_ref(), namespace));
+        }
+        self
+    }
+}
+
+/// How to resolve properties that are set in both packets when calling
+/// [`XmpPacket::merge`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MergePolicy {
+    /// Keep the value of the packet that is merged into.
+    KeepFirst,
+    /// Keep the value of the packet that is merged from.
+    KeepSecond,
+    /// Fail the merge.
+    Error,
+}
+
+/// A property that is set in both packets of a merge with
+/// [`MergePolicy::Error`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeError<'n> {
+    /// The namespace of the conflicting property.
+    pub namespace: Namespace<'n>,
+    /// The name of the conflicting property.
+    pub name: String,
+}
+
+impl std::fmt::Display for MergeError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "property {}:{} is set in both packets",
+            self.namespace.prefix(),
+            self.name
+        )
+    }
+}
+
+impl std::error::Error for MergeError<'_> {}
+
+/// A property of a packet or a struct field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property<'n> {
+    /// The namespace of the property.
+    pub namespace: Namespace<'n>,
+    /// The local name of the property.
+    pub name: String,
+    /// The value of the property.
+    pub value: Value<'n>,
+}
+
+impl<'n> Property<'n> {
+    /// Create a new property.
+    pub fn new(
+        namespace: Namespace<'n>,
+        name: impl Into<String>,
+        value: impl Into<Value<'n>>,
+    ) -> Self {
+        Self { namespace, name: name.into(), value: value.into() }
+    }
+
+    /// Whether this is the property with the given name in the given
+    /// namespace. Namespaces are compared by their URL.
+    pub fn is(&self, namespace: &Namespace, name: &str) -> bool {
+        self.name == name && self.namespace.url() == namespace.url()
+    }
+
+    /// The qualified name of the property, e.g. `dc:title`.
+    pub fn qname(&self) -> String {
+        format!("{}:{}", self.namespace.prefix(), self.name)
+    }
+}
+
+/// The value of a property in an [`XmpPacket`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Value<'n> {
+    /// A text value.
+    Text(String),
+    /// An integer value.
+    Integer(i64),
+    /// A real value.
+    Real(f64),
+    /// A boolean value.
+    Bool(bool),
+    /// A date value.
+    Date(DateTime),
+    /// An array of values.
+    Array(RdfCollectionType, Vec<Value<'n>>),
+    /// A language alternative with pairs of the language and the text.
+    LangAlt(Vec<(String, String)>),
+    /// A struct with fields.
+    Struct(Vec<Property<'n>>),
+    /// A value with qualifiers.
+    Qualified(Box<Value<'n>>, Vec<Property<'n>>),
+}
+
+impl<'n> Value<'n> {
+    /// Create a text value with an `xml:lang` qualifier.
+    pub fn with_lang(lang: impl Into<String>, text: impl Into<String>) -> Self {
+        let namespace = CustomNamespace::new("XML", "xml", XML);
+        Self::Qualified(
+            Box::new(Self::Text(text.into())),
+            vec![Property::new(
+                Namespace::Custom(Box::new(namespace)),
+                "lang",
+                lang.into(),
+            )],
+        )
+    }
+
+    /// Get the language and the text of a text value whose only qualifier is
+    /// `xml:lang`, as created by [`Value::with_lang`].
+    pub fn lang_text(&self) -> Option<(&str, &str)> {
+        let Self::Qualified(value, qualifiers) = self else { return None };
+        match (&**value, &qualifiers[..]) {
+            (Self::Text(text), [lang])
+                if lang.name == "lang" && lang.namespace.url() == XML =>
+            {
+                Some((lang.value.as_text()?, text))
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the value as text, if it is a text value.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Self::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Get a field of a struct value.
+    pub fn field(&self, namespace: &Namespace, name: &str) -> Option<&Value<'n>> {
+        match self {
+            Self::Struct(fields) => {
+                fields.iter().find(|p| p.is(namespace, name)).map(|p| &p.value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Write the value to an element.
+    pub fn write<'a>(&'a self, element: Element<'a, 'n>) {
+        if let Some((lang, text)) = self.lang_text() {
+            element.lang(lang).value(text);
+            return;
+        }
+
+        match self {
+            Self::Text(text) => element.value(text.as_str()),
+            Self::Integer(int) => element.value(*int),
+            Self::Real(real) => element.value(*real),
+            Self::Bool(b) => element.value(*b),
+            Self::Date(date) => element.value(*date),
+            Self::Array(kind, items) => {
+                let mut array = element.array(*kind);
+                for item in items {
+                    item.write(array.element());
+                }
+            }
+            Self::LangAlt(items) => element.language_alternative(
+                items.iter().map(|(lang, text)| (Some(LangId(lang)), text.as_str())),
+            ),
+            Self::Struct(fields) => {
+                let mut stc = element.obj();
+                for field in fields {
+                    field.value.write(stc.element(&field.name, field.namespace.clone()));
+                }
+            }
+            Self::Qualified(value, qualifiers) => {
+                let mut stc = element.obj();
+                value.write(stc.element("value", Namespace::Rdf));
+                for qualifier in qualifiers {
+                    qualifier
+                        .value
+                        .write(stc.element(&qualifier.name, qualifier.namespace.clone()));
+                }
+            }
+        }
+    }
+}
+
+impl From<&str> for Value<'_> {
+    fn from(text: &str) -> Self {
+        Self::Text(text.into())
+    }
+}
+
+impl From<String> for Value<'_> {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl From<i64> for Value<'_> {
+    fn from(int: i64) -> Self {
+        Self::Integer(int)
+    }
+}
+
+impl From<f64> for Value<'_> {
+    fn from(real: f64) -> Self {
+        Self::Real(real)
+    }
+}
+
+impl From<bool> for Value<'_> {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl<'n, T: Into<Value<'n>>> From<Vec<T>> for Value<'n> {
+    /// Create an unordered array.
+    fn from(items: Vec<T>) -> Self {
+        Self::Array(RdfCollectionType::Bag, items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<DateTime> for Value<'_> {
+    fn from(date: DateTime) -> Self {
+        Self::Date(date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MergeError, MergePolicy, Namespace, Value, XmpPacket};
+
+    fn packets() -> (XmpPacket<'static>, XmpPacket<'static>) {
+        let mut first = XmpPacket::new();
+        first.set(Namespace::DublinCore, "source", "first");
+        first.set(Namespace::Xmp, "Label", "first");
+
+        let mut second = XmpPacket::new();
+        second.set(Namespace::Xmp, "Label", "second");
+        second.set(Namespace::Xmp, "Nickname", "second");
+        (first, second)
+    }
+
+    fn texts<'a>(packet: &'a XmpPacket) -> Vec<(String, Option<&'a str>)> {
+        packet.properties().map(|p| (p.qname(), p.value.as_text())).collect()
+    }
+
+    #[test]
+    fn merge_keep_first() {
+        let (mut first, second) = packets();
+        first.merge(second, MergePolicy::KeepFirst).unwrap();
+        assert_eq!(
+            texts(&first),
+            [
+                ("dc:source".into(), Some("first")),
+                ("xmp:Label".into(), Some("first")),
+                ("xmp:Nickname".into(), Some("second")),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_keep_second() {
+        let (mut first, second) = packets();
+        first.merge(second, MergePolicy::KeepSecond).unwrap();
+        assert_eq!(
+            texts(&first),
+            [
+                ("dc:source".into(), Some("first")),
+                ("xmp:Label".into(), Some("second")),
+                ("xmp:Nickname".into(), Some("second")),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_error() {
+        let (mut first, second) = packets();
+        let original = first.clone();
+        assert_eq!(
+            first.merge(second, MergePolicy::Error),
+            Err(MergeError { namespace: Namespace::Xmp, name: "Label".into() })
+        );
+        assert_eq!(first, original);
+
+        let mut other = XmpPacket::new();
+        other.set(Namespace::Xmp, "Rating", Value::Integer(3));
+        first.merge(other, MergePolicy::Error).unwrap();
+        assert_eq!(first.get(&Namespace::Xmp, "Rating"), Some(&Value::Integer(3)));
+    }
+
+    #[test]
+    fn merge_compares_namespaces_by_url() {
+        let custom = crate::CustomNamespace::new("XMP", "xap", Namespace::Xmp.url());
+        let mut first = XmpPacket::new();
+        first.set(Namespace::Xmp, "Label", "first");
+
+        let mut second = XmpPacket::new();
+        second.set(Namespace::Custom(Box::new(custom)), "Label", "second");
+        first.merge(second, MergePolicy::KeepSecond).unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first.get(&Namespace::Xmp, "Label"), Some(&"second".into()));
+    }
+}
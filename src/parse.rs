@@ -0,0 +1,640 @@
+//! A small XML and RDF parser for reading existing XMP packets.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::{CustomNamespace, Namespace, Property, RdfCollectionType, Value, XmpPacket};
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+pub(crate) const XML: &str = "http://www.w3.org/XML/1998/namespace";
+const XMLNS: &str = "http://www.w3.org/2000/xmlns/";
+
+impl<'a> XmpPacket<'a> {
+    /// Parse an existing XMP packet.
+    ///
+    /// The input may be a complete packet with `xpacket` processing
+    /// instructions, a bare `x:xmpmeta` element, or a bare `rdf:RDF` element.
+    /// All simple values are read as [`Value::Text`]. A simple value with an
+    /// `xml:lang` attribute is read as a [`Value::Qualified`] text with an
+    /// `xml:lang` qualifier. Namespaces with known
+    /// URLs are mapped to the predefined [`Namespace`] variants.
+    pub fn parse(src: &'a str) -> Result<Self, ParseError> {
+        let root = parse_xml(src)?;
+        let rdf = find(&root, RDF, "RDF").ok_or_else(|| ParseError {
+            offset: root.offset,
+            message: "missing rdf:RDF element".into(),
+        })?;
+
+        let mut parser = RdfParser { nodes: HashMap::new() };
+        let mut packet = XmpPacket::new();
+        for description in rdf.elements() {
+            if !description.is(RDF, "Description") {
+                return Err(description.error("expected rdf:Description"));
+            }
+
+            for property in parser.fields(description)? {
+                packet.set(property.namespace, property.name, property.value);
+            }
+        }
+
+        Ok(packet)
+    }
+}
+
+/// An error that occurred while parsing an XMP packet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The byte offset in the input at which the error occurred.
+    pub offset: usize,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (at byte {})", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Interprets XML elements as RDF.
+struct RdfParser<'a> {
+    /// Structs with an `rdf:nodeID` that were parsed so far.
+    nodes: HashMap<Cow<'a, str>, Value<'a>>,
+}
+
+impl<'a> RdfParser<'a> {
+    /// Parse the properties of a node element, given both as attributes and
+    /// as child elements.
+    fn fields(&mut self, node: &XmlNode<'a>) -> Result<Vec<Property<'a>>, ParseError> {
+        let mut fields = vec![];
+        for attr in node.attrs.iter().filter(|a| a.is_field()) {
+            fields.push(Property {
+                namespace: namespace(attr.prefix, &attr.url),
+                name: attr.local.into(),
+                value: Value::Text(attr.value.to_string()),
+            });
+        }
+
+        for child in node.elements() {
+            let value = self.value(child)?;
+            fields.push(Property {
+                namespace: namespace(child.prefix, &child.url),
+                name: child.local.into(),
+                value,
+            });
+        }
+
+        Ok(fields)
+    }
+
+    /// Parse the value of a property element.
+    fn value(&mut self, elem: &XmlNode<'a>) -> Result<Value<'a>, ParseError> {
+        if let Some(id) = elem.attr(RDF, "nodeID") {
+            if elem.elements().next().is_none() && elem.text.trim().is_empty() {
+                return self
+                    .nodes
+                    .get(id)
+                    .cloned()
+                    .ok_or_else(|| elem.error("reference to unknown rdf:nodeID"));
+            }
+        }
+
+        if let Some(resource) = elem.attr(RDF, "resource") {
+            return Ok(Value::Text(resource.to_string()));
+        }
+
+        if elem.attr(RDF, "parseType").is_some_and(|t| t == "Resource") {
+            return Ok(qualify(self.fields(elem)?));
+        }
+
+        let has_fields = elem.attrs.iter().any(XmlAttr::is_field);
+
+        let mut children = elem.elements();
+        let Some(child) = children.next() else {
+            if has_fields {
+                return Ok(qualify(self.fields(elem)?));
+            }
+            let text = elem.text.clone();
+            return Ok(match elem.attr(XML, "lang") {
+                Some(lang) => Value::with_lang(lang.to_string(), text),
+                None => Value::Text(text),
+            });
+        };
+
+        if children.next().is_some() || has_fields {
+            return Err(elem.error("expected a single value node"));
+        }
+
+        if child.url == RDF {
+            let kind = match child.local {
+                "Bag" => Some(RdfCollectionType::Bag),
+                "Seq" => Some(RdfCollectionType::Seq),
+                "Alt" => Some(RdfCollectionType::Alt),
+                _ => None,
+            };
+
+            if let Some(kind) = kind {
+                return self.array(kind, child);
+            }
+
+            if child.local == "Description" {
+                let value = qualify(self.fields(child)?);
+                if let Some(id) = child.attr(RDF, "nodeID") {
+                    self.nodes.insert(id.clone(), value.clone());
+                }
+                return Ok(value);
+            }
+        }
+
+        Err(child.error("unexpected element"))
+    }
+
+    /// Parse an `rdf:Bag`, `rdf:Seq`, or `rdf:Alt` element.
+    fn array(
+        &mut self,
+        kind: RdfCollectionType,
+        elem: &XmlNode<'a>,
+    ) -> Result<Value<'a>, ParseError> {
+        let mut items = vec![];
+        for li in elem.elements() {
+            if !li.is(RDF, "li") {
+                return Err(li.error("expected rdf:li"));
+            }
+
+            items.push(self.value(li)?);
+        }
+
+        let lang_alt = kind == RdfCollectionType::Alt
+            && !items.is_empty()
+            && items.iter().all(|item| item.lang_text().is_some());
+
+        if lang_alt {
+            return Ok(Value::LangAlt(
+                items
+                    .iter()
+                    .filter_map(Value::lang_text)
+                    .map(|(lang, text)| (lang.into(), text.into()))
+                    .collect(),
+            ));
+        }
+
+        Ok(Value::Array(kind, items))
+    }
+}
+
+/// Turn struct fields into a qualified value if they contain an `rdf:value`.
+fn qualify(mut fields: Vec<Property<'_>>) -> Value<'_> {
+    match fields.iter().position(|p| p.is(&Namespace::Rdf, "value")) {
+        Some(i) => {
+            let value = fields.remove(i).value;
+            Value::Qualified(Box::new(value), fields)
+        }
+        None => Value::Struct(fields),
+    }
+}
+
+/// Create the namespace for a prefix and a URL.
+fn namespace<'a>(prefix: &'a str, url: &Cow<'a, str>) -> Namespace<'a> {
+    Namespace::from_url(url).unwrap_or_else(|| {
+        Namespace::Custom(Box::new(CustomNamespace::from_strings(
+            prefix,
+            prefix,
+            url.clone(),
+        )))
+    })
+}
+
+/// Find the first element with the given name in a tree.
+fn find<'n, 'a>(
+    node: &'n XmlNode<'a>,
+    url: &str,
+    local: &str,
+) -> Option<&'n XmlNode<'a>> {
+    if node.is(url, local) {
+        return Some(node);
+    }
+
+    node.elements().find_map(|child| find(child, url, local))
+}
+
+/// An XML element with resolved namespaces.
+#[derive(Debug)]
+pub(crate) struct XmlNode<'a> {
+    pub(crate) offset: usize,
+    pub(crate) prefix: &'a str,
+    pub(crate) local: &'a str,
+    pub(crate) url: Cow<'a, str>,
+    pub(crate) attrs: Vec<XmlAttr<'a>>,
+    pub(crate) children: Vec<XmlNode<'a>>,
+    /// The concatenated text content of the element.
+    pub(crate) text: String,
+}
+
+/// An XML attribute with a resolved namespace.
+#[derive(Debug)]
+pub(crate) struct XmlAttr<'a> {
+    pub(crate) prefix: &'a str,
+    pub(crate) local: &'a str,
+    pub(crate) url: Cow<'a, str>,
+    pub(crate) value: Cow<'a, str>,
+}
+
+impl XmlAttr<'_> {
+    /// Whether the attribute is an RDF property attribute.
+    fn is_field(&self) -> bool {
+        if self.url == RDF {
+            return self.local == "value";
+        }
+        !self.url.is_empty() && self.url != XML && self.url != XMLNS
+    }
+}
+
+impl<'a> XmlNode<'a> {
+    fn is(&self, url: &str, local: &str) -> bool {
+        self.local == local && self.url == url
+    }
+
+    fn elements(&self) -> std::slice::Iter<'_, XmlNode<'a>> {
+        self.children.iter()
+    }
+
+    fn attr(&self, url: &str, local: &str) -> Option<&Cow<'a, str>> {
+        self.attrs
+            .iter()
+            .find(|a| a.local == local && a.url == url)
+            .map(|a| &a.value)
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError { offset: self.offset, message: message.into() }
+    }
+}
+
+/// Parse an XML document into a tree of elements.
+pub(crate) fn parse_xml(src: &str) -> Result<XmlNode<'_>, ParseError> {
+    let mut s = Scanner { src, pos: 0 };
+    s.eat("\u{feff}");
+    s.misc()?;
+    if !s.peek("<") {
+        return Err(s.error("expected root element"));
+    }
+
+    let root = s.element(&[])?;
+    s.misc()?;
+    if s.pos < src.len() {
+        return Err(s.error("unexpected content after root element"));
+    }
+
+    Ok(root)
+}
+
+/// A cursor into the XML source.
+struct Scanner<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self, pat: &str) -> bool {
+        self.rest().starts_with(pat)
+    }
+
+    fn eat(&mut self, pat: &str) -> bool {
+        let found = self.peek(pat);
+        if found {
+            self.pos += pat.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, pat: &str) -> Result<(), ParseError> {
+        if self.eat(pat) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{pat}`")))
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skip past the next occurrence of `end`.
+    fn skip_past(&mut self, end: &str) -> Result<&'a str, ParseError> {
+        let rest = self.rest();
+        let i = rest
+            .find(end)
+            .ok_or_else(|| self.error(&format!("missing `{end}`")))?;
+        self.pos += i + end.len();
+        Ok(&rest[..i])
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError { offset: self.pos, message: message.into() }
+    }
+
+    /// Skip whitespace, comments, processing instructions, and doctypes.
+    fn misc(&mut self) -> Result<(), ParseError> {
+        loop {
+            self.skip_ws();
+            if self.eat("<?") {
+                self.skip_past("?>")?;
+            } else if self.eat("<!--") {
+                self.skip_past("-->")?;
+            } else if self.eat("<!DOCTYPE") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, ParseError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '=' | '<'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected name"));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    /// Parse an element, given the namespace declarations in scope.
+    fn element(
+        &mut self,
+        scope: &[(&'a str, Cow<'a, str>)],
+    ) -> Result<XmlNode<'a>, ParseError> {
+        let offset = self.pos;
+        self.expect("<")?;
+        let qname = self.name()?;
+
+        let mut raw_attrs = vec![];
+        loop {
+            self.skip_ws();
+            if self.peek("/>") || self.peek(">") {
+                break;
+            }
+
+            let key = self.name()?;
+            self.skip_ws();
+            self.expect("=")?;
+            self.skip_ws();
+            let quote = if self.eat("\"") {
+                "\""
+            } else if self.eat("'") {
+                "'"
+            } else {
+                return Err(self.error("expected quoted attribute value"));
+            };
+            let start = self.pos;
+            let raw = self.skip_past(quote)?;
+            if raw.contains('<') {
+                return Err(ParseError {
+                    offset: start,
+                    message: "`<` in attribute value".into(),
+                });
+            }
+            raw_attrs.push((key, decode(raw, start)?));
+        }
+
+        // Collect the namespace declarations of this element.
+        let mut scope = scope.to_vec();
+        for (key, value) in &raw_attrs {
+            if *key == "xmlns" {
+                scope.push(("", value.clone()));
+            } else if let Some(prefix) = key.strip_prefix("xmlns:") {
+                scope.push((prefix, value.clone()));
+            }
+        }
+
+        let resolve = |qname: &'a str, attr: bool| -> Result<_, ParseError> {
+            let (prefix, local) = qname.split_once(':').unwrap_or(("", qname));
+            let url = match prefix {
+                "xml" => Cow::Borrowed(XML),
+                "xmlns" => Cow::Borrowed(XMLNS),
+                "" if attr => Cow::Borrowed(if local == "xmlns" { XMLNS } else { "" }),
+                _ => match scope.iter().rev().find(|(p, _)| *p == prefix) {
+                    Some((_, url)) => url.clone(),
+                    None if prefix.is_empty() => Cow::Borrowed(""),
+                    None => {
+                        return Err(ParseError {
+                            offset,
+                            message: format!("undeclared namespace prefix `{prefix}`"),
+                        })
+                    }
+                },
+            };
+            Ok((prefix, local, url))
+        };
+
+        let (prefix, local, url) = resolve(qname, false)?;
+        let mut attrs = vec![];
+        for (key, value) in raw_attrs {
+            let (prefix, local, url) = resolve(key, true)?;
+            if attrs.iter().any(|a: &XmlAttr| a.local == local && a.url == url) {
+                return Err(ParseError {
+                    offset,
+                    message: format!("duplicate attribute `{key}`"),
+                });
+            }
+            attrs.push(XmlAttr { prefix, local, url, value });
+        }
+
+        let mut node = XmlNode {
+            offset,
+            prefix,
+            local,
+            url,
+            attrs,
+            children: vec![],
+            text: String::new(),
+        };
+
+        if self.eat("/>") {
+            return Ok(node);
+        }
+
+        self.expect(">")?;
+        loop {
+            let start = self.pos;
+            let rest = self.rest();
+            let len = rest.find('<').unwrap_or(rest.len());
+            if len > 0 {
+                node.text.push_str(&decode(&rest[..len], start)?);
+                self.pos += len;
+            }
+
+            if self.pos == self.src.len() {
+                return Err(self.error(&format!("unclosed element `{qname}`")));
+            } else if self.eat("</") {
+                let end = self.name()?;
+                if end != qname {
+                    return Err(ParseError {
+                        offset: start,
+                        message: format!("expected `</{qname}>`, found `</{end}>`"),
+                    });
+                }
+                self.skip_ws();
+                self.expect(">")?;
+                return Ok(node);
+            } else if self.eat("<!--") {
+                self.skip_past("-->")?;
+            } else if self.eat("<![CDATA[") {
+                node.text.push_str(self.skip_past("]]>")?);
+            } else if self.eat("<?") {
+                self.skip_past("?>")?;
+            } else {
+                node.children.push(self.element(&scope)?);
+            }
+        }
+    }
+}
+
+/// Resolve the entity and character references in text.
+fn decode(raw: &str, offset: usize) -> Result<Cow<'_, str>, ParseError> {
+    if !raw.contains('&') {
+        return Ok(Cow::Borrowed(raw));
+    }
+
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(i) = rest.find('&') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let end = rest.find(';').ok_or_else(|| ParseError {
+            offset: offset + raw.len() - rest.len(),
+            message: "unterminated entity reference".into(),
+        })?;
+
+        let entity = &rest[..end];
+        let c = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "apos" => Some('\''),
+            "quot" => Some('"'),
+            _ => {
+                if let Some(hex) =
+                    entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X"))
+                {
+                    u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+                } else if let Some(dec) = entity.strip_prefix('#') {
+                    dec.parse().ok().and_then(char::from_u32)
+                } else {
+                    None
+                }
+            }
+        };
+
+        out.push(c.ok_or_else(|| ParseError {
+            offset: offset + raw.len() - rest.len(),
+            message: format!("unknown entity `&{entity};`"),
+        })?);
+        rest = &rest[end + 1..];
+    }
+
+    out.push_str(rest);
+    Ok(Cow::Owned(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        CustomNamespace, DateTime, LangId, Namespace, RdfCollectionType, Value,
+        XmpPacket, XmpWriter,
+    };
+
+    #[test]
+    fn round_trip() {
+        let custom = CustomNamespace::new("Example", "ex", "http://example.com/?a=1&b=2");
+        let mut writer = XmpWriter::new();
+        writer.title([(None, "Title"), (Some(LangId("de")), "Titel")]);
+        writer.creator(["Alice", "Bob"]);
+        writer.create_date(DateTime::date(2024, 1, 2));
+        writer
+            .element("source", Namespace::DublinCore)
+            .lang("en")
+            .value("a < b");
+        writer
+            .element("prop", Namespace::Custom(Box::new(custom.clone())))
+            .value("\"quoted\" & more");
+
+        let packet = writer.finish(None);
+        assert!(packet.contains(r#"xmlns:ex="http://example.com/?a=1&amp;b=2""#));
+        assert!(packet.contains(r#"<dc:source xml:lang="en">a &lt; b</dc:source>"#));
+
+        let parsed = XmpPacket::parse(&packet).unwrap();
+        assert_eq!(
+            parsed.get(&Namespace::DublinCore, "title"),
+            Some(&Value::LangAlt(vec![
+                ("x-default".into(), "Title".into()),
+                ("de".into(), "Titel".into()),
+            ]))
+        );
+        assert_eq!(
+            parsed.get(&Namespace::DublinCore, "creator"),
+            Some(&Value::Array(
+                RdfCollectionType::Seq,
+                vec!["Alice".into(), "Bob".into()]
+            ))
+        );
+        assert_eq!(parsed.get(&Namespace::Xmp, "CreateDate"), Some(&"2024-01-02".into()));
+        assert_eq!(
+            parsed.get(&Namespace::DublinCore, "source"),
+            Some(&Value::with_lang("en", "a < b"))
+        );
+        let custom = Namespace::Custom(Box::new(custom));
+        assert_eq!(parsed.get(&custom, "prop"), Some(&"\"quoted\" & more".into()));
+
+        let serialized = parsed.serialize(None);
+        assert_eq!(XmpPacket::parse(&serialized).unwrap(), parsed);
+        assert!(serialized.contains(r#"xmlns:ex="http://example.com/?a=1&amp;b=2""#));
+        assert!(serialized.contains(r#"<dc:source xml:lang="en">a &lt; b</dc:source>"#));
+    }
+
+    #[test]
+    fn localized_array_items_keep_their_language() {
+        let packet = XmpPacket::parse(
+            r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+                <rdf:Description xmlns:dc="http://purl.org/dc/elements/1.1/">
+                    <dc:creator><rdf:Seq>
+                        <rdf:li xml:lang="ja">山田</rdf:li>
+                        <rdf:li>Yamada</rdf:li>
+                    </rdf:Seq></dc:creator>
+                </rdf:Description>
+            </rdf:RDF>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            packet.get(&Namespace::DublinCore, "creator"),
+            Some(&Value::Array(
+                RdfCollectionType::Seq,
+                vec![Value::with_lang("ja", "山田"), "Yamada".into()]
+            ))
+        );
+    }
+
+    #[test]
+    fn entity_encoded_namespace_urls() {
+        let packet = XmpPacket::parse(
+            r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+                <rdf:Description xmlns:ex="http://example.com/&#x3F;q&amp;r"
+                    ex:attr="1"><ex:elem>2</ex:elem></rdf:Description>
+            </rdf:RDF>"#,
+        )
+        .unwrap();
+        let custom = CustomNamespace::new("ex", "ex", "http://example.com/?q&r");
+        let custom = Namespace::Custom(Box::new(custom));
+        assert_eq!(packet.get(&custom, "attr"), Some(&"1".into()));
+        assert_eq!(packet.get(&custom, "elem"), Some(&"2".into()));
+    }
+}
@@ -0,0 +1,524 @@
+//! XMP path expressions for packets.
+
+use crate::{Namespace, Property, Value, XmpPacket};
+
+impl<'n> XmpPacket<'n> {
+    /// Query a value with an XMP path expression.
+    ///
+    /// The path syntax follows the XMP specification: struct fields
+    /// (`xmpMM:DerivedFrom/stRef:documentID`), 1-based array indices and
+    /// `last()` (`xmpMM:History[last()]/stEvt:when`), language selectors
+    /// (`dc:title[?xml:lang='de']`), field selectors
+    /// (`xmpMM:History[stEvt:action='saved']`), and qualifiers
+    /// (`xmp:Identifier[1]/?xmpidq:Scheme`). Items of language alternatives
+    /// are returned as text values.
+    ///
+    /// Prefixes are resolved against the namespaces used in the packet and
+    /// the predefined namespaces. Returns `Ok(None)` if the path is valid but
+    /// does not exist in the packet.
+    pub fn get_path(&self, path: &str) -> Result<Option<Value<'n>>, PathError> {
+        let (namespace, name, steps) = self.parse_path(path)?;
+        let Some(mut node) = self.get(&namespace, name).map(Node::Value) else {
+            return Ok(None);
+        };
+
+        for (offset, step) in &steps {
+            let next = match (node, step) {
+                (
+                    Node::Value(Value::Qualified(_, qualifiers)),
+                    Step::Qualifier(ns, name),
+                ) => Ok(find_field(qualifiers, ns, name)),
+                (Node::Value(Value::Qualified(value, _)), _) => {
+                    descend(Node::Value(value), step)
+                }
+                (node, step) => descend(node, step),
+            };
+
+            match next {
+                Ok(Some(next)) => node = next,
+                Ok(None) => return Ok(None),
+                Err(message) => return Err(PathError::new(*offset, message)),
+            }
+        }
+
+        Ok(Some(match node {
+            Node::Value(value) => value.clone(),
+            Node::Text(text) => Value::Text(text.into()),
+        }))
+    }
+
+    /// Set a value with an XMP path expression, returning the previous value.
+    ///
+    /// See [`XmpPacket::get_path`] for the path syntax. Missing structs and
+    /// language alternatives along the path are created. An index one past
+    /// the end of an array appends an item. Items of language alternatives
+    /// must be set to text values and can only be added with a language
+    /// selector like `[?xml:lang='de']`.
+    pub fn set_path(
+        &mut self,
+        path: &str,
+        value: impl Into<Value<'n>>,
+    ) -> Result<Option<Value<'n>>, PathError> {
+        let (namespace, name, steps) = self.parse_path(path)?;
+        let Some(((offset, first), _)) = steps.split_first() else {
+            return Ok(self.set(namespace, name, value));
+        };
+
+        if self.get(&namespace, name).is_none() {
+            let Some(empty) = first.container() else {
+                return Err(PathError::new(*offset, "the property does not exist"));
+            };
+            self.set(namespace.clone(), name, empty);
+        }
+
+        let target = self.get_mut(&namespace, name).unwrap();
+        set_in(target, &steps, value.into())
+    }
+
+    /// Split a path into the top-level property and the following steps.
+    fn parse_path<'p>(&self, path: &'p str) -> Result<ParsedPath<'p, 'n>, PathError> {
+        let mut parser = PathParser { packet: self, path, cursor: 0 };
+        let (namespace, name) = parser.qname()?;
+        let mut steps = vec![];
+        while !parser.done() {
+            let offset = parser.cursor;
+            steps.push((offset, parser.step()?));
+        }
+        Ok((namespace, name, steps))
+    }
+
+    /// Find a namespace with the given prefix in the packet.
+    fn namespace_with_prefix(&self, prefix: &str) -> Option<Namespace<'n>> {
+        fn search<'n>(props: &[Property<'n>], prefix: &str) -> Option<Namespace<'n>> {
+            props.iter().find_map(|prop| {
+                if prop.namespace.prefix() == prefix {
+                    return Some(prop.namespace.clone());
+                }
+                search_value(&prop.value, prefix)
+            })
+        }
+
+        fn search_value<'n>(value: &Value<'n>, prefix: &str) -> Option<Namespace<'n>> {
+            match value {
+                Value::Array(_, items) => {
+                    items.iter().find_map(|item| search_value(item, prefix))
+                }
+                Value::Struct(fields) => search(fields, prefix),
+                Value::Qualified(value, qualifiers) => {
+                    search_value(value, prefix).or_else(|| search(qualifiers, prefix))
+                }
+                _ => None,
+            }
+        }
+
+        self.properties()
+            .find_map(|prop| search(std::slice::from_ref(prop), prefix))
+    }
+}
+
+/// An error in an XMP path expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathError {
+    /// The byte offset in the path at which the error occurred.
+    pub offset: usize,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl PathError {
+    fn new(offset: usize, message: impl Into<String>) -> Self {
+        Self { offset, message: message.into() }
+    }
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (at byte {} of the path)", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// The namespace and name of the top-level property of a path and the
+/// following steps with their offsets.
+type ParsedPath<'p, 'n> = (Namespace<'n>, &'p str, Vec<(usize, Step<'p, 'n>)>);
+
+/// A step of a path after the top-level property.
+enum Step<'p, 'n> {
+    /// `/ns:name`
+    Field(Namespace<'n>, &'p str),
+    /// `/?ns:name`
+    Qualifier(Namespace<'n>, &'p str),
+    /// `[1]`, stored zero-based.
+    Index(usize),
+    /// `[last()]`
+    Last,
+    /// `[?xml:lang='de']`
+    Lang(&'p str),
+    /// `[ns:name='value']`
+    Select(Namespace<'n>, &'p str, &'p str),
+}
+
+impl<'n> Step<'_, 'n> {
+    /// An empty value that this step can be applied to, if it can be created
+    /// on demand.
+    fn container(&self) -> Option<Value<'n>> {
+        match self {
+            Self::Field(..) => Some(Value::Struct(vec![])),
+            Self::Lang(_) => Some(Value::LangAlt(vec![])),
+            _ => None,
+        }
+    }
+}
+
+/// A node reached while following a path.
+#[derive(Copy, Clone)]
+enum Node<'a, 'n> {
+    Value(&'a Value<'n>),
+    /// An item of a language alternative.
+    Text(&'a str),
+}
+
+/// Apply a step that does not address a qualifier.
+fn descend<'a, 'n>(
+    node: Node<'a, 'n>,
+    step: &Step<'_, 'n>,
+) -> Result<Option<Node<'a, 'n>>, &'static str> {
+    let Node::Value(value) = node else {
+        return Err("a language alternative item has no children");
+    };
+
+    Ok(match (value, step) {
+        (Value::Struct(fields), Step::Field(ns, name)) => {
+            return Ok(find_field(fields, ns, name));
+        }
+        (Value::Array(_, items), Step::Index(i)) => items.get(*i).map(Node::Value),
+        (Value::Array(_, items), Step::Last) => items.last().map(Node::Value),
+        (Value::Array(_, items), Step::Select(ns, name, expected)) => items
+            .iter()
+            .find(|item| item.field(ns, name).and_then(Value::as_text) == Some(expected))
+            .map(Node::Value),
+        (Value::LangAlt(items), Step::Index(i)) => {
+            items.get(*i).map(|(_, text)| Node::Text(text))
+        }
+        (Value::LangAlt(items), Step::Last) => {
+            items.last().map(|(_, text)| Node::Text(text))
+        }
+        (Value::LangAlt(items), Step::Lang(lang)) => items
+            .iter()
+            .find(|(l, _)| l.eq_ignore_ascii_case(lang))
+            .map(|(_, text)| Node::Text(text)),
+        (_, Step::Qualifier(..)) => None,
+        _ => return Err("the path does not match the structure of the value"),
+    })
+}
+
+fn find_field<'a, 'n>(
+    fields: &'a [Property<'n>],
+    namespace: &Namespace,
+    name: &str,
+) -> Option<Node<'a, 'n>> {
+    fields
+        .iter()
+        .find(|p| p.is(namespace, name))
+        .map(|p| Node::Value(&p.value))
+}
+
+/// Follow the steps from a value and replace the value at the end.
+fn set_in<'n>(
+    value: &mut Value<'n>,
+    steps: &[(usize, Step<'_, 'n>)],
+    new: Value<'n>,
+) -> Result<Option<Value<'n>>, PathError> {
+    let Some(((offset, step), rest)) = steps.split_first() else {
+        return Ok(Some(std::mem::replace(value, new)));
+    };
+
+    let offset = *offset;
+    let error = |message| Err(PathError::new(offset, message));
+
+    let value = match (value, step) {
+        (value @ Value::Qualified(..), Step::Qualifier(..)) => value,
+        (value, Step::Qualifier(..)) => {
+            let inner = std::mem::replace(value, Value::Struct(vec![]));
+            *value = Value::Qualified(Box::new(inner), vec![]);
+            value
+        }
+        (Value::Qualified(value, _), _) => value.as_mut(),
+        (value, _) => value,
+    };
+
+    let target = match (value, step) {
+        (Value::Struct(fields), Step::Field(ns, name))
+        | (Value::Qualified(_, fields), Step::Qualifier(ns, name)) => {
+            match fields.iter().position(|p| p.is(ns, name)) {
+                Some(i) => &mut fields[i].value,
+                None if rest.is_empty() => {
+                    fields.push(Property::new(ns.clone(), *name, new));
+                    return Ok(None);
+                }
+                None => {
+                    let Some(empty) = rest[0].1.container() else {
+                        return error("the field does not exist");
+                    };
+                    fields.push(Property::new(ns.clone(), *name, empty));
+                    &mut fields.last_mut().unwrap().value
+                }
+            }
+        }
+        (Value::Array(_, items), Step::Index(i)) => {
+            if *i == items.len() && rest.is_empty() {
+                items.push(new);
+                return Ok(None);
+            }
+            match items.get_mut(*i) {
+                Some(item) => item,
+                None => return error("the index is out of bounds"),
+            }
+        }
+        (Value::Array(_, items), Step::Last) => match items.last_mut() {
+            Some(item) => item,
+            None => return error("the array is empty"),
+        },
+        (Value::Array(_, items), Step::Select(ns, name, expected)) => match items
+            .iter_mut()
+            .find(|item| item.field(ns, name).and_then(Value::as_text) == Some(expected))
+        {
+            Some(item) => item,
+            None => return error("no item matches the selector"),
+        },
+        (Value::LangAlt(items), step @ (Step::Index(_) | Step::Last | Step::Lang(_))) => {
+            if !rest.is_empty() {
+                return error("a language alternative item has no children");
+            }
+            let Value::Text(text) = new else {
+                return error("language alternative items must be text");
+            };
+
+            let slot = match step {
+                Step::Index(i) if *i == items.len() => {
+                    return error(
+                        "appending to a language alternative requires a language",
+                    );
+                }
+                Step::Index(i) => match items.get_mut(*i) {
+                    Some((_, slot)) => slot,
+                    None => return error("the index is out of bounds"),
+                },
+                Step::Last => match items.last_mut() {
+                    Some((_, slot)) => slot,
+                    None => return error("the language alternative is empty"),
+                },
+                Step::Lang(lang) => {
+                    match items.iter().position(|(l, _)| l.eq_ignore_ascii_case(lang)) {
+                        Some(i) => &mut items[i].1,
+                        None => {
+                            items.push((lang.to_string(), text));
+                            return Ok(None);
+                        }
+                    }
+                }
+                _ => unreachable!(),
+            };
+
+            return Ok(Some(Value::Text(std::mem::replace(slot, text))));
+        }
+        _ => return error("the path does not match the structure of the value"),
+    };
+
+    set_in(target, rest, new)
+}
+
+/// A parser for XMP path expressions.
+struct PathParser<'a, 'p, 'n> {
+    packet: &'a XmpPacket<'n>,
+    path: &'p str,
+    cursor: usize,
+}
+
+impl<'p, 'n> PathParser<'_, 'p, 'n> {
+    fn done(&self) -> bool {
+        self.cursor >= self.path.len()
+    }
+
+    fn rest(&self) -> &'p str {
+        &self.path[self.cursor..]
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        let matches = self.rest().starts_with(s);
+        if matches {
+            self.cursor += s.len();
+        }
+        matches
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), PathError> {
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{s}`")))
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> PathError {
+        PathError::new(self.cursor, message)
+    }
+
+    fn step(&mut self) -> Result<Step<'p, 'n>, PathError> {
+        if self.eat("/") {
+            if self.eat("?") {
+                let (ns, name) = self.qname()?;
+                return Ok(Step::Qualifier(ns, name));
+            }
+            let (ns, name) = self.qname()?;
+            return Ok(Step::Field(ns, name));
+        }
+
+        self.expect("[")?;
+        let step = if self.eat("last()") {
+            Step::Last
+        } else if self.rest().starts_with(|c: char| c.is_ascii_digit()) {
+            let start = self.cursor;
+            let digits = self.rest().bytes().take_while(u8::is_ascii_digit).count();
+            self.cursor += digits;
+            match self.path[start..self.cursor].parse::<usize>() {
+                Ok(i) if i > 0 => Step::Index(i - 1),
+                _ => return Err(PathError::new(start, "array indices start at 1")),
+            }
+        } else if self.eat("?xml:lang") {
+            self.expect("=")?;
+            Step::Lang(self.quoted()?)
+        } else {
+            let (ns, name) = self.qname()?;
+            self.expect("=")?;
+            Step::Select(ns, name, self.quoted()?)
+        };
+        self.expect("]")?;
+        Ok(step)
+    }
+
+    fn qname(&mut self) -> Result<(Namespace<'n>, &'p str), PathError> {
+        let start = self.cursor;
+        let len = self
+            .rest()
+            .find(['/', '[', ']', '=', '?'])
+            .unwrap_or(self.rest().len());
+        let qname = &self.path[start..start + len];
+        let Some((prefix, name)) = qname.split_once(':') else {
+            return Err(self.error("expected a prefixed name"));
+        };
+        if prefix.is_empty() || name.is_empty() {
+            return Err(self.error("expected a prefixed name"));
+        }
+
+        let namespace = self
+            .packet
+            .namespace_with_prefix(prefix)
+            .or_else(|| Namespace::with_prefix(prefix))
+            .ok_or_else(|| self.error(format!("unknown namespace prefix `{prefix}`")))?;
+
+        self.cursor += len;
+        Ok((namespace, name))
+    }
+
+    fn quoted(&mut self) -> Result<&'p str, PathError> {
+        let quote = match self.rest().chars().next() {
+            Some(c @ ('"' | '\'')) => c,
+            _ => return Err(self.error("expected a quoted string")),
+        };
+        self.cursor += 1;
+        let Some(len) = self.rest().find(quote) else {
+            return Err(self.error("unterminated string"));
+        };
+        let value = &self.path[self.cursor..self.cursor + len];
+        self.cursor += len + 1;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Namespace, Property, RdfCollectionType, Value, XmpPacket};
+
+    fn id(id: &str, scheme: &str) -> Value<'static> {
+        Value::Qualified(
+            Box::new(id.into()),
+            vec![Property::new(Namespace::XmpIdq, "Scheme", scheme)],
+        )
+    }
+
+    fn identifiers() -> XmpPacket<'static> {
+        let mut packet = XmpPacket::new();
+        packet.set(
+            Namespace::Xmp,
+            "Identifier",
+            Value::Array(RdfCollectionType::Bag, vec![id("a", "DOI"), id("b", "ISBN")]),
+        );
+        packet
+    }
+
+    #[test]
+    fn qualifiers() {
+        let mut packet = identifiers();
+        assert_eq!(
+            packet.get_path("xmp:Identifier[1]/?xmpidq:Scheme"),
+            Ok(Some("DOI".into()))
+        );
+        assert_eq!(packet.get_path("xmp:Identifier[last()]"), Ok(Some(id("b", "ISBN"))));
+        assert_eq!(packet.get_path("xmp:Identifier[2]/?xmp:Label"), Ok(None));
+
+        assert_eq!(
+            packet.set_path("xmp:Identifier[2]/?xmpidq:Scheme", "ISSN"),
+            Ok(Some("ISBN".into()))
+        );
+        assert_eq!(
+            packet.get_path("xmp:Identifier[2]/?xmpidq:Scheme"),
+            Ok(Some("ISSN".into()))
+        );
+        assert_eq!(packet.get_path("xmp:Identifier[2]"), Ok(Some(id("b", "ISSN"))));
+    }
+
+    #[test]
+    fn language_qualifiers_of_simple_values() {
+        let mut packet = XmpPacket::new();
+        packet.set(Namespace::DublinCore, "source", Value::with_lang("en", "Book"));
+        assert_eq!(packet.get_path("dc:source/?xml:lang"), Ok(Some("en".into())));
+        assert_eq!(packet.set_path("dc:source/?xml:lang", "de"), Ok(Some("en".into())));
+        assert_eq!(
+            packet.get(&Namespace::DublinCore, "source"),
+            Some(&Value::with_lang("de", "Book"))
+        );
+    }
+
+    #[test]
+    fn language_alternatives() {
+        let mut packet = XmpPacket::new();
+        assert_eq!(packet.set_path("dc:title[?xml:lang='x-default']", "Title"), Ok(None));
+        assert_eq!(packet.set_path("dc:title[?xml:lang='de']", "Titel"), Ok(None));
+        assert_eq!(
+            packet.set_path("dc:title[?xml:lang='de']", "Neuer Titel"),
+            Ok(Some("Titel".into()))
+        );
+        assert_eq!(packet.get_path("dc:title[?xml:lang='fr']"), Ok(None));
+        assert_eq!(packet.get_path("dc:title[2]"), Ok(Some("Neuer Titel".into())));
+        assert_eq!(packet.set_path("dc:title[1]", "Titre"), Ok(Some("Title".into())));
+        assert!(packet.set_path("dc:title[3]", "Titolo").is_err());
+        assert!(packet.set_path("dc:title[?xml:lang='it']", 1i64).is_err());
+        assert_eq!(
+            packet.get(&Namespace::DublinCore, "title"),
+            Some(&Value::LangAlt(vec![
+                ("x-default".into(), "Titre".into()),
+                ("de".into(), "Neuer Titel".into()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn invalid_paths() {
+        let packet = identifiers();
+        assert_eq!(packet.get_path("xmp:Identifier[0]").unwrap_err().offset, 15);
+        assert!(packet.get_path("unknown:Identifier").is_err());
+        assert!(packet.get_path("xmp:Identifier[1]/stRef:").is_err());
+    }
+}
@@ -2,11 +2,19 @@
 //!
 //! Enabled by the `pdfa` feature (enabled by default).
 
-use crate::{deref, Array, Namespace, RdfCollectionType, Struct};
+use std::fmt::Write;
+
+use crate::conformance::{predefined, Kind};
+use crate::types::prefix_of;
+use crate::{
+    deref, Array, Namespace, RdfCollectionType, Struct, ToNamespace, Value, XmpError,
+    XmpPacket, XmpWriter,
+};
 
 /// Write a extension schema description.
 ///
 /// Created by [`PdfAExtSchemasWriter::add_schema`].
+#[derive(Debug)]
 pub struct PdfAExtSchemaWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
 }
@@ -23,7 +31,8 @@ impl<'a, 'n: 'a> PdfAExtSchemaWriter<'a, 'n> {
     pub fn namespace(&mut self, namespace: Namespace<'n>) -> &mut Self {
         self.schema(&format!("{} schema", namespace.name()));
         self.namespace_uri(namespace.url());
-        self.prefix(namespace.prefix());
+        let prefix = self.stc.prefix_of(&namespace);
+        self.prefix(&prefix);
         self
     }
 
@@ -40,6 +49,7 @@ impl<'a, 'n: 'a> PdfAExtSchemaWriter<'a, 'n> {
     /// The namespace URI of the extension schema.
     fn namespace_uri(&mut self, uri: &str) -> &mut Self {
         self.stc.element("namespaceURI", Namespace::PdfASchema).value(uri);
+        self.stc.writer().described_schemas.push(uri.into());
         self
     }
 
@@ -79,6 +89,7 @@ deref!('a, 'n, PdfAExtSchemaWriter<'a, 'n> => Struct<'a, 'n>, stc);
 /// Write a property of an extension schema.
 ///
 /// Created by [`PdfAExtPropertiesWriter::add_property`].
+#[derive(Debug)]
 pub struct PdfAExtPropertyWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
 }
@@ -134,6 +145,7 @@ deref!('a, 'n, PdfAExtPropertyWriter<'a, 'n> => Struct<'a, 'n>, stc);
 /// Write a value type of an extension schema.
 ///
 /// Created by [`PdfAExtTypesWriter::add_value_type`].
+#[derive(Debug)]
 pub struct PdfAExtTypeWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
 }
@@ -154,7 +166,8 @@ impl<'a, 'n: 'a> PdfAExtTypeWriter<'a, 'n> {
     /// Write the `pdfaType:namespaceURI` and `pdfaType:prefix` properties.
     pub fn namespace(&mut self, namespace: Namespace<'n>) -> &mut Self {
         self.namespace_uri(namespace.url());
-        self.prefix(namespace.prefix());
+        let prefix = self.stc.prefix_of(&namespace);
+        self.prefix(&prefix);
         self
     }
 
@@ -203,6 +216,7 @@ deref!('a, 'n, PdfAExtTypeWriter<'a, 'n> => Struct<'a, 'n>, stc);
 /// Write a field of an extension schema value type.
 ///
 /// Created by [`PdfAExtTypeFieldsWriter::add_field`].
+#[derive(Debug)]
 pub struct PdfAExtTypeFieldWriter<'a, 'n: 'a> {
     stc: Struct<'a, 'n>,
 }
@@ -245,6 +259,7 @@ deref!('a, 'n, PdfAExtTypeFieldWriter<'a, 'n> => Struct<'a, 'n>, stc);
 /// Write an array of extension schema value type fields.
 ///
 /// Created by [`PdfAExtTypeWriter::fields`].
+#[derive(Debug)]
 pub struct PdfAExtTypeFieldsWriter<'a, 'n: 'a> {
     array: Array<'a, 'n>,
 }
@@ -265,6 +280,7 @@ deref!('a, 'n, PdfAExtTypeFieldsWriter<'a, 'n> => Array<'a, 'n>, array);
 /// Write an array of extension schema properties.
 ///
 /// Created by [`PdfAExtSchemaWriter::properties`].
+#[derive(Debug)]
 pub struct PdfAExtPropertiesWriter<'a, 'n: 'a> {
     array: Array<'a, 'n>,
 }
@@ -285,6 +301,7 @@ deref!('a, 'n, PdfAExtPropertiesWriter<'a, 'n> => Array<'a, 'n>, array);
 /// Write an array of extension schema value types.
 ///
 /// Created by [`PdfAExtSchemaWriter::value_types`].
+#[derive(Debug)]
 pub struct PdfAExtTypesWriter<'a, 'n: 'a> {
     array: Array<'a, 'n>,
 }
@@ -304,6 +321,7 @@ impl<'a, 'n: 'a> PdfAExtTypesWriter<'a, 'n> {
 ///
 /// Created by [`crate::XmpWriter::extension_schemas`]. Check PDF/A-1 TechNote
 /// 0008 to learn which schemas and properties need to be described.
+#[derive(Debug)]
 pub struct PdfAExtSchemasWriter<'a, 'n: 'a> {
     array: Array<'a, 'n>,
 }
@@ -360,6 +378,37 @@ impl<'a, 'n: 'a> PdfAExtSchemasWriter<'a, 'n> {
         self
     }
 
+    /// Describe the `pdfuaid` schema.
+    pub fn pdfuaid(&mut self) -> &mut Self {
+        {
+            let mut schema = self.add_schema();
+            schema.namespace(Namespace::PdfUAId);
+            let mut properties = schema.properties();
+
+            properties
+                .add_property()
+                .category(true)
+                .description("Indicates, which part of ISO 14289 standard is followed")
+                .name("part")
+                .value_type("Integer");
+
+            properties
+                .add_property()
+                .category(true)
+                .description("Optional PDF/UA amendment identifier")
+                .name("amd")
+                .value_type("Text");
+
+            properties
+                .add_property()
+                .category(true)
+                .description("Optional PDF/UA corrigenda identifier")
+                .name("corr")
+                .value_type("Text");
+        }
+        self
+    }
+
     /// Start describing the `pdf` schema.
     pub fn pdf(&mut self) -> AdobePdfDescsWriter<'_, 'n> {
         AdobePdfDescsWriter::start(self.add_schema())
@@ -397,6 +446,7 @@ deref!('a, 'n, PdfAExtSchemasWriter<'a, 'n> => Array<'a, 'n>, array);
 ///
 /// Only contains methods for properties that are defined in XMP 2005 or later.
 /// Created by [`XmpDescsWriter::properties`].
+#[derive(Debug)]
 pub struct XmpPropertiesWriter<'a, 'n: 'a> {
     props: PdfAExtPropertiesWriter<'a, 'n>,
 }
@@ -437,6 +487,7 @@ deref!('a, 'n, XmpPropertiesWriter<'a, 'n> => PdfAExtPropertiesWriter<'a, 'n>, p
 /// Writer for describing the XMP schema.
 ///
 /// Created by [`PdfAExtSchemasWriter::xmp`].
+#[derive(Debug)]
 pub struct XmpDescsWriter<'a, 'n: 'a> {
     schema: PdfAExtSchemaWriter<'a, 'n>,
 }
@@ -458,6 +509,7 @@ deref!('a, 'n, XmpDescsWriter<'a, 'n> => PdfAExtSchemaWriter<'a, 'n>, schema);
 /// Writer for the property descriptions of the `xmpMM` schema.
 ///
 /// Created by [`XmpMMDescsWriter::properties`].
+#[derive(Debug)]
 pub struct XmpMMPropertiesWriter<'a, 'n: 'a> {
     props: PdfAExtPropertiesWriter<'a, 'n>,
 }
@@ -521,6 +573,7 @@ deref!('a, 'n, XmpMMPropertiesWriter<'a, 'n> => PdfAExtPropertiesWriter<'a, 'n>,
 /// Writer for describing the XMP Media Management schema.
 ///
 /// Created by [`PdfAExtSchemasWriter::xmp_media_management`].
+#[derive(Debug)]
 pub struct XmpMMDescsWriter<'a, 'n: 'a> {
     schema: PdfAExtSchemaWriter<'a, 'n>,
 }
@@ -542,6 +595,7 @@ deref!('a, 'n, XmpMMDescsWriter<'a, 'n> => PdfAExtSchemaWriter<'a, 'n>, schema);
 /// Writer for the property descriptions of the `pdf` schema.
 ///
 /// Created by [`AdobePdfDescsWriter::properties`].
+#[derive(Debug)]
 pub struct AdobePdfPropertiesWriter<'a, 'n: 'a> {
     props: PdfAExtPropertiesWriter<'a, 'n>,
 }
@@ -613,6 +667,7 @@ deref!('a, 'n, AdobePdfPropertiesWriter<'a, 'n> => PdfAExtPropertiesWriter<'a, '
 /// Writer for describing the Adobe PDF extension schema.
 ///
 /// Created by [`PdfAExtSchemasWriter::pdf`].
+#[derive(Debug)]
 pub struct AdobePdfDescsWriter<'a, 'n: 'a> {
     schema: PdfAExtSchemaWriter<'a, 'n>,
 }
@@ -634,6 +689,7 @@ deref!('a, 'n, AdobePdfDescsWriter<'a, 'n> => PdfAExtSchemaWriter<'a, 'n>, schem
 /// Writer for describing the Paged Text extension schema.
 ///
 /// Created by [`PdfAExtSchemasWriter::paged_text`].
+#[derive(Debug)]
 pub struct PagedTextDescsWriter<'a, 'n: 'a> {
     schema: PdfAExtSchemaWriter<'a, 'n>,
 }
@@ -655,6 +711,7 @@ deref!('a, 'n, PagedTextDescsWriter<'a, 'n> => PdfAExtSchemaWriter<'a, 'n>, sche
 /// Writer for the property descriptions of the `xmpTPg` schema.
 ///
 /// Created by [`PagedTextDescsWriter::properties`].
+#[derive(Debug)]
 pub struct PagedTextPropertiesWriter<'a, 'n: 'a> {
     props: PdfAExtPropertiesWriter<'a, 'n>,
 }
@@ -705,6 +762,7 @@ deref!('a, 'n, PagedTextPropertiesWriter<'a, 'n> => PdfAExtPropertiesWriter<'a,
 /// Writer for the auxiliary ResourceEvent extension schema.
 ///
 /// Created by [`PdfAExtSchemasWriter::resource_event`].
+#[derive(Debug)]
 pub struct ResourceEventDescsWriter<'a, 'n: 'a> {
     schema: PdfAExtSchemaWriter<'a, 'n>,
 }
@@ -726,6 +784,7 @@ deref!('a, 'n, ResourceEventDescsWriter<'a, 'n> => PdfAExtSchemaWriter<'a, 'n>,
 /// Writer for the property descriptions of the `stEvt` schema.
 ///     
 /// Created by [`ResourceEventDescsWriter::properties`].
+#[derive(Debug)]
 pub struct ResourceEventPropertiesWriter<'a, 'n: 'a> {
     props: PdfAExtPropertiesWriter<'a, 'n>,
 }
@@ -751,6 +810,7 @@ deref!('a, 'n, ResourceEventPropertiesWriter<'a, 'n> => PdfAExtPropertiesWriter<
 /// Writer for the thumbnail extension schema.
 ///
 /// Created by [`PdfAExtSchemasWriter::thumbnail`].
+#[derive(Debug)]
 pub struct ThumbnailSchemaWriter<'a, 'n: 'a> {
     schema: PdfAExtSchemaWriter<'a, 'n>,
 }
@@ -772,6 +832,7 @@ deref!('a, 'n, ThumbnailSchemaWriter<'a, 'n> => PdfAExtSchemaWriter<'a, 'n>, sch
 /// Writer for the property descriptions of the `xmpGImg` schema.
 ///
 /// Created by [`ThumbnailSchemaWriter::properties`].
+#[derive(Debug)]
 pub struct ThumbnailPropertiesWriter<'a, 'n: 'a> {
     props: PdfAExtPropertiesWriter<'a, 'n>,
 }
@@ -831,3 +892,438 @@ impl<'a, 'n: 'a> ThumbnailPropertiesWriter<'a, 'n> {
 }
 
 deref!('a, 'n, ThumbnailPropertiesWriter<'a, 'n> => PdfAExtPropertiesWriter<'a, 'n>, props);
+
+/// Automatic extension schema descriptions.
+impl<'n> XmpWriter<'n> {
+    /// Provide the description of a property or struct field that is not
+    /// predefined by PDF/A.
+    ///
+    /// The description is used when the property is described automatically
+    /// by [`XmpWriter::write_extension_schemas_auto`] or [`XmpWriter::pdfa`].
+    /// For properties with custom struct values, it also describes their
+    /// value type.
+    pub fn describe_property(
+        &mut self,
+        namespace: impl ToNamespace<'n>,
+        name: &str,
+        description: impl Into<String>,
+    ) -> &mut Self {
+        let url = namespace.to_namespace(self).url().to_string();
+        let description = description.into();
+        match self.descriptions.iter_mut().find(|(u, n, _)| *u == url && n == name) {
+            Some(entry) => entry.2 = description,
+            None => self.descriptions.push((url, name.into(), description)),
+        }
+        self
+    }
+
+    /// Write the `pdfaExtension:schemas` property with descriptions of all
+    /// properties written so far that are not predefined by PDF/A.
+    ///
+    /// The value types are inferred from the written values. Structs that are
+    /// not of a predefined type are described as custom value types named
+    /// after their property. The descriptions are taken from
+    /// [`XmpWriter::describe_property`], missing ones are recorded as
+    /// [`XmpError::MissingDescription`]. Call this after all other properties
+    /// and instead of [`XmpWriter::extension_schemas`]. Writes nothing if all
+    /// properties are predefined. If `pdfaExtension:schemas` was already
+    /// written, the schemas it does not describe are recorded as
+    /// [`XmpError::UndescribedSchema`] instead.
+    pub fn write_extension_schemas_auto(&mut self) -> &mut Self {
+        // PDF/A-1 predefines the fewest properties, so descriptions written
+        // for it are valid for all parts.
+        self.describe_extension_schemas(1);
+        self
+    }
+
+    /// Describe the properties written so far that a part of PDF/A does not
+    /// predefine in `pdfaExtension:schemas`, unless that property was already
+    /// written.
+    pub(crate) fn describe_extension_schemas(&mut self, part: i32) {
+        let undescribed: Vec<_> = self
+            .properties
+            .keys()
+            .filter(|(namespace, name)| predefined(namespace, name, part).is_none())
+            .cloned()
+            .collect();
+        if undescribed.is_empty() {
+            return;
+        }
+
+        // The schemas can't be extended once written, so report the ones
+        // that are missing instead.
+        if self.has_property(Namespace::PdfAExtension, "schemas") {
+            let mut missing: Vec<_> = undescribed
+                .iter()
+                .filter(|(namespace, _)| !self.is_described(namespace))
+                .map(|(namespace, _)| namespace.url())
+                .collect();
+            missing.dedup();
+            for url in missing {
+                self.errors.push(XmpError::UndescribedSchema(url.into()));
+            }
+            return;
+        }
+
+        // Only parse the properties that need to be described instead of the
+        // whole packet.
+        let snapshot = self.snapshot(&undescribed);
+        let packet = match XmpPacket::parse(&snapshot) {
+            Ok(packet) => packet,
+            Err(err) => {
+                self.errors.push(XmpError::Malformed(err));
+                return;
+            }
+        };
+
+        // Group the properties by schema. The PDF/UA identification schema
+        // has a predefined description.
+        let mut pdfuaid = false;
+        let mut schemas: Vec<AutoSchema<'_, 'n>> = vec![];
+        for property in packet.properties() {
+            if predefined(&property.namespace, &property.name, part).is_some() {
+                continue;
+            }
+            if property.namespace.url() == Namespace::PdfUAId.url() {
+                pdfuaid = true;
+                continue;
+            }
+
+            let Some(namespace) = self.namespace_with_url(property.namespace.url())
+            else {
+                continue;
+            };
+
+            let i = match schemas.iter().position(|s| s.namespace == namespace) {
+                Some(i) => i,
+                None => {
+                    schemas.push(AutoSchema {
+                        namespace,
+                        properties: vec![],
+                        types: vec![],
+                    });
+                    schemas.len() - 1
+                }
+            };
+
+            let schema = &mut schemas[i];
+            if schema.properties.iter().any(|(name, ..)| *name == property.name) {
+                continue;
+            }
+            let value_type = schema.value_type(self, &property.name, &property.value);
+            let description =
+                self.property_description(&property.namespace, &property.name);
+            schema.properties.push((&property.name, value_type, description));
+        }
+
+        if schemas.is_empty() && !pdfuaid {
+            return;
+        }
+
+        let mut writer = self.extension_schemas();
+        if pdfuaid {
+            writer.pdfuaid();
+        }
+        for schema in schemas {
+            let mut desc = writer.add_schema();
+            desc.namespace(schema.namespace.clone());
+
+            let mut properties = desc.properties();
+            for (name, value_type, description) in &schema.properties {
+                properties
+                    .add_property()
+                    .category(false)
+                    .description(description)
+                    .name(name)
+                    .value_type(value_type);
+            }
+            drop(properties);
+
+            if schema.types.is_empty() {
+                continue;
+            }
+
+            let mut types = desc.value_types();
+            for ty in &schema.types {
+                let mut desc = types.add_value_type();
+                desc.name(&ty.name)
+                    .namespace(ty.namespace.clone())
+                    .description(&ty.description);
+                let mut fields = desc.fields();
+                for (name, value_type, description) in &ty.fields {
+                    fields
+                        .add_field()
+                        .name(name)
+                        .value_type(value_type)
+                        .description(description);
+                }
+            }
+        }
+    }
+
+    /// The description of a property or struct field provided with
+    /// [`XmpWriter::describe_property`]. A missing description is recorded
+    /// as an error.
+    fn property_description(&mut self, namespace: &Namespace, name: &str) -> String {
+        let url = namespace.url();
+        match self.descriptions.iter().find(|(u, n, _)| *u == url && n == name) {
+            Some((.., description)) => description.clone(),
+            None => {
+                let prefix = match self.namespace_with_url(url) {
+                    Some(namespace) => prefix_of(&self.prefixes, &namespace).to_string(),
+                    None => namespace.prefix().to_string(),
+                };
+                self.errors
+                    .push(XmpError::MissingDescription(format!("{prefix}:{name}")));
+                String::new()
+            }
+        }
+    }
+
+    /// A minimal packet with only some of the top-level properties written
+    /// so far.
+    fn snapshot(&mut self, keys: &[(Namespace<'n>, String)]) -> String {
+        self.close_open_tag();
+        let mut starts: Vec<usize> = self.properties.values().copied().collect();
+        starts.sort_unstable();
+
+        let mut snapshot = String::new();
+        write!(
+            snapshot,
+            "<rdf:RDF xmlns:rdf=\"{}\"><rdf:Description",
+            Namespace::Rdf.url(),
+        )
+        .unwrap();
+        self.write_namespaces(&mut snapshot);
+        snapshot.push('>');
+
+        // A property extends up to the start of the next one.
+        for key in keys {
+            let start = self.properties[key];
+            let next = starts.partition_point(|&s| s <= start);
+            let end = starts.get(next).copied().unwrap_or(self.buf.len());
+            snapshot.push_str(&self.buf[start..end]);
+        }
+
+        snapshot.push_str("</rdf:Description></rdf:RDF>");
+        snapshot
+    }
+
+    /// Whether the namespace was described in `pdfaExtension:schemas`.
+    pub(crate) fn is_described(&self, namespace: &Namespace) -> bool {
+        self.described_schemas.iter().any(|url| url == namespace.url())
+    }
+
+    /// Find a namespace that was used by the writer by its URL.
+    fn namespace_with_url(&self, url: &str) -> Option<Namespace<'n>> {
+        self.namespaces.iter().find(|ns| ns.url() == url).cloned()
+    }
+}
+
+/// The properties and custom value types of an automatically described
+/// extension schema.
+struct AutoSchema<'a, 'n> {
+    namespace: Namespace<'n>,
+    /// The names, value types, and descriptions of the properties.
+    properties: Vec<(&'a str, String, String)>,
+    types: Vec<AutoType<'n>>,
+}
+
+/// A custom struct type of an automatically described extension schema.
+struct AutoType<'n> {
+    name: String,
+    namespace: Namespace<'n>,
+    description: String,
+    /// The names, value types, and descriptions of the fields.
+    fields: Vec<(String, String, String)>,
+}
+
+impl<'n> AutoSchema<'_, 'n> {
+    /// Infer the PDF/A value type of a value, registering custom types for
+    /// structs.
+    fn value_type(
+        &mut self,
+        writer: &mut XmpWriter<'n>,
+        name: &str,
+        value: &Value,
+    ) -> String {
+        match value {
+            Value::Text(_) => {
+                // The parser does not know the types, so infer them from the
+                // lexical form.
+                [Kind::Integer, Kind::Real, Kind::Boolean, Kind::Date]
+                    .into_iter()
+                    .find(|kind| kind.matches(value))
+                    .map_or("Text", Kind::name)
+                    .into()
+            }
+            Value::Date(_) => "Date".into(),
+            Value::Integer(_) => "Integer".into(),
+            Value::Real(_) => "Real".into(),
+            Value::Bool(_) => "Boolean".into(),
+            Value::LangAlt(_) => "Lang Alt".into(),
+            Value::Qualified(value, _) => self.value_type(writer, name, value),
+            Value::Array(kind, items) => {
+                let item = match items.first() {
+                    Some(item) => self.value_type(writer, name, item),
+                    None => "Text".into(),
+                };
+                let kind = match kind {
+                    RdfCollectionType::Seq => "seq",
+                    RdfCollectionType::Bag => "bag",
+                    RdfCollectionType::Alt => "alt",
+                };
+                format!("{kind} {item}")
+            }
+            Value::Struct(fields) => {
+                let namespace = fields
+                    .first()
+                    .and_then(|field| writer.namespace_with_url(field.namespace.url()));
+                let Some(namespace) = namespace else { return "Text".into() };
+                if let Some(name) = predefined_struct_type(&namespace) {
+                    return name.into();
+                }
+
+                let mut chars = name.chars();
+                let ty_name: String = chars
+                    .next()
+                    .into_iter()
+                    .flat_map(char::to_uppercase)
+                    .chain(chars)
+                    .collect();
+                if self.types.iter().any(|ty| ty.name == ty_name) {
+                    return ty_name;
+                }
+
+                let fields = fields
+                    .iter()
+                    .map(|field| {
+                        let value_type =
+                            self.value_type(writer, &field.name, &field.value);
+                        let description =
+                            writer.property_description(&field.namespace, &field.name);
+                        (field.name.clone(), value_type, description)
+                    })
+                    .collect();
+                let description = writer.property_description(&self.namespace, name);
+                self.types.push(AutoType {
+                    name: ty_name.clone(),
+                    namespace,
+                    description,
+                    fields,
+                });
+                ty_name
+            }
+        }
+    }
+}
+
+/// The name of the predefined struct type whose fields are in the namespace.
+fn predefined_struct_type(namespace: &Namespace) -> Option<&'static str> {
+    Some(match namespace {
+        Namespace::XmpResourceRef => "ResourceRef",
+        Namespace::XmpResourceEvent => "ResourceEvent",
+        Namespace::XmpVersion => "Version",
+        Namespace::XmpJob => "Job",
+        Namespace::XmpColorant => "Colorant",
+        Namespace::XmpFont => "Font",
+        Namespace::XmpDimensions => "Dimensions",
+        Namespace::XmpImage => "Thumbnail",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CustomNamespace, Namespace, XmpError, XmpPacket, XmpWriter};
+
+    const SHOP: CustomNamespace<'static> =
+        CustomNamespace::new("Shop", "shop", "http://example.com/shop/");
+
+    fn shop() -> Namespace<'static> {
+        Namespace::Custom(Box::new(SHOP))
+    }
+
+    #[test]
+    fn auto_schemas_are_complete() {
+        let mut writer = XmpWriter::new();
+        writer.pdfa_part(1).pdfa_conformance("B");
+        writer.element("sku", shop()).value("A-1");
+        let mut price = writer.element("price", shop()).obj();
+        price.field("amount", shop(), 12);
+        price.field("currency", shop(), "EUR");
+        drop(price);
+
+        writer
+            .describe_property(shop(), "sku", "Stock keeping unit")
+            .describe_property(shop(), "price", "Price of the item")
+            .describe_property(shop(), "amount", "Amount in minor units")
+            .describe_property(shop(), "currency", "ISO 4217 currency code");
+        writer.write_extension_schemas_auto();
+        assert_eq!(writer.errors(), []);
+
+        let packet = writer.finish(None);
+        assert!(packet.contains("Stock keeping unit"));
+        let parsed = XmpPacket::parse(&packet).unwrap();
+        assert_eq!(parsed.check_pdfa(1), []);
+    }
+
+    #[test]
+    fn auto_schemas_require_descriptions() {
+        let mut writer = XmpWriter::new();
+        writer.element("sku", shop()).value("A-1");
+        writer.write_extension_schemas_auto();
+        assert_eq!(writer.errors(), [XmpError::MissingDescription("shop:sku".into())]);
+    }
+
+    #[test]
+    fn auto_schemas_are_written_once() {
+        let mut writer = XmpWriter::new();
+        writer.element("sku", shop()).value("A-1");
+        writer.describe_property(shop(), "sku", "Stock keeping unit");
+        writer.write_extension_schemas_auto();
+        writer.write_extension_schemas_auto();
+        assert_eq!(writer.errors(), []);
+        assert_eq!(writer.finish(None).matches("<pdfaExtension:schemas>").count(), 1);
+    }
+
+    #[test]
+    fn auto_schemas_report_malformed_properties() {
+        let mut writer = XmpWriter::new();
+        writer.element("sku", shop()).value("A-1");
+        writer.describe_property(shop(), "sku", "Stock keeping unit");
+        let len = writer.buf.len() - 4;
+        writer.buf.truncate(len);
+        writer.write_extension_schemas_auto();
+        assert!(matches!(writer.errors(), [XmpError::Malformed(_)]));
+    }
+
+    #[test]
+    fn preset_describes_pdfuaid() {
+        let mut writer = XmpWriter::new();
+        writer.title([(None, "Report")]);
+        writer.pdfua(1);
+        writer.element("sku", shop()).value("A-1");
+        writer.describe_property(shop(), "sku", "Stock keeping unit");
+        writer.pdfa(2, Some("B"));
+        assert_eq!(writer.errors(), []);
+
+        let packet = writer.finish(None);
+        let parsed = XmpPacket::parse(&packet).unwrap();
+        assert_eq!(parsed.check_pdfa(2), []);
+    }
+
+    #[test]
+    fn pdfua_reports_written_schemas() {
+        let mut writer = XmpWriter::new();
+        writer.pdfa_part(2);
+        writer.element("sku", shop()).value("A-1");
+        writer.describe_property(shop(), "sku", "Stock keeping unit");
+        writer.write_extension_schemas_auto();
+        writer.pdfua(1);
+        assert_eq!(
+            writer.errors(),
+            [XmpError::UndescribedSchema(Namespace::PdfUAId.url().into())],
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Summaries of written metadata.
+
+use crate::{Namespace, XmpWriter};
+
+impl XmpWriter<'_> {
+    /// Summarize the metadata written so far.
+    ///
+    /// The report lists all namespaces that were used, the top-level
+    /// properties in each of them, and how many bytes each property takes up
+    /// in the packet. It is computed from the writer's bookkeeping without
+    /// parsing the XML.
+    pub fn coverage_report(&self) -> CoverageReport {
+        let mut starts: Vec<_> = self.properties.iter().collect();
+        starts.sort_by_key(|(_, &start)| start);
+
+        let mut namespaces: Vec<NamespaceCoverage> = self
+            .namespaces
+            .iter()
+            .filter(|&ns| ns != &Namespace::Rdf)
+            .map(|ns| NamespaceCoverage {
+                prefix: ns.prefix().into(),
+                url: ns.url().into(),
+                properties: vec![],
+            })
+            .collect();
+
+        for (i, ((namespace, name), &start)) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).map_or(self.buf.len(), |(_, &end)| end);
+            let property = PropertyCoverage { name: name.clone(), size: end - start };
+            match namespaces.iter_mut().find(|ns| ns.url == namespace.url()) {
+                Some(ns) => ns.properties.push(property),
+                None => namespaces.push(NamespaceCoverage {
+                    prefix: namespace.prefix().into(),
+                    url: namespace.url().into(),
+                    properties: vec![property],
+                }),
+            }
+        }
+
+        CoverageReport { namespaces, size: self.len() }
+    }
+}
+
+/// A summary of the metadata written by an [`XmpWriter`].
+///
+/// Created by [`XmpWriter::coverage_report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    /// The namespaces that were used, ordered like in the packet.
+    pub namespaces: Vec<NamespaceCoverage>,
+    /// The size of all properties in bytes, without the packet header and
+    /// trailer.
+    pub size: usize,
+}
+
+impl CoverageReport {
+    /// The number of top-level properties.
+    pub fn property_count(&self) -> usize {
+        self.namespaces.iter().map(|ns| ns.properties.len()).sum()
+    }
+}
+
+/// The properties written in a namespace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamespaceCoverage {
+    /// The prefix of the namespace.
+    pub prefix: String,
+    /// The URL of the namespace.
+    pub url: String,
+    /// The top-level properties in the namespace, in the order they were
+    /// written. Empty for namespaces that are only used in structs.
+    pub properties: Vec<PropertyCoverage>,
+}
+
+/// A top-level property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyCoverage {
+    /// The name of the property without prefix.
+    pub name: String,
+    /// The approximate size of the property in bytes, including comments
+    /// written directly after it.
+    pub size: usize,
+}
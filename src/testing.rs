@@ -0,0 +1,217 @@
+//! Helpers for testing generated packets.
+//!
+//! Packets are compared by their content instead of their exact bytes: the
+//! properties are parsed and listed in a canonical form, one per line and
+//! sorted by namespace and name. Differences in whitespace between
+//! elements, the order of namespace declarations and properties, the order
+//! of the items in bags, and the packet wrapper do not matter. Text values
+//! are compared verbatim.
+//!
+//! ```
+//! use xmp_writer::testing::assert_equivalent;
+//! use xmp_writer::XmpWriter;
+//!
+//! let mut writer = XmpWriter::new();
+//! writer.creator_tool("xmp-writer");
+//! writer.title([(None, "Test")]);
+//!
+//! assert_equivalent(
+//!     &writer.finish(None),
+//!     r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#">
+//!         <rdf:Description xmlns:dc="http://purl.org/dc/elements/1.1/"
+//!                          xmlns:xmp="http://ns.adobe.com/xap/1.0/">
+//!           <dc:title><rdf:Alt>
+//!             <rdf:li xml:lang="x-default">Test</rdf:li>
+//!           </rdf:Alt></dc:title>
+//!           <xmp:CreatorTool>xmp-writer</xmp:CreatorTool>
+//!         </rdf:Description>
+//!       </rdf:RDF>"#,
+//! );
+//! ```
+//!
+//! Packets are read with [`XmpPacket::parse`], so a mistake that the writer
+//! and the parser of this crate share goes unnoticed. Check packets with
+//! corner cases like escaped text, language alternatives, and qualifiers
+//! against another XMP implementation as well.
+//!
+//! Enabled by the `testing` feature.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use crate::{Property, RdfCollectionType, Value, XmpPacket, XmpType};
+
+/// The environment variable that makes [`assert_golden`] overwrite the
+/// golden files instead of comparing against them.
+pub const BLESS_VAR: &str = "XMP_WRITER_BLESS";
+
+/// Assert that a packet is well-formed XML and RDF.
+///
+/// # Panics
+/// Panics with the position and reason if the packet cannot be parsed.
+#[track_caller]
+pub fn assert_well_formed(packet: &str) {
+    if let Err(err) = XmpPacket::parse(packet) {
+        panic!("packet is not well-formed: {err}\n{packet}");
+    }
+}
+
+/// List the properties of a packet in a canonical form.
+///
+/// Each top-level property and struct field is on its own line, followed by
+/// the items of its value on indented lines. Properties and fields are
+/// sorted by their namespace URL and name.
+///
+/// # Panics
+/// Panics if the packet is not well-formed.
+#[track_caller]
+pub fn normalize(packet: &str) -> String {
+    let parsed = match XmpPacket::parse(packet) {
+        Ok(parsed) => parsed,
+        Err(err) => panic!("packet is not well-formed: {err}\n{packet}"),
+    };
+
+    let mut out = String::new();
+    let properties: Vec<_> = parsed.properties().collect();
+    write_properties(&mut out, &properties, 0);
+    out
+}
+
+/// Assert that two packets have the same properties.
+///
+/// # Panics
+/// Panics with the differing lines of the normalized forms if the packets
+/// differ, or if either packet is not well-formed.
+#[track_caller]
+pub fn assert_equivalent(actual: &str, expected: &str) {
+    let actual = normalize(actual);
+    let expected = normalize(expected);
+    if actual != expected {
+        panic!("packets differ\n{}", diff(&expected, &actual));
+    }
+}
+
+/// Assert that a packet has the same properties as the packet in a golden
+/// file.
+///
+/// If the environment variable [`BLESS_VAR`] is set, the golden file is
+/// created or overwritten with the packet instead.
+///
+/// # Panics
+/// Panics if the packets differ, if either is not well-formed, or if the
+/// golden file cannot be read or written.
+#[track_caller]
+pub fn assert_golden(actual: &str, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    if std::env::var_os(BLESS_VAR).is_some() {
+        assert_well_formed(actual);
+        if let Err(err) = std::fs::write(path, actual) {
+            panic!("failed to write golden file {}: {err}", path.display());
+        }
+        return;
+    }
+
+    let expected = match std::fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(err) => panic!(
+            "failed to read golden file {} ({err}), set {BLESS_VAR}=1 to create it",
+            path.display(),
+        ),
+    };
+
+    let actual = normalize(actual);
+    let expected = normalize(&expected);
+    if actual != expected {
+        panic!(
+            "packet differs from golden file {}, set {BLESS_VAR}=1 to update it\n{}",
+            path.display(),
+            diff(&expected, &actual),
+        );
+    }
+}
+
+/// Write properties sorted by namespace URL and name.
+fn write_properties(out: &mut String, properties: &[&Property], depth: usize) {
+    let mut properties = properties.to_vec();
+    properties
+        .sort_by(|a, b| (a.namespace.url(), &a.name).cmp(&(b.namespace.url(), &b.name)));
+    for property in properties {
+        indent(out, depth);
+        write!(out, "{} =", property.qname()).unwrap();
+        write_value(out, &property.value, depth);
+    }
+}
+
+/// Write a value after its label. Simple values stay on the line of the
+/// label, the items of other values follow on indented lines.
+fn write_value(out: &mut String, value: &Value, depth: usize) {
+    match value {
+        Value::Text(text) => writeln!(out, " {text:?}").unwrap(),
+        Value::Integer(int) => write_simple(out, int),
+        Value::Real(real) => write_simple(out, real),
+        Value::Bool(b) => write_simple(out, b),
+        Value::Date(date) => write_simple(out, date),
+        Value::Array(kind, items) => {
+            let mut rendered: Vec<String> = items
+                .iter()
+                .map(|item| {
+                    let mut line = String::new();
+                    indent(&mut line, depth + 1);
+                    line.push('-');
+                    write_value(&mut line, item, depth + 1);
+                    line
+                })
+                .collect();
+            if *kind == RdfCollectionType::Bag {
+                rendered.sort();
+            }
+            writeln!(out, " {kind:?}").unwrap();
+            out.extend(rendered);
+        }
+        Value::LangAlt(items) => {
+            out.push_str(" Alt\n");
+            for (lang, text) in items {
+                indent(out, depth + 1);
+                writeln!(out, "[{lang}] {text:?}").unwrap();
+            }
+        }
+        Value::Struct(fields) => {
+            out.push('\n');
+            let fields: Vec<_> = fields.iter().collect();
+            write_properties(out, &fields, depth + 1);
+        }
+        Value::Qualified(value, qualifiers) => {
+            write_value(out, value, depth);
+            for qualifier in qualifiers {
+                indent(out, depth + 1);
+                write!(out, "?{} =", qualifier.qname()).unwrap();
+                write_value(out, &qualifier.value, depth + 1);
+            }
+        }
+    }
+}
+
+/// Write a simple value in its XMP form.
+fn write_simple(out: &mut String, value: &impl XmpType) {
+    out.push(' ');
+    value.write(out);
+    out.push('\n');
+}
+
+/// Indent a line.
+fn indent(out: &mut String, depth: usize) {
+    out.extend(std::iter::repeat_n("  ", depth));
+}
+
+/// Mark the lines that only appear in one of the normalized forms.
+fn diff(expected: &str, actual: &str) -> String {
+    let mut out = String::new();
+    for line in expected.lines() {
+        let marker = if actual.lines().any(|l| l == line) { ' ' } else { '-' };
+        writeln!(out, "{marker} {line}").unwrap();
+    }
+    for line in actual.lines().filter(|line| !expected.lines().any(|l| l == *line)) {
+        writeln!(out, "+ {line}").unwrap();
+    }
+    out
+}
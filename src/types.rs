@@ -1,173 +1,289 @@
 use std::{
+    borrow::Cow,
+    collections::BTreeMap,
     fmt::{Debug, Write},
     iter,
+    ops::Range,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
 };
 
-use crate::XmpWriter;
+use crate::{MissingField, XmpError, XmpWriter, HEADER_CAPACITY};
+
+/// Define the [`Namespace`] enum with its predefined variants and their
+/// human-readable names, prefixes, and URLs.
+macro_rules! namespaces {
+    ($(
+        $(#[$attr:meta])*
+        $variant:ident => $name:literal, $prefix:literal, $url:literal;
+    )*) => {
+        /// XML Namespaces for the XMP properties.
+        #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+        #[allow(missing_docs)]
+        #[non_exhaustive]
+        pub enum Namespace<'a> {
+            $($(#[$attr])* $variant,)*
+            Custom(Box<CustomNamespace<'a>>),
+        }
 
-/// XML Namespaces for the XMP properties.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-#[allow(missing_docs)]
-#[non_exhaustive]
-pub enum Namespace<'a> {
-    Rdf,
-    DublinCore,
-    Xmp,
-    XmpRights,
-    XmpResourceRef,
-    XmpResourceEvent,
-    XmpVersion,
-    XmpJob,
-    XmpJobManagement,
-    XmpColorant,
-    XmpFont,
-    XmpDimensions,
-    XmpMedia,
-    XmpPaged,
-    XmpDynamicMedia,
-    XmpImage,
-    XmpIdq,
-    AdobePdf,
+        /// The predefined namespaces, in the order of their variants.
+        const BUILTIN: &[Namespace<'static>] =
+            &[$($(#[$attr])* Namespace::$variant,)*];
+
+        /// The indices of the predefined namespaces in [`BUILTIN`].
+        #[allow(dead_code)]
+        enum BuiltinIndex {
+            $($(#[$attr])* $variant,)*
+        }
+
+        impl<'a> Namespace<'a> {
+            /// Returns a human-readable name for the namespace.
+            pub fn name(&self) -> &str {
+                match self {
+                    $($(#[$attr])* Self::$variant => $name,)*
+                    Self::Custom(custom) => &custom.name,
+                }
+            }
+
+            /// Returns the URL for the namespace.
+            pub fn url(&self) -> &str {
+                match self {
+                    $($(#[$attr])* Self::$variant => $url,)*
+                    Self::Custom(custom) => &custom.url,
+                }
+            }
+
+            /// Returns the prefix for the namespace.
+            pub fn prefix(&self) -> &str {
+                match self {
+                    $($(#[$attr])* Self::$variant => $prefix,)*
+                    Self::Custom(custom) => &custom.namespace,
+                }
+            }
+
+            /// Returns the predefined namespace with the given URL, if any.
+            ///
+            /// This is the reverse of [`Namespace::url`] for the predefined
+            /// namespaces and can be used to recognize standard schemas in
+            /// external data. The URL must match exactly. The PDF/A namespaces
+            /// are only recognized with the `pdfa` feature.
+            ///
+            /// ```
+            /// use xmp_writer::Namespace;
+            ///
+            /// let dc = Namespace::from_url("http://purl.org/dc/elements/1.1/");
+            /// assert_eq!(dc, Some(Namespace::DublinCore));
+            /// assert_eq!(Namespace::from_url("http://example.com/ns/"), None);
+            /// ```
+            pub fn from_url(url: &str) -> Option<Self> {
+                Some(match url {
+                    $($(#[$attr])* $url => Self::$variant,)*
+                    _ => return None,
+                })
+            }
+
+            /// Returns the index of a predefined namespace in [`BUILTIN`].
+            fn builtin_index(&self) -> Option<u32> {
+                match self {
+                    $($(#[$attr])* Self::$variant => Some(BuiltinIndex::$variant as u32),)*
+                    Self::Custom(_) => None,
+                }
+            }
+
+            /// Returns the predefined namespace with the given prefix, if any.
+            pub(crate) fn with_prefix(prefix: &str) -> Option<Self> {
+                Some(match prefix {
+                    $($(#[$attr])* $prefix => Self::$variant,)*
+                    _ => return None,
+                })
+            }
+        }
+    };
+}
+
+namespaces! {
+    Rdf => "RDF", "rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+    DublinCore => "Dublin Core", "dc", "http://purl.org/dc/elements/1.1/";
+    Xmp => "XMP", "xmp", "http://ns.adobe.com/xap/1.0/";
+    XmpRights => "XMP Rights", "xmpRights", "http://ns.adobe.com/xap/1.0/rights/";
+    XmpResourceRef => "XMP Resource Reference", "stRef",
+        "http://ns.adobe.com/xap/1.0/sType/ResourceRef#";
+    XmpResourceEvent => "XMP Resource Event", "stEvt",
+        "http://ns.adobe.com/xap/1.0/sType/ResourceEvent#";
+    XmpVersion => "XMP Version", "stVer", "http://ns.adobe.com/xap/1.0/sType/Version#";
+    XmpJob => "XMP Job Management", "stJob", "http://ns.adobe.com/xap/1.0/sType/Job#";
+    XmpJobManagement => "XMP Job Management", "xmpBJ", "http://ns.adobe.com/xap/1.0/bj/";
+    XmpColorant => "XMP Colorant", "xmpG", "http://ns.adobe.com/xap/1.0/g/";
+    XmpFont => "XMP Font", "stFnt", "http://ns.adobe.com/xap/1.0/sType/Font#";
+    XmpDimensions => "XMP Dimensions", "stDim",
+        "http://ns.adobe.com/xap/1.0/sType/Dimensions#";
+    XmpMedia => "XMP Media Management", "xmpMM", "http://ns.adobe.com/xap/1.0/mm/";
+    XmpPaged => "XMP Paged Text", "xmpTPg", "http://ns.adobe.com/xap/1.0/t/pg/";
+    XmpDynamicMedia => "XMP Dynamic Media", "xmpDM",
+        "http://ns.adobe.com/xap/1.0/DynamicMedia/";
+    XmpImage => "XMP Image", "xmpGImg", "http://ns.adobe.com/xap/1.0/g/img/";
+    XmpIdq => "XMP Identifier Qualifier", "xmpidq",
+        "http://ns.adobe.com/xmp/Identifier/qual/1.0/";
+    AdobePdf => "Adobe PDF", "pdf", "http://ns.adobe.com/pdf/1.3/";
     #[cfg(feature = "pdfa")]
-    PdfAId,
-    PdfUAId,
-    PdfXId,
+    PdfAId => "PDF/A Identification", "pdfaid", "http://www.aiim.org/pdfa/ns/id/";
+    PdfUAId => "PDF/UA Identification", "pdfuaid", "http://www.aiim.org/pdfua/ns/id/";
+    PdfXId => "PDF/X Identification", "pdfxid", "http://www.npes.org/pdfx/ns/id/";
+    AdobePdfX => "Adobe PDF/X", "pdfx", "http://ns.adobe.com/pdfx/1.3/";
+    Tiff => "TIFF", "tiff", "http://ns.adobe.com/tiff/1.0/";
+    Exif => "Exif", "exif", "http://ns.adobe.com/exif/1.0/";
     #[cfg(feature = "pdfa")]
-    PdfAExtension,
+    PdfAExtension => "PDF/A Extension schema container", "pdfaExtension",
+        "http://www.aiim.org/pdfa/ns/extension/";
     #[cfg(feature = "pdfa")]
-    PdfASchema,
+    PdfASchema => "PDF/A Schema container", "pdfaSchema",
+        "http://www.aiim.org/pdfa/ns/schema#";
     #[cfg(feature = "pdfa")]
-    PdfAProperty,
+    PdfAProperty => "PDF/A Property", "pdfaProperty",
+        "http://www.aiim.org/pdfa/ns/property#";
     #[cfg(feature = "pdfa")]
-    PdfAType,
+    PdfAType => "PDF/A Type", "pdfaType", "http://www.aiim.org/pdfa/ns/type#";
     #[cfg(feature = "pdfa")]
-    PdfAField,
-    Custom(Box<CustomNamespace<'a>>),
+    PdfAField => "PDF/A Field", "pdfaField", "http://www.aiim.org/pdfa/ns/field#";
+}
+
+/// A set of namespaces that iterates in the order of [`Namespace`]'s `Ord`
+/// implementation.
+///
+/// Predefined namespaces are stored as bits, so that declaring them for
+/// each element neither allocates nor compares custom namespaces.
+#[derive(Default, Clone)]
+pub(crate) struct NamespaceSet<'a> {
+    builtin: u32,
+    custom: Vec<Namespace<'a>>,
+}
+
+// The predefined namespaces must fit into the bits of `NamespaceSet`.
+const _: () = assert!(BUILTIN.len() <= 32);
+
+impl<'a> NamespaceSet<'a> {
+    /// Add a namespace to the set if it is not contained yet.
+    pub(crate) fn insert(&mut self, namespace: &Namespace<'a>) {
+        match namespace.builtin_index() {
+            Some(i) => self.builtin |= 1 << i,
+            None => {
+                if let Err(i) = self.custom.binary_search(namespace) {
+                    self.custom.insert(i, namespace.clone());
+                }
+            }
+        }
+    }
+
+    /// Whether the set contains a namespace.
+    pub(crate) fn contains(&self, namespace: &Namespace) -> bool {
+        match namespace.builtin_index() {
+            Some(i) => self.builtin & (1 << i) != 0,
+            None => self.custom.iter().any(|ns| ns == namespace),
+        }
+    }
+
+    /// Iterate over the namespaces in the set.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Namespace<'a>> {
+        BUILTIN
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| self.builtin & (1 << i) != 0)
+            .map(|(_, ns)| ns)
+            .chain(&self.custom)
+    }
+
+    /// Remove all namespaces from the set.
+    pub(crate) fn clear(&mut self) {
+        self.builtin = 0;
+        self.custom.clear();
+    }
+}
+
+impl std::fmt::Debug for NamespaceSet<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// A handle for a namespace registered with
+/// [`XmpWriter::register_namespace`](crate::XmpWriter::register_namespace).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct NamespaceId {
+    /// The writer that registered the namespace.
+    pub(crate) writer: WriterId,
+    /// The index of the namespace in the writer's registered namespaces.
+    pub(crate) index: usize,
+}
+
+/// Identifies a writer and its clones, so that namespace handles cannot be
+/// resolved by a writer that did not create them.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct WriterId(usize);
+
+impl Default for WriterId {
+    fn default() -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// A namespace argument: either a [`Namespace`] or a [`NamespaceId`].
+pub trait ToNamespace<'n> {
+    /// Resolve the namespace with the namespaces registered in the writer.
+    fn to_namespace(self, writer: &XmpWriter<'n>) -> Namespace<'n>;
+}
+
+impl<'n> ToNamespace<'n> for Namespace<'n> {
+    fn to_namespace(self, _: &XmpWriter<'n>) -> Namespace<'n> {
+        self
+    }
+}
+
+impl<'n> ToNamespace<'n> for NamespaceId {
+    fn to_namespace(self, writer: &XmpWriter<'n>) -> Namespace<'n> {
+        match writer.registered.get(self.index) {
+            Some(custom) if self.writer == writer.id => {
+                Namespace::Custom(Box::new(custom.clone()))
+            }
+            _ => panic!("namespace handle was not registered with this writer"),
+        }
+    }
 }
 
 /// A custom XML namespace.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// The name, prefix, and URL can be borrowed or owned, e.g. when they are
+/// only known at runtime.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CustomNamespace<'a> {
-    name: &'a str,
-    namespace: &'a str,
-    url: &'a str,
+    name: Cow<'a, str>,
+    namespace: Cow<'a, str>,
+    url: Cow<'a, str>,
 }
 
-impl<'a> Namespace<'a> {
-    /// Returns a human-readable name for the namespace.
-    pub const fn name(&self) -> &'a str {
-        match self {
-            Self::Rdf => "RDF",
-            Self::DublinCore => "Dublin Core",
-            Self::Xmp => "XMP",
-            Self::XmpRights => "XMP Rights",
-            Self::XmpResourceRef => "XMP Resource Reference",
-            Self::XmpResourceEvent => "XMP Resource Event",
-            Self::XmpVersion => "XMP Version",
-            Self::XmpJob => "XMP Job Management",
-            Self::XmpColorant => "XMP Colorant",
-            Self::XmpFont => "XMP Font",
-            Self::XmpDimensions => "XMP Dimensions",
-            Self::XmpMedia => "XMP Media Management",
-            Self::XmpJobManagement => "XMP Job Management",
-            Self::XmpPaged => "XMP Paged Text",
-            Self::XmpDynamicMedia => "XMP Dynamic Media",
-            Self::XmpImage => "XMP Image",
-            Self::AdobePdf => "Adobe PDF",
-            Self::XmpIdq => "XMP Identifier Qualifier",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAId => "PDF/A Identification",
-            Self::PdfUAId => "PDF/UA Identification",
-            Self::PdfXId => "PDF/X Identification",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAExtension => "PDF/A Extension schema container",
-            #[cfg(feature = "pdfa")]
-            Self::PdfASchema => "PDF/A Schema container",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAProperty => "PDF/A Property",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAType => "PDF/A Type",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAField => "PDF/A Field",
-            Self::Custom(custom) => custom.name,
-        }
-    }
-
-    /// Returns the URL for the namespace.
-    pub fn url(&self) -> &'a str {
-        match self {
-            Self::Rdf => "http://www.w3.org/1999/02/22-rdf-syntax-ns#",
-            Self::DublinCore => "http://purl.org/dc/elements/1.1/",
-            Self::Xmp => "http://ns.adobe.com/xap/1.0/",
-            Self::XmpRights => "http://ns.adobe.com/xap/1.0/rights/",
-            Self::XmpResourceRef => "http://ns.adobe.com/xap/1.0/sType/ResourceRef#",
-            Self::XmpResourceEvent => "http://ns.adobe.com/xap/1.0/sType/ResourceEvent#",
-            Self::XmpVersion => "http://ns.adobe.com/xap/1.0/sType/Version#",
-            Self::XmpJob => "http://ns.adobe.com/xap/1.0/sType/Job#",
-            Self::XmpColorant => "http://ns.adobe.com/xap/1.0/g/",
-            Self::XmpFont => "http://ns.adobe.com/xap/1.0/sType/Font#",
-            Self::XmpDimensions => "http://ns.adobe.com/xap/1.0/sType/Dimensions#",
-            Self::XmpMedia => "http://ns.adobe.com/xap/1.0/mm/",
-            Self::XmpJobManagement => "http://ns.adobe.com/xap/1.0/bj/",
-            Self::XmpPaged => "http://ns.adobe.com/xap/1.0/t/pg/",
-            Self::XmpDynamicMedia => "http://ns.adobe.com/xap/1.0/DynamicMedia/",
-            Self::XmpImage => "http://ns.adobe.com/xap/1.0/g/img/",
-            Self::AdobePdf => "http://ns.adobe.com/pdf/1.3/",
-            Self::XmpIdq => "http://ns.adobe.com/xmp/Identifier/qual/1.0/",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAId => "http://www.aiim.org/pdfa/ns/id/",
-            Self::PdfUAId => "http://www.aiim.org/pdfua/ns/id/",
-            Self::PdfXId => "http://www.npes.org/pdfx/ns/id/",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAExtension => "http://www.aiim.org/pdfa/ns/extension/",
-            #[cfg(feature = "pdfa")]
-            Self::PdfASchema => "http://www.aiim.org/pdfa/ns/schema#",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAProperty => "http://www.aiim.org/pdfa/ns/property#",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAType => "http://www.aiim.org/pdfa/ns/type#",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAField => "http://www.aiim.org/pdfa/ns/field#",
-            Self::Custom(custom) => custom.url,
-        }
-    }
-
-    /// Returns the prefix for the namespace.
-    pub fn prefix(&self) -> &'a str {
-        match self {
-            Self::Rdf => "rdf",
-            Self::DublinCore => "dc",
-            Self::Xmp => "xmp",
-            Self::XmpRights => "xmpRights",
-            Self::XmpResourceRef => "stRef",
-            Self::XmpResourceEvent => "stEvt",
-            Self::XmpVersion => "stVer",
-            Self::XmpJob => "stJob",
-            Self::XmpColorant => "xmpG",
-            Self::XmpFont => "stFnt",
-            Self::XmpDimensions => "stDim",
-            Self::XmpMedia => "xmpMM",
-            Self::XmpJobManagement => "xmpBJ",
-            Self::XmpPaged => "xmpTPg",
-            Self::XmpDynamicMedia => "xmpDM",
-            Self::XmpImage => "xmpGImg",
-            Self::AdobePdf => "pdf",
-            Self::XmpIdq => "xmpidq",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAId => "pdfaid",
-            Self::PdfUAId => "pdfuaid",
-            Self::PdfXId => "pdfxid",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAExtension => "pdfaExtension",
-            #[cfg(feature = "pdfa")]
-            Self::PdfASchema => "pdfaSchema",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAProperty => "pdfaProperty",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAType => "pdfaType",
-            #[cfg(feature = "pdfa")]
-            Self::PdfAField => "pdfaField",
-            Self::Custom(custom) => custom.namespace,
+impl<'a> CustomNamespace<'a> {
+    /// Create a new custom namespace with a human-readable name, a prefix, and
+    /// the namespace URL.
+    pub const fn new(name: &'a str, prefix: &'a str, url: &'a str) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            namespace: Cow::Borrowed(prefix),
+            url: Cow::Borrowed(url),
+        }
+    }
+
+    /// Create a new custom namespace from strings that may be owned, e.g.
+    /// because they are only known at runtime. See [`CustomNamespace::new`].
+    pub fn from_strings(
+        name: impl Into<Cow<'a, str>>,
+        prefix: impl Into<Cow<'a, str>>,
+        url: impl Into<Cow<'a, str>>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            namespace: prefix.into(),
+            url: url.into(),
         }
     }
 }
@@ -177,10 +293,18 @@ impl<'a> Namespace<'a> {
 /// Created by [`XmpWriter::element`], [`Array::element`],
 /// [`Array::element_with_attrs`], [`Struct::element`],
 /// [`Struct::element_with_attrs`].
+#[derive(Debug)]
 pub struct Element<'a, 'n: 'a> {
     writer: &'a mut XmpWriter<'n>,
     name: &'a str,
     namespace: Namespace<'n>,
+    /// Whether nothing has been written for the element yet. The start tag
+    /// is only written once a value is committed, so that unused elements
+    /// leave no trace.
+    deferred: bool,
+    /// Whether the element is a top-level property, which is recorded by the
+    /// writer once its start tag is written.
+    top_level: bool,
 }
 
 impl<'a, 'n: 'a> Element<'a, 'n> {
@@ -189,58 +313,260 @@ impl<'a, 'n: 'a> Element<'a, 'n> {
         name: &'a str,
         namespace: Namespace<'n>,
     ) -> Self {
-        Self::with_attrs(writer, name, namespace, iter::empty())
+        Self {
+            top_level: true,
+            ..Self::with_attrs(writer, name, namespace, iter::empty::<(&str, &str)>())
+        }
+    }
+
+    /// Start an element whose name and prefix the caller already checked.
+    pub(crate) fn start_unchecked(
+        writer: &'a mut XmpWriter<'n>,
+        name: &'a str,
+        namespace: Namespace<'n>,
+    ) -> Self {
+        Self {
+            top_level: true,
+            ..Self::new(writer, name, namespace, iter::empty::<(&str, &str)>())
+        }
     }
 
+    /// Start an element, recording an error if its name or prefix is not a
+    /// valid XML name.
     fn with_attrs<'b>(
         writer: &'a mut XmpWriter<'n>,
         name: &'a str,
         namespace: Namespace<'n>,
-        attrs: impl IntoIterator<Item = (&'b str, &'b str)>,
+        attrs: impl IntoIterator<Item = (&'b str, impl XmpAttrValue)>,
     ) -> Self {
-        write!(writer.buf, "<{}:{}", namespace.prefix(), name).unwrap();
+        for name in [name, prefix_of(&writer.prefixes, &namespace)] {
+            if !is_ncname(name) {
+                writer
+                    .errors
+                    .push(XmpError::InvalidName(NameError { name: name.into() }));
+            }
+        }
+        Self::new(writer, name, namespace, attrs)
+    }
 
+    fn new<'b>(
+        writer: &'a mut XmpWriter<'n>,
+        name: &'a str,
+        namespace: Namespace<'n>,
+        attrs: impl IntoIterator<Item = (&'b str, impl XmpAttrValue)>,
+    ) -> Self {
+        let buf = &mut writer.attrs;
+        buf.clear();
         for (key, value) in attrs {
-            write!(writer.buf, " {}=\"{}\"", key, value).unwrap();
+            if let Err(err) = XmpAttrValue::check(&value) {
+                let skip = err == XmpError::NonFiniteNumber;
+                writer.errors.push(err);
+                if skip {
+                    continue;
+                }
+            }
+            write!(buf, " {}=\"", key).unwrap();
+            value.write_attr(buf);
+            buf.push('"');
         }
 
-        writer.namespaces.insert(namespace.clone());
-        Element { writer, name, namespace }
+        Element {
+            writer,
+            name,
+            namespace,
+            deferred: true,
+            top_level: false,
+        }
+    }
+
+    /// Write the start tag of the element if it was not written yet.
+    fn open(&mut self) {
+        if self.deferred {
+            self.writer.close_open_tag();
+            if self.top_level {
+                self.writer.record_property(self.name, &self.namespace);
+            }
+            write!(
+                self.writer.buf,
+                "<{}:{}{}",
+                prefix_of(&self.writer.prefixes, &self.namespace),
+                self.name,
+                self.writer.attrs,
+            )
+            .unwrap();
+            self.writer.declare(&self.namespace);
+            self.deferred = false;
+        }
+    }
+
+    /// Add an `xml:lang` attribute to the start tag of the element.
+    pub(crate) fn lang(self, lang: &str) -> Self {
+        let buf = &mut self.writer.attrs;
+        buf.push_str(" xml:lang=\"");
+        lang.write_attr(buf);
+        buf.push('"');
+        self
     }
 
     /// Sets the property to a primitive value.
-    pub fn value(self, val: impl XmpType) {
+    pub fn value(mut self, val: impl XmpType) {
+        if let Err(err) = val.check() {
+            // Non-finite numbers have no representation in XMP.
+            let skip = err == XmpError::NonFiniteNumber;
+            self.writer.errors.push(err);
+            if skip {
+                return;
+            }
+        }
+
+        // Fields of a compact struct become attributes if they are simple
+        // values.
+        if self.writer.open_tag && self.writer.attrs.is_empty() {
+            self.writer.declare(&self.namespace);
+            let buf = &mut self.writer.buf;
+            write!(
+                buf,
+                " {}:{}=\"",
+                prefix_of(&self.writer.prefixes, &self.namespace),
+                self.name
+            )
+            .unwrap();
+            let start = buf.len();
+            val.write(buf);
+            escape_attr_value(buf, start);
+            buf.push('"');
+            return;
+        }
+
+        self.open();
         self.writer.buf.push('>');
         val.write(&mut self.writer.buf);
         self.close();
     }
 
+    /// Sets the property to a primitive value if there is one.
+    ///
+    /// If the value is `None`, nothing is written and the property counts as
+    /// not written, e.g. for [`XmpWriter::has_property`].
+    pub fn opt_value(self, val: Option<impl XmpType>) {
+        if let Some(val) = val {
+            self.value(val);
+        }
+    }
+
     /// Start writing a struct as the property value.
+    ///
+    /// The struct is serialized according to the writer's
+    /// [`StructForm`](crate::XmpWriter::set_struct_form).
     pub fn obj(self) -> Struct<'a, 'n> {
-        self.writer.namespaces.insert(Namespace::Rdf);
-        self.writer.buf.push_str(" rdf:parseType=\"Resource\">");
-        Struct::start(self.writer, self.name, self.namespace)
+        let form = self.writer.struct_form;
+        self.obj_as(form)
+    }
+
+    /// Start writing a struct as the property value, serialized in the given
+    /// form regardless of the writer's setting.
+    pub fn obj_as(self, form: StructForm) -> Struct<'a, 'n> {
+        self.start_obj(form, None)
+    }
+
+    /// Set the property value to a struct written by a type implementing
+    /// [`XmpStruct`].
+    pub fn obj_value(self, val: &(impl XmpStruct + ?Sized)) {
+        val.write(&mut self.obj());
+    }
+
+    /// Start writing a struct with an `rdf:nodeID` as the property value.
+    ///
+    /// Other properties can reference the struct with [`Element::node_ref`]
+    /// instead of repeating it. Because `rdf:parseType="Resource"` cannot
+    /// carry a node ID, the struct is written as a nested `rdf:Description`
+    /// unless the writer uses [`StructForm::Compact`].
+    pub fn obj_with_node_id(self, id: &str) -> Struct<'a, 'n> {
+        let form = match self.writer.struct_form {
+            StructForm::ParseTypeResource => StructForm::Description,
+            form => form,
+        };
+        self.start_obj(form, Some(id))
+    }
+
+    fn start_obj(mut self, form: StructForm, node_id: Option<&str>) -> Struct<'a, 'n> {
+        self.open();
+        self.writer.namespaces.insert(&Namespace::Rdf);
+        if form == StructForm::ParseTypeResource {
+            self.writer.buf.push_str(" rdf:parseType=\"Resource\">");
+        } else {
+            self.writer.buf.push_str("><rdf:Description");
+            if let Some(id) = node_id {
+                self.writer.buf.push_str(" rdf:nodeID=\"");
+                id.write_attr(&mut self.writer.buf);
+                self.writer.buf.push('"');
+            }
+
+            if form == StructForm::Compact {
+                self.writer.open_tag = true;
+            } else {
+                self.writer.buf.push('>');
+            }
+        }
+        Struct::start(self.writer, self.name, self.namespace, form)
+    }
+
+    /// Set the property value to a reference to a struct that was written with
+    /// [`Element::obj_with_node_id`].
+    pub fn node_ref(mut self, id: &str) {
+        self.open();
+        self.writer.namespaces.insert(&Namespace::Rdf);
+        self.writer.buf.push_str(" rdf:nodeID=\"");
+        id.write_attr(&mut self.writer.buf);
+        self.writer.buf.push_str("\"/>");
+    }
+
+    /// Start writing a qualified value as the property value.
+    ///
+    /// The value is written as the `rdf:value` of a struct. Use the returned
+    /// struct to attach qualifiers like `xmpidq:Scheme` to the value.
+    pub fn qualified(self, val: impl XmpType) -> Struct<'a, 'n> {
+        let mut stc = self.obj();
+        stc.element("value", Namespace::Rdf).value(val);
+        stc
     }
 
     /// Start writing an array as the property value.
-    pub fn array(self, kind: RdfCollectionType) -> Array<'a, 'n> {
+    pub fn array(mut self, kind: RdfCollectionType) -> Array<'a, 'n> {
+        self.open();
         self.writer.buf.push('>');
         Array::start(self.writer, kind, self.name, self.namespace)
     }
 
     fn close(self) {
-        write!(self.writer.buf, "</{}:{}>", self.namespace.prefix(), self.name).unwrap();
+        write!(
+            self.writer.buf,
+            "</{}:{}>",
+            prefix_of(&self.writer.prefixes, &self.namespace),
+            self.name
+        )
+        .unwrap();
     }
 
     /// Set a language alternative of primitive values as the property value.
+    ///
+    /// If [`XmpWriter::set_fix_x_default`] is enabled, the `x-default` item
+    /// is moved to the front or, if there is none, the first item is repeated
+    /// as the `x-default` item.
     pub fn language_alternative<'b>(
         self,
         items: impl IntoIterator<Item = (Option<LangId<'b>>, &'b str)>,
     ) {
+        let items: Box<dyn Iterator<Item = _>> = if self.writer.fix_x_default {
+            Box::new(fix_x_default(items.into_iter().collect()).into_iter())
+        } else {
+            Box::new(items.into_iter())
+        };
+
         let mut array = self.array(RdfCollectionType::Alt);
         for (lang, value) in items {
             array
-                .element_with_attrs(iter::once(("xml:lang", lang.unwrap_or_default().0)))
+                .element_with_attrs(iter::once(("xml:lang", lang.unwrap_or_default())))
                 .value(value);
         }
         drop(array);
@@ -269,11 +595,56 @@ impl<'a, 'n: 'a> Element<'a, 'n> {
             array.element().value(item);
         }
     }
+
+    /// Set an array of texts in different languages as the property value.
+    ///
+    /// Each item gets its own `xml:lang` qualifier. Unlike a
+    /// [language alternative](Self::language_alternative), the items are not
+    /// alternatives of each other, e.g. the names of creators that are
+    /// written in different scripts.
+    pub fn localized_array<'b>(
+        self,
+        kind: RdfCollectionType,
+        items: impl IntoIterator<Item = (LangId<'b>, &'b str)>,
+    ) {
+        let mut array = self.array(kind);
+        for (lang, value) in items {
+            array.element_with_attrs(iter::once(("xml:lang", lang))).value(value);
+        }
+    }
+
+    /// Set an array of structs as the property value.
+    pub fn struct_array(
+        self,
+        kind: RdfCollectionType,
+        items: impl IntoIterator<Item = impl XmpStruct>,
+    ) {
+        self.array(kind).extend(items);
+    }
+}
+
+/// Move the `x-default` item of a language alternative to the front, adding
+/// one if necessary.
+fn fix_x_default<'b>(
+    mut items: Vec<(Option<LangId<'b>>, &'b str)>,
+) -> Vec<(Option<LangId<'b>>, &'b str)> {
+    let is_default =
+        |lang: &Option<LangId>| lang.as_ref().is_none_or(|l| l.0 == "x-default");
+    match items.iter().position(|(lang, _)| is_default(lang)) {
+        Some(i) => items[..=i].rotate_right(1),
+        None => {
+            if let Some(&(_, text)) = items.first() {
+                items.insert(0, (None, text));
+            }
+        }
+    }
+    items
 }
 
 /// An XMP array value.
 ///
 /// Created by [`Element::array`].
+#[derive(Debug)]
 pub struct Array<'a, 'n: 'a> {
     writer: &'a mut XmpWriter<'n>,
     kind: RdfCollectionType,
@@ -288,23 +659,48 @@ impl<'a, 'n: 'a> Array<'a, 'n> {
         name: &'a str,
         namespace: Namespace<'n>,
     ) -> Self {
-        writer.namespaces.insert(Namespace::Rdf);
+        writer.namespaces.insert(&Namespace::Rdf);
         write!(writer.buf, "<rdf:{}>", kind.rdf_type()).unwrap();
         Self { writer, kind, name, namespace }
     }
 
     /// Start writing an element in the array.
     pub fn element(&mut self) -> Element<'_, 'n> {
-        self.element_with_attrs(iter::empty())
+        self.element_with_attrs(iter::empty::<(&str, &str)>())
     }
 
     /// Start writing an element with attributes in the array.
-    pub fn element_with_attrs(
+    pub fn element_with_attrs<'b>(
         &mut self,
-        attrs: impl IntoIterator<Item = (&'a str, &'a str)>,
+        attrs: impl IntoIterator<Item = (&'b str, impl XmpAttrValue)>,
     ) -> Element<'_, 'n> {
         Element::with_attrs(self.writer, "li", Namespace::Rdf, attrs)
     }
+
+    /// Write an element in the array for each struct.
+    pub fn extend(
+        &mut self,
+        items: impl IntoIterator<Item = impl XmpStruct>,
+    ) -> &mut Self {
+        for item in items {
+            self.element().obj_value(&item);
+        }
+        self
+    }
+
+    /// Add an XML comment to the array. See [`XmpWriter::comment`].
+    pub fn comment(&mut self, text: &str) -> &mut Self {
+        self.writer.comment(text);
+        self
+    }
+
+    /// Start writing a qualified element in the array.
+    ///
+    /// The value is written as the `rdf:value` of the item. Use the returned
+    /// struct to attach qualifiers to the item. See [`Element::qualified`].
+    pub fn qualified_element(&mut self, val: impl XmpType) -> Struct<'_, 'n> {
+        self.element().qualified(val)
+    }
 }
 
 impl Drop for Array<'_, '_> {
@@ -313,7 +709,7 @@ impl Drop for Array<'_, '_> {
             self.writer.buf,
             "</rdf:{}></{}:{}>",
             self.kind.rdf_type(),
-            self.namespace.prefix(),
+            prefix_of(&self.writer.prefixes, &self.namespace),
             self.name
         )
         .unwrap();
@@ -323,10 +719,19 @@ impl Drop for Array<'_, '_> {
 /// An XMP struct value.
 ///
 /// Created by [`Element::obj`].
+#[derive(Debug)]
 pub struct Struct<'a, 'n: 'a> {
     writer: &'a mut XmpWriter<'n>,
     name: &'a str,
     namespace: Namespace<'a>,
+    form: StructForm,
+    /// The fields started so far, as the ranges of the URLs of their
+    /// namespaces and of their names in `field_names`, and the offsets in the
+    /// buffer at which they start.
+    fields: Vec<(Range<usize>, Range<usize>, usize)>,
+    /// The namespace URLs and names of the fields started so far,
+    /// concatenated.
+    field_names: String,
 }
 
 impl<'a, 'n: 'a> Struct<'a, 'n> {
@@ -334,40 +739,273 @@ impl<'a, 'n: 'a> Struct<'a, 'n> {
         writer: &'a mut XmpWriter<'n>,
         name: &'a str,
         namespace: Namespace<'n>,
+        form: StructForm,
     ) -> Self {
-        Self { writer, name, namespace }
+        Self {
+            writer,
+            name,
+            namespace,
+            form,
+            fields: vec![],
+            field_names: String::new(),
+        }
     }
 
     /// Start writing a property in the struct.
-    pub fn element(
-        &mut self,
-        name: &'a str,
-        namespace: Namespace<'n>,
-    ) -> Element<'_, 'n> {
-        self.element_with_attrs(name, namespace, iter::empty())
+    pub fn element<'b>(
+        &'b mut self,
+        name: &'b str,
+        namespace: impl ToNamespace<'n>,
+    ) -> Element<'b, 'n> {
+        self.element_with_attrs(name, namespace, iter::empty::<(&str, &str)>())
     }
 
     /// Start writing a property with attributes in the struct.
-    pub fn element_with_attrs<'b>(
-        &mut self,
-        name: &'a str,
-        namespace: Namespace<'n>,
-        attrs: impl IntoIterator<Item = (&'b str, &'b str)>,
-    ) -> Element<'_, 'n> {
+    pub fn element_with_attrs<'b, 'c>(
+        &'b mut self,
+        name: &'b str,
+        namespace: impl ToNamespace<'n>,
+        attrs: impl IntoIterator<Item = (&'c str, impl XmpAttrValue)>,
+    ) -> Element<'b, 'n> {
+        let namespace = namespace.to_namespace(self.writer);
+        let start = self.writer.buf.len();
+        self.push_field(&namespace, name, start);
         Element::with_attrs(self.writer, name, namespace, attrs)
     }
+
+    /// Write a field with a primitive value to the struct.
+    ///
+    /// Shorthand for `stc.element(name, namespace).value(value)`.
+    pub fn field(
+        &mut self,
+        name: &str,
+        namespace: impl ToNamespace<'n>,
+        value: impl XmpType,
+    ) -> &mut Self {
+        self.element(name, namespace).value(value);
+        self
+    }
+
+    /// Write fields with primitive values in the same namespace to the
+    /// struct.
+    pub fn fields<'b>(
+        &mut self,
+        namespace: impl ToNamespace<'n>,
+        fields: impl IntoIterator<Item = (&'b str, impl XmpType)>,
+    ) -> &mut Self {
+        let namespace = namespace.to_namespace(self.writer);
+        for (name, value) in fields {
+            self.element(name, namespace.clone()).value(value);
+        }
+        self
+    }
+
+    /// Record the fields of the namespace that were not written to the struct
+    /// as missing. See [`XmpWriter::missing_fields`].
+    pub(crate) fn require(
+        &mut self,
+        value_type: &'static str,
+        namespace: Namespace,
+        names: &[&str],
+    ) {
+        for name in names {
+            if !self.has_field(&namespace, name) {
+                self.writer.missing_fields.push(MissingField {
+                    value_type,
+                    field: format!(
+                        "{}:{}",
+                        prefix_of(&self.writer.prefixes, &namespace),
+                        name
+                    ),
+                });
+            }
+        }
+    }
+
+    /// Whether a field with the namespace and name was written to the
+    /// struct.
+    ///
+    /// A field that was started but left without a value, e.g. with
+    /// [`Element::opt_value`], ends where the next one starts.
+    pub(crate) fn has_field(&self, namespace: &Namespace, name: &str) -> bool {
+        self.fields.iter().enumerate().any(|(i, (url, range, start))| {
+            let end = self.fields.get(i + 1).map_or(self.writer.buf.len(), |f| f.2);
+            self.field_names[url.clone()] == *namespace.url()
+                && &self.field_names[range.clone()] == name
+                && end > *start
+        })
+    }
+
+    /// Record that a field starts at an offset in the buffer.
+    fn push_field(&mut self, namespace: &Namespace<'n>, name: &str, start: usize) {
+        let url = self.field_names.len();
+        self.field_names.push_str(namespace.url());
+        let len = self.field_names.len();
+        self.field_names.push_str(name);
+        self.fields.push((url..len, len..self.field_names.len(), start));
+    }
+
+    /// Splice the properties of another writer into the struct as fields.
+    /// See [`PantryItemWriter::include`](crate::PantryItemWriter::include).
+    pub(crate) fn include(&mut self, other: &XmpWriter<'n>) {
+        self.writer.close_open_tag();
+        self.writer.adopt_namespaces(other);
+
+        let offset = self.writer.buf.len();
+        let mut properties: Vec<_> = other.properties.iter().collect();
+        properties.sort_by_key(|&(_, start)| *start);
+        for ((namespace, name), start) in properties {
+            self.push_field(namespace, name, offset + start - HEADER_CAPACITY);
+        }
+
+        self.writer.buf.push_str(other.buf.body());
+        self.writer
+            .missing_fields
+            .extend(other.missing_fields.iter().cloned());
+        self.writer.duplicates.extend(other.duplicates.iter().cloned());
+        self.writer.errors.extend(other.errors.iter().cloned());
+    }
+
+    /// Record an error. See [`XmpWriter::errors`].
+    pub(crate) fn error(&mut self, error: XmpError) {
+        self.writer.errors.push(error);
+    }
+
+    /// The writer the struct is written to.
+    #[cfg(feature = "pdfa")]
+    pub(crate) fn writer(&mut self) -> &mut XmpWriter<'n> {
+        self.writer
+    }
+
+    /// Write a language alternative of primitive values as a property in the
+    /// struct.
+    pub fn language_alternative<'b>(
+        &mut self,
+        name: &str,
+        namespace: impl ToNamespace<'n>,
+        items: impl IntoIterator<Item = (Option<LangId<'b>>, &'b str)>,
+    ) -> &mut Self {
+        self.element(name, namespace).language_alternative(items);
+        self
+    }
+
+    /// Add an XML comment to the struct. See [`XmpWriter::comment`].
+    pub fn comment(&mut self, text: &str) -> &mut Self {
+        self.writer.comment(text);
+        self
+    }
+
+    /// The prefix of the namespace in the packet. See
+    /// [`XmpWriter::set_prefix`].
+    #[cfg(feature = "pdfa")]
+    pub(crate) fn prefix_of(&self, namespace: &Namespace) -> String {
+        prefix_of(&self.writer.prefixes, namespace).into()
+    }
+
+    /// Write an `rdf:type` property that declares the type of the struct.
+    ///
+    /// Some RDF-aware consumers and extension schemas require structs to be
+    /// typed with the URI of their value type.
+    pub fn rdf_type(&mut self, uri: &str) -> &mut Self {
+        self.writer.close_open_tag();
+        self.writer.buf.push_str("<rdf:type rdf:resource=\"");
+        uri.write_attr(&mut self.writer.buf);
+        self.writer.buf.push_str("\"/>");
+        self
+    }
 }
 
 impl Drop for Struct<'_, '_> {
     fn drop(&mut self) {
-        write!(self.writer.buf, "</{}:{}>", self.namespace.prefix(), self.name).unwrap();
+        match self.form {
+            StructForm::ParseTypeResource => {}
+            StructForm::Description => self.writer.buf.push_str("</rdf:Description>"),
+            StructForm::Compact if self.writer.open_tag => {
+                self.writer.buf.push_str("/>");
+                self.writer.open_tag = false;
+            }
+            StructForm::Compact => self.writer.buf.push_str("</rdf:Description>"),
+        }
+        write!(
+            self.writer.buf,
+            "</{}:{}>",
+            prefix_of(&self.writer.prefixes, &self.namespace),
+            self.name
+        )
+        .unwrap();
+    }
+}
+
+/// Whether the string is a valid XML name without a colon (an `NCName`), as
+/// required for property names and namespace prefixes.
+pub(crate) fn is_ncname(name: &str) -> bool {
+    fn is_start(c: char) -> bool {
+        matches!(c,
+            'A'..='Z' | '_' | 'a'..='z' | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}'
+            | '\u{F8}'..='\u{2FF}' | '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}'
+            | '\u{200C}'..='\u{200D}' | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}'
+            | '\u{3001}'..='\u{D7FF}' | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}'
+            | '\u{10000}'..='\u{EFFFF}')
+    }
+
+    fn is_continue(c: char) -> bool {
+        is_start(c)
+            || matches!(c,
+                '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}'
+                | '\u{203F}'..='\u{2040}')
     }
+
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_start) && chars.all(is_continue)
+}
+
+/// An error for a property name or namespace prefix that is not a valid XML
+/// name.
+///
+/// Returned by [`XmpWriter::try_element`](crate::XmpWriter::try_element).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameError {
+    /// The invalid name.
+    pub name: String,
 }
 
+impl std::fmt::Display for NameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "`{}` is not a valid XML name", self.name)
+    }
+}
+
+impl std::error::Error for NameError {}
+
 /// Primitive XMP types.
 pub trait XmpType {
     /// Write the value to the buffer.
     fn write(&self, buf: &mut String);
+
+    /// Check that the value is valid.
+    ///
+    /// Invalid values are still written, but the error is recorded by the
+    /// writer. See [`XmpWriter::errors`](crate::XmpWriter::errors). Only
+    /// [`XmpError::NonFiniteNumber`] values are not written at all, as they
+    /// have no representation in XMP.
+    fn check(&self) -> Result<(), XmpError> {
+        Ok(())
+    }
+}
+
+/// Complex XMP types that are written as structs.
+///
+/// Implement this for plain data types that are used as the value of several
+/// properties and write them with [`Element::obj_value`].
+pub trait XmpStruct {
+    /// Write the fields of the value to the struct.
+    fn write(&self, stc: &mut Struct<'_, '_>);
+}
+
+impl<T: XmpStruct + ?Sized> XmpStruct for &T {
+    fn write(&self, stc: &mut Struct<'_, '_>) {
+        T::write(self, stc)
+    }
 }
 
 impl XmpType for bool {
@@ -382,44 +1020,351 @@ impl XmpType for bool {
 
 impl XmpType for i32 {
     fn write(&self, buf: &mut String) {
-        write!(buf, "{}", self).unwrap();
+        write_int(buf, *self);
     }
 }
 
 impl XmpType for i64 {
     fn write(&self, buf: &mut String) {
-        write!(buf, "{}", self).unwrap();
+        write_int(buf, *self);
     }
 }
 
+/// Written in fixed notation with as many digits as needed to round-trip.
+/// NaN and infinity are recorded as errors and not written.
 impl XmpType for f32 {
+    fn check(&self) -> Result<(), XmpError> {
+        XmpType::check(&f64::from(*self))
+    }
+
     fn write(&self, buf: &mut String) {
-        write!(buf, "{}", self).unwrap();
+        write_float(buf, *self);
     }
 }
 
+/// Written in fixed notation with as many digits as needed to round-trip.
+/// NaN and infinity are recorded as errors and not written.
 impl XmpType for f64 {
+    fn check(&self) -> Result<(), XmpError> {
+        if self.is_finite() {
+            Ok(())
+        } else {
+            Err(XmpError::NonFiniteNumber)
+        }
+    }
+
     fn write(&self, buf: &mut String) {
-        write!(buf, "{}", self).unwrap();
+        write_float(buf, *self);
+    }
+}
+
+/// Write an integer, formatted with `itoa`.
+#[cfg(feature = "itoa")]
+fn write_int(buf: &mut String, value: impl itoa::Integer) {
+    buf.push_str(itoa::Buffer::new().format(value));
+}
+
+/// Write an integer.
+#[cfg(not(feature = "itoa"))]
+fn write_int(buf: &mut String, value: impl std::fmt::Display) {
+    write!(buf, "{value}").unwrap();
+}
+
+/// Write a float in fixed notation, formatted with `ryu` if possible.
+///
+/// `ryu` switches to exponential notation for very large and small numbers
+/// and always adds a fractional part, so its output is only used if it
+/// matches the standard formatting.
+#[cfg(feature = "ryu")]
+fn write_float(buf: &mut String, value: impl ryu::Float + std::fmt::Display) {
+    let mut ryu = ryu::Buffer::new();
+    let text = ryu.format(value);
+    let text = text.strip_suffix(".0").unwrap_or(text);
+    if text.contains(['e', 'N', 'i']) {
+        write!(buf, "{value}").unwrap();
+    } else {
+        buf.push_str(text);
+    }
+}
+
+/// Write a float in fixed notation.
+#[cfg(not(feature = "ryu"))]
+fn write_float(buf: &mut String, value: impl std::fmt::Display) {
+    write!(buf, "{value}").unwrap();
+}
+
+/// A real number written in fixed notation with limited precision.
+///
+/// Use this instead of a plain `f64` to avoid writing long fractions like
+/// `0.30000000000000004`. NaN and infinity are recorded as errors and not
+/// written.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fixed {
+    /// The number.
+    pub value: f64,
+    /// The maximum number of fractional digits. Trailing zeros are omitted.
+    pub precision: u8,
+}
+
+impl XmpType for Fixed {
+    fn check(&self) -> Result<(), XmpError> {
+        XmpType::check(&self.value)
+    }
+
+    fn write(&self, buf: &mut String) {
+        let start = buf.len();
+        write!(buf, "{:.*}", usize::from(self.precision), self.value).unwrap();
+        if buf[start..].contains('.') {
+            let len = buf.trim_end_matches('0').trim_end_matches('.').len();
+            buf.truncate(len);
+        }
+        if &buf[start..] == "-0" {
+            buf.remove(start);
+        }
     }
 }
 
 impl XmpType for &str {
     fn write(&self, buf: &mut String) {
-        for c in self.chars() {
-            match c {
-                '<' => buf.push_str("&lt;"),
-                '>' => buf.push_str("&gt;"),
-                '&' => buf.push_str("&amp;"),
-                '\'' => buf.push_str("&apos;"),
-                '"' => buf.push_str("&quot;"),
-                _ => buf.push(c),
+        write_escaped(buf, self, text_entity);
+    }
+}
+
+/// Escape the text for use as element content, like `&str` values are
+/// escaped by the writer.
+///
+/// Borrows the text if nothing needs to be escaped. Combine it with
+/// [`RawXmpStr`] to escape a value ahead of time.
+pub fn escape_text(text: &str) -> Cow<'_, str> {
+    escape(text, text_entity)
+}
+
+/// Escape the text for use in a double-quoted attribute value, like
+/// attribute values are escaped by the writer.
+///
+/// Borrows the text if nothing needs to be escaped.
+pub fn escape_attr(text: &str) -> Cow<'_, str> {
+    escape(text, attr_value_entity)
+}
+
+/// Escape the text, borrowing it if `entity` returns no replacement for any
+/// of its bytes.
+fn escape(text: &str, entity: impl Fn(u8) -> Option<&'static str>) -> Cow<'_, str> {
+    if text.bytes().all(|byte| entity(byte).is_none()) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut buf = String::with_capacity(text.len() + 16);
+    write_escaped(&mut buf, text, entity);
+    Cow::Owned(buf)
+}
+
+/// The replacements of the characters that are not allowed verbatim in
+/// element content.
+fn text_entity(byte: u8) -> Option<&'static str> {
+    match byte {
+        b'<' => Some("&lt;"),
+        b'>' => Some("&gt;"),
+        b'&' => Some("&amp;"),
+        b'\'' => Some("&apos;"),
+        b'"' => Some("&quot;"),
+        _ => None,
+    }
+}
+
+/// Write the text to the buffer, replacing the ASCII characters for which
+/// `entity` returns a replacement. The runs between them are copied in bulk.
+fn write_escaped(
+    buf: &mut String,
+    text: &str,
+    entity: impl Fn(u8) -> Option<&'static str>,
+) {
+    // Only ASCII characters are replaced, so the byte offsets are always
+    // character boundaries.
+    let mut last = 0;
+    for (i, byte) in text.bytes().enumerate() {
+        if let Some(entity) = entity(byte) {
+            buf.push_str(&text[last..i]);
+            buf.push_str(entity);
+            last = i + 1;
+        }
+    }
+    buf.push_str(&text[last..]);
+}
+
+impl XmpType for String {
+    fn write(&self, buf: &mut String) {
+        self.as_str().write(buf);
+    }
+}
+
+impl XmpType for Cow<'_, str> {
+    fn write(&self, buf: &mut String) {
+        self.as_ref().write(buf);
+    }
+}
+
+impl XmpType for char {
+    fn write(&self, buf: &mut String) {
+        (&*self.encode_utf8(&mut [0; 4])).write(buf);
+    }
+}
+
+/// Text from anything that can be referenced as a string.
+pub(crate) struct Text<T>(pub T);
+
+impl<T: AsRef<str>> XmpType for Text<T> {
+    fn write(&self, buf: &mut String) {
+        self.0.as_ref().write(buf);
+    }
+}
+
+/// Primitive XMP types that can be used as attribute values.
+pub trait XmpAttrValue {
+    /// Check whether the value can be written. See [`XmpType::check`].
+    fn check(&self) -> Result<(), XmpError> {
+        Ok(())
+    }
+
+    /// Write the value to the buffer, escaped for use in a double-quoted
+    /// attribute.
+    fn write_attr(&self, buf: &mut String);
+}
+
+impl XmpAttrValue for &str {
+    fn write_attr(&self, buf: &mut String) {
+        write_escaped(buf, self, attr_value_entity);
+    }
+}
+
+impl XmpAttrValue for LangId<'_> {
+    fn write_attr(&self, buf: &mut String) {
+        self.0.write_attr(buf);
+    }
+}
+
+/// Escape the characters of the text content starting at `start` that are
+/// not allowed verbatim in an attribute value. Quotes would end the value and
+/// whitespace other than spaces would be normalized by XML parsers.
+fn escape_attr_value(buf: &mut String, start: usize) {
+    if !buf[start..].contains(['"', '\t', '\n', '\r']) {
+        return;
+    }
+
+    let value = buf.split_off(start);
+    write_escaped(buf, &value, attr_entity);
+}
+
+/// The replacements of the characters that are not allowed verbatim in an
+/// attribute value.
+fn attr_value_entity(byte: u8) -> Option<&'static str> {
+    match byte {
+        b'<' => Some("&lt;"),
+        b'&' => Some("&amp;"),
+        byte => attr_entity(byte),
+    }
+}
+
+/// The replacements of the characters that are not allowed verbatim in an
+/// attribute value besides `<` and `&`.
+fn attr_entity(byte: u8) -> Option<&'static str> {
+    match byte {
+        b'"' => Some("&quot;"),
+        // Whitespace other than spaces would be normalized by XML parsers.
+        b'\t' => Some("&#x9;"),
+        b'\n' => Some("&#xA;"),
+        b'\r' => Some("&#xD;"),
+        _ => None,
+    }
+}
+
+/// Implements `XmpAttrValue` for types whose `XmpType` representation never
+/// needs escaping.
+macro_rules! attr_value {
+    ($($ty:ty),* $(,)?) => {
+        $(impl XmpAttrValue for $ty {
+            fn check(&self) -> Result<(), XmpError> {
+                XmpType::check(self)
+            }
+
+            fn write_attr(&self, buf: &mut String) {
+                self.write(buf);
+            }
+        })*
+    };
+}
+
+attr_value!(bool, i32, i64, f32, f64, Fixed, DateTime);
+
+/// A string value that is written to the packet verbatim.
+///
+/// Unlike `&str`, the value is not escaped. The caller must guarantee that it
+/// is valid XML content, i.e. that it does not contain `<`, `&`, or quotes
+/// that are not part of markup or an entity. Use it for values that are known
+/// to be safe, like identifiers, or to inject already serialized fragments.
+/// Text can be escaped ahead of time with [`escape_text`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RawXmpStr<'a>(pub &'a str);
+
+impl XmpType for RawXmpStr<'_> {
+    fn write(&self, buf: &mut String) {
+        buf.push_str(self.0);
+    }
+}
+
+/// Binary data that is base64-encoded directly into the packet.
+///
+/// The data is encoded in chunks, so no encoded copy of it is kept alongside
+/// the packet. Use it for large payloads like thumbnails or depth maps.
+/// Requires the `base64` feature.
+#[cfg(feature = "base64")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Base64<'a> {
+    data: &'a [u8],
+    line_len: Option<usize>,
+}
+
+#[cfg(feature = "base64")]
+impl<'a> Base64<'a> {
+    /// Encode the data without line breaks.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, line_len: None }
+    }
+
+    /// Insert a line break after every `line_len` characters.
+    ///
+    /// The length is rounded down to a multiple of four, but is at least four.
+    pub fn wrap(mut self, line_len: usize) -> Self {
+        self.line_len = Some((line_len / 4).max(1) * 4);
+        self
+    }
+}
+
+#[cfg(feature = "base64")]
+impl XmpType for Base64<'_> {
+    fn write(&self, buf: &mut String) {
+        use base64::Engine;
+
+        let engine = base64::engine::general_purpose::STANDARD;
+        let Some(line_len) = self.line_len else {
+            engine.encode_string(self.data, buf);
+            return;
+        };
+
+        buf.reserve(base64::encoded_len(self.data.len(), true).unwrap_or(0));
+        for (i, line) in self.data.chunks(line_len / 4 * 3).enumerate() {
+            if i > 0 {
+                buf.push('\n');
             }
+            engine.encode_string(line, buf);
         }
     }
 }
 
 /// Types of RDF collections.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RdfCollectionType {
     /// An ordered array / sequence.
     Seq,
@@ -440,9 +1385,29 @@ impl RdfCollectionType {
     }
 }
 
+/// How struct values are serialized.
+///
+/// Set for a writer with [`XmpWriter::set_struct_form`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StructForm {
+    /// A property element with `rdf:parseType="Resource"`. This is the most
+    /// compact form and the one used by the Adobe XMP Toolkit.
+    #[default]
+    ParseTypeResource,
+    /// A property element containing a nested `rdf:Description` element. Some
+    /// older RDF parsers and validators only understand this form.
+    Description,
+    /// A nested `rdf:Description` element with simple, unqualified fields
+    /// written as attributes. Fields with other values are written as
+    /// elements. This shrinks the output for structs like `stDim` and `stRef`.
+    Compact,
+}
+
 /// A language specifier as defined in RFC 3066. Can also be `x-default` if the
 /// language is not known.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LangId<'a>(pub &'a str);
 
 impl XmpType for LangId<'_> {
@@ -457,9 +1422,134 @@ impl Default for LangId<'_> {
     }
 }
 
+/// Implement conversions from strings for a text newtype.
+macro_rules! text_type {
+    ($ty:ident) => {
+        impl<'a> From<&'a str> for $ty<'a> {
+            fn from(text: &'a str) -> Self {
+                Self(Cow::Borrowed(text))
+            }
+        }
+
+        impl<'a> From<&'a String> for $ty<'a> {
+            fn from(text: &'a String) -> Self {
+                Self(Cow::Borrowed(text))
+            }
+        }
+
+        impl From<String> for $ty<'_> {
+            fn from(text: String) -> Self {
+                Self(Cow::Owned(text))
+            }
+        }
+
+        impl<'a> From<Cow<'a, str>> for $ty<'a> {
+            fn from(text: Cow<'a, str>) -> Self {
+                Self(text)
+            }
+        }
+    };
+}
+
+/// The name of an XMP processor, e.g. `Adobe Acrobat 9.0 (Windows)`.
+///
+/// Recommended to contain the organization, the software name, the version,
+/// and the platform.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AgentName<'a>(pub Cow<'a, str>);
+
+text_type!(AgentName);
+
+impl XmpType for AgentName<'_> {
+    fn write(&self, buf: &mut String) {
+        self.0.write(buf);
+    }
+}
+
+/// The name of a person or organization.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProperName<'a>(pub Cow<'a, str>);
+
+text_type!(ProperName);
+
+impl XmpType for ProperName<'_> {
+    fn write(&self, buf: &mut String) {
+        self.0.write(buf);
+    }
+}
+
+/// A URI, e.g. `https://example.com/rights.html`.
+///
+/// Can be created from a [`url::Url`] with the `url` feature, which ensures
+/// that the URI is valid.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Uri<'a>(pub Cow<'a, str>);
+
+text_type!(Uri);
+
+#[cfg(feature = "url")]
+impl<'a> From<&'a url::Url> for Uri<'a> {
+    fn from(url: &'a url::Url) -> Self {
+        Self(Cow::Borrowed(url.as_str()))
+    }
+}
+
+impl XmpType for Uri<'_> {
+    fn write(&self, buf: &mut String) {
+        self.0.write(buf);
+    }
+}
+
+#[cfg(feature = "url")]
+impl XmpType for url::Url {
+    fn write(&self, buf: &mut String) {
+        self.as_str().write(buf);
+    }
+}
+
+/// A MIME type as defined in RFC 2046, e.g. `application/pdf`.
+///
+/// Types that are not of the form `type/subtype`, optionally followed by
+/// parameters, are recorded as errors when written. See
+/// [`XmpWriter::errors`](crate::XmpWriter::errors).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MimeType<'a>(pub Cow<'a, str>);
+
+text_type!(MimeType);
+
+impl XmpType for MimeType<'_> {
+    fn check(&self) -> Result<(), XmpError> {
+        let is_name = |s: &str| {
+            !s.is_empty()
+                && s.bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b"!#$&-^_.+".contains(&b))
+        };
+
+        let essence = self.0.split(';').next().unwrap_or_default().trim();
+        match essence.split_once('/') {
+            Some((kind, subtype)) if is_name(kind) && is_name(subtype) => Ok(()),
+            _ => Err(XmpError::InvalidMimeType(self.0.to_string())),
+        }
+    }
+
+    fn write(&self, buf: &mut String) {
+        self.0.write(buf);
+    }
+}
+
 /// A date and time.
+///
+/// This struct is non-exhaustive so that fields can be added without breaking
+/// changes. Construct it with one of the constructors like [`DateTime::new`]
+/// and then modify its fields.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
+#[non_exhaustive]
 pub struct DateTime {
     pub year: u16,
     pub month: Option<u8>,
@@ -467,23 +1557,94 @@ pub struct DateTime {
     pub hour: Option<u8>,
     pub minute: Option<u8>,
     pub second: Option<u8>,
+    /// The fractional part of the second in nanoseconds. Trailing zeros are
+    /// omitted when writing it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub nanosecond: Option<u32>,
     /// The timezone of this date and time. No assumptions about the timezone or
     /// locale should be made if this is `None`.
     pub timezone: Option<Timezone>,
 }
 
-/// A timezone.
-#[derive(Debug, Copy, Clone, PartialEq)]
-pub enum Timezone {
-    /// UTC time. Use `Local` for British time.
-    Utc,
-    /// A local timezone offset.
-    Local {
-        /// Timezone offset in hours.
-        hour: i8,
-        /// Timezone offset in minutes.
-        minute: i8,
-    },
+/// A timezone.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Timezone {
+    /// UTC time. Use `Local` for British time.
+    Utc,
+    /// A local timezone offset.
+    Local {
+        /// Timezone offset in hours.
+        hour: i8,
+        /// Timezone offset in minutes. Only negative for offsets between
+        /// -00:59 and -00:01, where `hour` is zero.
+        minute: i8,
+    },
+}
+
+impl Timezone {
+    /// Create a timezone from an offset east of UTC in minutes, e.g. `-210`
+    /// for `-03:30`.
+    ///
+    /// An offset of zero results in [`Timezone::Utc`]. Returns an error if
+    /// the offset is beyond ±14:00.
+    pub fn from_offset_minutes(minutes: i16) -> Result<Self, DateTimeError> {
+        if !(-14 * 60..=14 * 60).contains(&minutes) {
+            return Err(DateTimeError::TimezoneOutOfRange);
+        }
+
+        Ok(Self::from_minutes(minutes))
+    }
+
+    /// Create a timezone from an offset east of UTC in minutes without
+    /// checking its range.
+    pub(crate) fn from_minutes(minutes: i16) -> Self {
+        if minutes == 0 {
+            return Self::Utc;
+        }
+
+        Self::Local {
+            hour: (minutes / 60) as i8,
+            minute: if minutes > -60 { minutes % 60 } else { minutes.abs() % 60 } as i8,
+        }
+    }
+
+    /// The offset east of UTC in minutes, e.g. `-210` for `-03:30`.
+    pub fn offset_minutes(&self) -> i16 {
+        match *self {
+            Self::Utc => 0,
+            Self::Local { hour, minute } => {
+                let minutes = i16::from(hour).abs() * 60 + i16::from(minute).abs();
+                if hour < 0 || minute < 0 {
+                    -minutes
+                } else {
+                    minutes
+                }
+            }
+        }
+    }
+
+    /// Write the offset as a sign, hours, and minutes with a separator
+    /// between and after them as in `+01:00` or `+01'00'`.
+    pub(crate) fn write_offset(&self, buf: &mut String, sep: &str, end: &str) {
+        let minutes = self.offset_minutes();
+        let sign = if minutes < 0 { '-' } else { '+' };
+        let minutes = minutes.abs();
+        write!(buf, "{sign}{:02}{sep}{:02}{end}", minutes / 60, minutes % 60).unwrap();
+    }
+
+    /// The current offset of the system's local timezone.
+    ///
+    /// The zone is read from the `TZ` environment variable or
+    /// `/etc/localtime`. Returns `None` if it can't be determined, e.g. on
+    /// platforms without zone files.
+    pub fn local() -> Option<Self> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?;
+        let seconds = crate::tz::local_offset(i64::try_from(now.as_secs()).ok()?)?;
+        Self::from_offset_minutes(i16::try_from(seconds / 60).ok()?).ok()
+    }
 }
 
 impl DateTime {
@@ -505,6 +1666,7 @@ impl DateTime {
             hour: Some(hour),
             minute: Some(minute),
             second: Some(second),
+            nanosecond: None,
             timezone: Some(timezone),
         }
     }
@@ -518,6 +1680,7 @@ impl DateTime {
             hour: None,
             minute: None,
             second: None,
+            nanosecond: None,
             timezone: None,
         }
     }
@@ -531,6 +1694,7 @@ impl DateTime {
             hour: None,
             minute: None,
             second: None,
+            nanosecond: None,
             timezone: None,
         }
     }
@@ -551,24 +1715,322 @@ impl DateTime {
             hour: Some(hour),
             minute: Some(minute),
             second: Some(second),
+            nanosecond: None,
             timezone: None,
         }
     }
 }
 
+impl DateTime {
+    /// Create a new date and time with all fields, checking that they are in
+    /// range.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+        timezone: Timezone,
+    ) -> Result<Self, DateTimeError> {
+        let date = Self::new(year, month, day, hour, minute, second, timezone);
+        date.validate().map(|_| date)
+    }
+
+    /// Create a new date without a time, checking that it exists.
+    pub fn try_date(year: u16, month: u8, day: u8) -> Result<Self, DateTimeError> {
+        let date = Self::date(year, month, day);
+        date.validate().map(|_| date)
+    }
+
+    /// Create a new date and time without a timezone, checking that the
+    /// fields are in range.
+    pub fn try_local_time(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<Self, DateTimeError> {
+        let date = Self::local_time(year, month, day, hour, minute, second);
+        date.validate().map(|_| date)
+    }
+
+    /// Check that all fields are in range and that the precision is
+    /// consistent.
+    ///
+    /// A field may only be set if all less precise fields are set, except
+    /// for the second and its fraction, which may be omitted. A timezone requires a time. Dates
+    /// that fail this check are serialized in an unspecified, possibly
+    /// truncated form.
+    pub fn validate(&self) -> Result<(), DateTimeError> {
+        let precise = [self.month, self.day, self.hour, self.minute];
+        let gap = precise.windows(2).any(|w| w[0].is_none() && w[1].is_some());
+        if gap
+            || (self.hour.is_some() != self.minute.is_some())
+            || (self.second.is_some() && self.minute.is_none())
+            || (self.nanosecond.is_some() && self.second.is_none())
+            || (self.timezone.is_some() && self.minute.is_none())
+        {
+            return Err(DateTimeError::InconsistentPrecision);
+        }
+
+        if self.year > 9999 {
+            return Err(DateTimeError::YearOutOfRange);
+        }
+
+        if let Some(month) = self.month {
+            if !(1..=12).contains(&month) {
+                return Err(DateTimeError::MonthOutOfRange);
+            }
+
+            if let Some(day) = self.day {
+                let leap =
+                    self.year % 4 == 0 && (self.year % 100 != 0 || self.year % 400 == 0);
+                let days = match month {
+                    2 if leap => 29,
+                    2 => 28,
+                    4 | 6 | 9 | 11 => 30,
+                    _ => 31,
+                };
+                if !(1..=days).contains(&day) {
+                    return Err(DateTimeError::DayOutOfRange);
+                }
+            }
+        }
+
+        if self.hour.is_some_and(|hour| hour > 23) {
+            return Err(DateTimeError::HourOutOfRange);
+        }
+
+        if self.minute.is_some_and(|minute| minute > 59) {
+            return Err(DateTimeError::MinuteOutOfRange);
+        }
+
+        if self.second.is_some_and(|second| second > 59) {
+            return Err(DateTimeError::SecondOutOfRange);
+        }
+
+        if self.nanosecond.is_some_and(|nanosecond| nanosecond > 999_999_999) {
+            return Err(DateTimeError::NanosecondOutOfRange);
+        }
+
+        if let Some(Timezone::Local { hour, minute }) = self.timezone {
+            let minutes = if hour == 0 { -59..=59 } else { 0..=59 };
+            if !(-23..=23).contains(&hour) || !minutes.contains(&minute) {
+                return Err(DateTimeError::TimezoneOutOfRange);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The current date and time in UTC, according to the system clock.
+    ///
+    /// Panics if the system clock is set to a year after 9999.
+    pub fn now_utc() -> Self {
+        Self::try_from(SystemTime::now()).expect("system time is out of range")
+    }
+}
+
+/// Converts the instant to a date and time in UTC. Fails for instants outside
+/// of the years 0 to 9999.
+impl TryFrom<SystemTime> for DateTime {
+    type Error = DateTimeError;
+
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        let (secs, nanos) = match time.duration_since(UNIX_EPOCH) {
+            Ok(duration) => (i64::try_from(duration.as_secs()), duration.subsec_nanos()),
+            Err(err) => {
+                let duration = err.duration();
+                let whole = i64::try_from(duration.as_secs()).map(|secs| -secs);
+                match duration.subsec_nanos() {
+                    0 => (whole, 0),
+                    nanos => (whole.map(|secs| secs - 1), 1_000_000_000 - nanos),
+                }
+            }
+        };
+        let secs = secs.map_err(|_| DateTimeError::YearOutOfRange)?;
+
+        // Convert days since the epoch to a civil date, see
+        // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = secs.div_euclid(86400);
+        let time = secs.rem_euclid(86400);
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = doy - (153 * mp + 2) / 5 + 1;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 };
+        let year = yoe + era * 400 + i64::from(month <= 2);
+
+        let year = u16::try_from(year)
+            .ok()
+            .filter(|&year| year <= 9999)
+            .ok_or(DateTimeError::YearOutOfRange)?;
+
+        Ok(Self {
+            nanosecond: Some(nanos),
+            ..Self::new(
+                year,
+                month as u8,
+                day as u8,
+                (time / 3600) as u8,
+                (time % 3600 / 60) as u8,
+                (time % 60) as u8,
+                Timezone::Utc,
+            )
+        })
+    }
+}
+
+/// Parses the subset of ISO 8601 used by XMP, e.g. `2021`, `2021-11-06`, or
+/// `2021-11-06T12:30:15.5+01:00`, and validates the result.
+impl FromStr for DateTime {
+    type Err = DateTimeError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        fn digits<T: FromStr>(s: &mut &str, n: usize) -> Result<T, DateTimeError> {
+            let Some((head, tail)) = s.split_at_checked(n) else {
+                return Err(DateTimeError::Malformed);
+            };
+            if !head.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(DateTimeError::Malformed);
+            }
+            *s = tail;
+            head.parse().map_err(|_| DateTimeError::Malformed)
+        }
+
+        let mut s = text;
+        let mut date = DateTime::year(digits(&mut s, 4)?);
+        if let Some(rest) = s.strip_prefix('-') {
+            s = rest;
+            date.month = Some(digits(&mut s, 2)?);
+            if let Some(rest) = s.strip_prefix('-') {
+                s = rest;
+                date.day = Some(digits(&mut s, 2)?);
+            }
+        }
+
+        if let Some(rest) = s.strip_prefix('T') {
+            s = rest;
+            date.hour = Some(digits(&mut s, 2)?);
+            s = s.strip_prefix(':').ok_or(DateTimeError::Malformed)?;
+            date.minute = Some(digits(&mut s, 2)?);
+            if let Some(rest) = s.strip_prefix(':') {
+                s = rest;
+                date.second = Some(digits(&mut s, 2)?);
+                if let Some(rest) = s.strip_prefix('.') {
+                    let len = rest.bytes().take_while(u8::is_ascii_digit).count();
+                    if len == 0 {
+                        return Err(DateTimeError::Malformed);
+                    }
+                    // Digits beyond nanosecond precision are truncated.
+                    let fraction = format!("{:0<9.9}", &rest[..len]);
+                    date.nanosecond = Some(digits(&mut fraction.as_str(), 9)?);
+                    s = &rest[len..];
+                }
+            }
+
+            if let Some(rest) = s.strip_prefix('Z') {
+                s = rest;
+                date.timezone = Some(Timezone::Utc);
+            } else if let Some(sign) = s.chars().next().filter(|c| matches!(c, '+' | '-'))
+            {
+                s = &s[1..];
+                let hour: i8 = digits(&mut s, 2)?;
+                s = s.strip_prefix(':').ok_or(DateTimeError::Malformed)?;
+                let minute: i8 = digits(&mut s, 2)?;
+                let (hour, minute) = match sign {
+                    '-' if hour == 0 => (0, -minute),
+                    '-' => (-hour, minute),
+                    _ => (hour, minute),
+                };
+                date.timezone = Some(Timezone::Local { hour, minute });
+            }
+        }
+
+        if !s.is_empty() {
+            return Err(DateTimeError::Malformed);
+        }
+
+        date.validate()?;
+        Ok(date)
+    }
+}
+
+/// An error that occurred while validating a [`DateTime`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DateTimeError {
+    /// The year is larger than 9999.
+    YearOutOfRange,
+    /// The month is not between 1 and 12.
+    MonthOutOfRange,
+    /// The day does not exist in the month.
+    DayOutOfRange,
+    /// The hour is larger than 23.
+    HourOutOfRange,
+    /// The minute is larger than 59.
+    MinuteOutOfRange,
+    /// The second is larger than 59.
+    SecondOutOfRange,
+    /// The nanosecond is larger than 999,999,999.
+    NanosecondOutOfRange,
+    /// The timezone offset is not between -23:59 and +23:59 or has a
+    /// negative minute.
+    TimezoneOutOfRange,
+    /// A field is set while a less precise one is missing, e.g. an hour
+    /// without a minute.
+    InconsistentPrecision,
+    /// The text is not a date in the format used by XMP.
+    Malformed,
+}
+
+impl std::fmt::Display for DateTimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::YearOutOfRange => "year is out of range",
+            Self::MonthOutOfRange => "month is out of range",
+            Self::DayOutOfRange => "day is out of range",
+            Self::HourOutOfRange => "hour is out of range",
+            Self::MinuteOutOfRange => "minute is out of range",
+            Self::SecondOutOfRange => "second is out of range",
+            Self::NanosecondOutOfRange => "nanosecond is out of range",
+            Self::TimezoneOutOfRange => "timezone offset is out of range",
+            Self::InconsistentPrecision => "date has inconsistent precision",
+            Self::Malformed => "date is malformed",
+        })
+    }
+}
+
+impl std::error::Error for DateTimeError {}
+
 impl XmpType for DateTime {
+    fn check(&self) -> Result<(), XmpError> {
+        self.validate().map_err(XmpError::InvalidDateTime)
+    }
+
     fn write(&self, buf: &mut String) {
         (|| {
             write!(buf, "{:04}", self.year).unwrap();
             write!(buf, "-{:02}", self.month?).unwrap();
             write!(buf, "-{:02}", self.day?).unwrap();
             write!(buf, "T{:02}:{:02}", self.hour?, self.minute?).unwrap();
-            write!(buf, ":{:02}", self.second?).unwrap();
+            if let Some(second) = self.second {
+                write!(buf, ":{:02}", second).unwrap();
+                if let Some(nanosecond) = self.nanosecond.filter(|&n| n > 0) {
+                    let fraction = format!("{nanosecond:09}");
+                    write!(buf, ".{}", fraction.trim_end_matches('0')).unwrap();
+                }
+            }
             match self.timezone? {
                 Timezone::Utc => buf.push('Z'),
-                Timezone::Local { hour, minute } => {
-                    write!(buf, "{:+03}:{:02}", hour, minute).unwrap();
-                }
+                timezone => timezone.write_offset(buf, ":", ""),
             }
             Some(())
         })();
@@ -577,6 +2039,7 @@ impl XmpType for DateTime {
 
 /// The intended use of the resource.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RenditionClass<'a> {
     /// The master resource.
     Default,
@@ -591,10 +2054,12 @@ pub enum RenditionClass<'a> {
     /// A thumbnail.
     Thumbnail {
         /// The format of the thumbnail.
+        #[cfg_attr(feature = "serde", serde(borrow))]
         format: Option<&'a str>,
         /// The size of the thumbnail.
         size: Option<(u32, u32)>,
         /// The color space of the thumbnail.
+        #[cfg_attr(feature = "serde", serde(borrow))]
         color_space: Option<&'a str>,
     },
     /// A custom rendition class.
@@ -632,6 +2097,8 @@ impl XmpType for RenditionClass<'_> {
 }
 
 /// A user-assigned rating.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Rating {
     /// The resource has been rejected.
     Rejected,
@@ -651,19 +2118,18 @@ pub enum Rating {
 
 impl Rating {
     /// Creates a new `Rating` from the number of stars.
-    pub fn from_stars(stars: Option<u32>) -> Self {
-        match stars {
-            Some(0) => Self::Unknown,
+    ///
+    /// Returns an error if there are more than five stars.
+    pub fn from_stars(stars: Option<u32>) -> Result<Self, RatingError> {
+        Ok(match stars {
+            Some(0) | None => Self::Unknown,
             Some(1) => Self::OneStar,
             Some(2) => Self::TwoStars,
             Some(3) => Self::ThreeStars,
             Some(4) => Self::FourStars,
             Some(5) => Self::FiveStars,
-            Some(stars) => {
-                panic!("Invalid number of stars: {} (must be between 0 and 5)", stars)
-            }
-            None => Self::Unknown,
-        }
+            Some(stars) => return Err(RatingError { value: stars.into() }),
+        })
     }
 
     /// Convert the rating to an XMP primitive.
@@ -680,7 +2146,33 @@ impl Rating {
     }
 }
 
+impl XmpType for Rating {
+    fn write(&self, buf: &mut String) {
+        self.to_xmp().write(buf);
+    }
+}
+
+/// An error for a rating outside of the range from -1 to 5.
+///
+/// Returned by [`Rating::from_stars`] and
+/// [`XmpWriter::try_rating`](crate::XmpWriter::try_rating).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RatingError {
+    /// The invalid rating.
+    pub value: f64,
+}
+
+impl std::fmt::Display for RatingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rating {} is not between -1 and 5", self.value)
+    }
+}
+
+impl std::error::Error for RatingError {}
+
 /// Whether to ignore the markers of an [ingredient.](crate::ResourceRefWriter)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum MaskMarkers {
     /// Ignore all markers and those of the children.
     All,
@@ -698,6 +2190,8 @@ impl XmpType for MaskMarkers {
 }
 
 /// The type of a resource event.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum ResourceEventAction<'a> {
     Converted,
@@ -740,6 +2234,8 @@ impl XmpType for ResourceEventAction<'_> {
 }
 
 /// The color space in which a colorant is defined.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum ColorantMode {
     CMYK,
@@ -747,6 +2243,18 @@ pub enum ColorantMode {
     Lab,
 }
 
+impl ColorantMode {
+    /// The names of the `xmpG` fields that hold the components of a colorant
+    /// in this mode.
+    pub(crate) fn components(self) -> &'static [&'static str] {
+        match self {
+            Self::CMYK => &["cyan", "magenta", "yellow", "black"],
+            Self::RGB => &["red", "green", "blue"],
+            Self::Lab => &["L", "A", "B"],
+        }
+    }
+}
+
 impl XmpType for ColorantMode {
     fn write(&self, buf: &mut String) {
         buf.push_str(match self {
@@ -758,6 +2266,8 @@ impl XmpType for ColorantMode {
 }
 
 /// The type of a colorant.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ColorantType {
     /// Colors inherent to the printing process.
     Process,
@@ -774,7 +2284,106 @@ impl XmpType for ColorantType {
     }
 }
 
+/// A colorant of a document as plain data.
+///
+/// Add it to the `xmpTPg:Colorants` array with [`ColorantsWriter::add`].
+///
+/// [`ColorantsWriter::add`]: crate::ColorantsWriter::add
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Colorant {
+    /// The name of the swatch.
+    pub swatch_name: String,
+    /// Whether the colorant is a process or a spot color.
+    pub kind: ColorantType,
+    /// The components of the color.
+    pub components: ColorantComponents,
+}
+
+impl Colorant {
+    /// A process color in the CMYK color space. Components range from 0 to
+    /// 100.
+    pub fn process_cmyk(
+        name: impl Into<String>,
+        cyan: f64,
+        magenta: f64,
+        yellow: f64,
+        black: f64,
+    ) -> Self {
+        let components = ColorantComponents::Cmyk { cyan, magenta, yellow, black };
+        Self::new(name, ColorantType::Process, components)
+    }
+
+    /// A spot color in the CMYK color space. Components range from 0 to 100.
+    pub fn spot_cmyk(
+        name: impl Into<String>,
+        cyan: f64,
+        magenta: f64,
+        yellow: f64,
+        black: f64,
+    ) -> Self {
+        let components = ColorantComponents::Cmyk { cyan, magenta, yellow, black };
+        Self::new(name, ColorantType::Spot, components)
+    }
+
+    /// A process color in the RGB color space. Components range from 0 to
+    /// 255.
+    pub fn process_rgb(name: impl Into<String>, red: i32, green: i32, blue: i32) -> Self {
+        let components = ColorantComponents::Rgb { red, green, blue };
+        Self::new(name, ColorantType::Process, components)
+    }
+
+    /// A spot color in the RGB color space. Components range from 0 to 255.
+    pub fn spot_rgb(name: impl Into<String>, red: i32, green: i32, blue: i32) -> Self {
+        let components = ColorantComponents::Rgb { red, green, blue };
+        Self::new(name, ColorantType::Spot, components)
+    }
+
+    /// A process color in the CIE L*a*b* color space. `l` ranges from 0 to
+    /// 100, `a` and `b` from -128 to 127.
+    pub fn process_lab(name: impl Into<String>, l: f64, a: i32, b: i32) -> Self {
+        Self::new(name, ColorantType::Process, ColorantComponents::Lab { l, a, b })
+    }
+
+    /// A spot color in the CIE L*a*b* color space. `l` ranges from 0 to 100,
+    /// `a` and `b` from -128 to 127.
+    pub fn spot_lab(name: impl Into<String>, l: f64, a: i32, b: i32) -> Self {
+        Self::new(name, ColorantType::Spot, ColorantComponents::Lab { l, a, b })
+    }
+
+    fn new(
+        name: impl Into<String>,
+        kind: ColorantType,
+        components: ColorantComponents,
+    ) -> Self {
+        Self { swatch_name: name.into(), kind, components }
+    }
+}
+
+/// The components of a [`Colorant`] in its color space.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+pub enum ColorantComponents {
+    Cmyk { cyan: f64, magenta: f64, yellow: f64, black: f64 },
+    Rgb { red: i32, green: i32, blue: i32 },
+    Lab { l: f64, a: i32, b: i32 },
+}
+
+impl ColorantComponents {
+    /// The color space of the components.
+    pub fn mode(&self) -> ColorantMode {
+        match self {
+            Self::Cmyk { .. } => ColorantMode::CMYK,
+            Self::Rgb { .. } => ColorantMode::RGB,
+            Self::Lab { .. } => ColorantMode::Lab,
+        }
+    }
+}
+
 /// The unit of a physical dimension.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum DimensionUnit<'a> {
     Inch,
@@ -798,7 +2407,77 @@ impl<'a> XmpType for DimensionUnit<'a> {
     }
 }
 
+impl DimensionUnit<'_> {
+    /// How many of the unit make up an inch, or `None` for custom units.
+    ///
+    /// The `dpi` is the resolution used for pixels.
+    pub fn per_inch(&self, dpi: f64) -> Option<f64> {
+        match self {
+            Self::Inch => Some(1.0),
+            Self::Mm => Some(25.4),
+            Self::Pixel => Some(dpi),
+            Self::Pica => Some(6.0),
+            Self::Point => Some(72.0),
+            Self::Custom(_) => None,
+        }
+    }
+}
+
+/// The dimensions of a resource, e.g. the maximum page size of a document.
+///
+/// Write it with [`DimensionsWriter::dimensions`].
+///
+/// [`DimensionsWriter::dimensions`]: crate::DimensionsWriter::dimensions
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dimensions<'a> {
+    /// The width.
+    pub width: f64,
+    /// The height.
+    pub height: f64,
+    /// The unit of the width and height.
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub unit: DimensionUnit<'a>,
+}
+
+impl<'a> Dimensions<'a> {
+    /// Create new dimensions.
+    pub fn new(width: f64, height: f64, unit: DimensionUnit<'a>) -> Self {
+        Self { width, height, unit }
+    }
+
+    /// Create new dimensions in points.
+    pub fn points(width: f64, height: f64) -> Self {
+        Self::new(width, height, DimensionUnit::Point)
+    }
+
+    /// Create new dimensions in millimeters.
+    pub fn mm(width: f64, height: f64) -> Self {
+        Self::new(width, height, DimensionUnit::Mm)
+    }
+
+    /// Create new dimensions in inches.
+    pub fn inches(width: f64, height: f64) -> Self {
+        Self::new(width, height, DimensionUnit::Inch)
+    }
+
+    /// Convert the dimensions to another unit.
+    ///
+    /// The `dpi` is the resolution used if either unit is pixels. Returns
+    /// `None` if either unit is a custom unit.
+    pub fn convert<'b>(
+        &self,
+        unit: DimensionUnit<'b>,
+        dpi: f64,
+    ) -> Option<Dimensions<'b>> {
+        let factor = unit.per_inch(dpi)? / self.unit.per_inch(dpi)?;
+        Some(Dimensions::new(self.width * factor, self.height * factor, unit))
+    }
+}
+
 /// The font file type.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(missing_docs)]
 pub enum FontType<'a> {
     TrueType,
@@ -808,6 +2487,45 @@ pub enum FontType<'a> {
     Custom(&'a str),
 }
 
+impl FontType<'_> {
+    /// Detect the type of a font from the signature of its data.
+    ///
+    /// Recognizes sfnt fonts and collections with TrueType or CFF outlines,
+    /// Type 1 fonts in the PFB and PFA formats, and bitmap fonts in the BDF
+    /// and PCF formats.
+    pub fn from_bytes(data: &[u8]) -> Option<Self> {
+        let tag = |offset: usize| data.get(offset..offset + 4);
+        let mut sfnt = tag(0)?;
+        if sfnt == b"ttcf" {
+            // The tag of the first font in the collection.
+            let offset = u32::from_be_bytes(tag(12)?.try_into().ok()?);
+            sfnt = tag(usize::try_from(offset).ok()?)?;
+        }
+
+        Some(match sfnt {
+            [0, 1, 0, 0] | b"true" => Self::TrueType,
+            b"OTTO" => Self::OpenType,
+            [0x80, 0x01, ..] | b"%!PS" | b"%!Fo" => Self::Type1,
+            b"STAR" if data.starts_with(b"STARTFONT") => Self::Bitmap,
+            [1, b'f', b'c', b'p'] => Self::Bitmap,
+            _ => return None,
+        })
+    }
+
+    /// Detect the type of a font from the extension of its file name, with or
+    /// without the leading dot. Case-insensitive.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.strip_prefix('.').unwrap_or(extension);
+        Some(match extension.to_ascii_lowercase().as_str() {
+            "ttf" | "ttc" => Self::TrueType,
+            "otf" | "otc" => Self::OpenType,
+            "pfb" | "pfa" | "t1" => Self::Type1,
+            "bdf" | "pcf" | "fon" | "fnt" => Self::Bitmap,
+            _ => return None,
+        })
+    }
+}
+
 impl<'a> XmpType for FontType<'a> {
     fn write(&self, buf: &mut String) {
         match self {
@@ -819,3 +2537,453 @@ impl<'a> XmpType for FontType<'a> {
         }
     }
 }
+
+/// A version of the PDF specification.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
+#[non_exhaustive]
+pub enum PdfVersion {
+    #[cfg_attr(feature = "serde", serde(rename = "1.0"))]
+    V1_0,
+    #[cfg_attr(feature = "serde", serde(rename = "1.1"))]
+    V1_1,
+    #[cfg_attr(feature = "serde", serde(rename = "1.2"))]
+    V1_2,
+    #[cfg_attr(feature = "serde", serde(rename = "1.3"))]
+    V1_3,
+    #[cfg_attr(feature = "serde", serde(rename = "1.4"))]
+    V1_4,
+    #[cfg_attr(feature = "serde", serde(rename = "1.5"))]
+    V1_5,
+    #[cfg_attr(feature = "serde", serde(rename = "1.6"))]
+    V1_6,
+    #[cfg_attr(feature = "serde", serde(rename = "1.7"))]
+    V1_7,
+    #[cfg_attr(feature = "serde", serde(rename = "2.0"))]
+    V2_0,
+}
+
+impl PdfVersion {
+    /// The version number, e.g. `"1.7"`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::V1_0 => "1.0",
+            Self::V1_1 => "1.1",
+            Self::V1_2 => "1.2",
+            Self::V1_3 => "1.3",
+            Self::V1_4 => "1.4",
+            Self::V1_5 => "1.5",
+            Self::V1_6 => "1.6",
+            Self::V1_7 => "1.7",
+            Self::V2_0 => "2.0",
+        }
+    }
+}
+
+impl XmpType for PdfVersion {
+    fn write(&self, buf: &mut String) {
+        buf.push_str(self.as_str());
+    }
+}
+
+/// Whether a document has been trapped, i.e. adjusted to compensate for
+/// misregistration of the printing plates.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trapped {
+    /// The document has been fully trapped.
+    True,
+    /// The document has not been trapped.
+    False,
+    /// It is unknown whether the document has been trapped, or it has only
+    /// been partially trapped.
+    Unknown,
+}
+
+impl From<bool> for Trapped {
+    fn from(trapped: bool) -> Self {
+        if trapped {
+            Self::True
+        } else {
+            Self::False
+        }
+    }
+}
+
+impl XmpType for Trapped {
+    fn write(&self, buf: &mut String) {
+        buf.push_str(match self {
+            Self::True => "True",
+            Self::False => "False",
+            Self::Unknown => "Unknown",
+        });
+    }
+}
+
+/// A version of the PDF/X standard.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum PdfXVersion {
+    /// PDF/X-1a:2001.
+    X1a2001,
+    /// PDF/X-1a:2003.
+    X1a2003,
+    /// PDF/X-3:2002.
+    X3_2002,
+    /// PDF/X-3:2003.
+    X3_2003,
+    /// PDF/X-4.
+    X4,
+    /// PDF/X-4p.
+    X4p,
+    /// PDF/X-5g.
+    X5g,
+    /// PDF/X-5n.
+    X5n,
+    /// PDF/X-5pg.
+    X5pg,
+    /// PDF/X-6.
+    X6,
+    /// PDF/X-6n.
+    X6n,
+    /// PDF/X-6p.
+    X6p,
+}
+
+impl PdfXVersion {
+    /// The value of the `GTS_PDFXVersion` property.
+    pub fn version(self) -> &'static str {
+        match self {
+            Self::X1a2001 => "PDF/X-1:2001",
+            Self::X1a2003 => "PDF/X-1:2003",
+            Self::X3_2002 => "PDF/X-3:2002",
+            Self::X3_2003 => "PDF/X-3:2003",
+            Self::X4 => "PDF/X-4",
+            Self::X4p => "PDF/X-4p",
+            Self::X5g => "PDF/X-5g",
+            Self::X5n => "PDF/X-5n",
+            Self::X5pg => "PDF/X-5pg",
+            Self::X6 => "PDF/X-6",
+            Self::X6n => "PDF/X-6n",
+            Self::X6p => "PDF/X-6p",
+        }
+    }
+
+    /// The value of the `GTS_PDFXConformance` property, which only PDF/X-1a
+    /// has.
+    pub fn conformance(self) -> Option<&'static str> {
+        match self {
+            Self::X1a2001 => Some("PDF/X-1a:2001"),
+            Self::X1a2003 => Some("PDF/X-1a:2003"),
+            _ => None,
+        }
+    }
+
+    /// The namespace of the identification properties. Versions before
+    /// PDF/X-4 use Adobe's `pdfx` namespace, later ones `pdfxid`.
+    pub(crate) fn namespace(self) -> Namespace<'static> {
+        match self {
+            Self::X1a2001 | Self::X1a2003 | Self::X3_2002 | Self::X3_2003 => {
+                Namespace::AdobePdfX
+            }
+            _ => Namespace::PdfXId,
+        }
+    }
+}
+
+/// The prefix of the namespace in the packet, taking the prefixes set with
+/// [`XmpWriter::set_prefix`] into account.
+pub(crate) fn prefix_of<'p>(
+    prefixes: &'p BTreeMap<Namespace, String>,
+    namespace: &'p Namespace,
+) -> &'p str {
+    prefixes.get(namespace).map_or(namespace.prefix(), String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Value, XmpPacket};
+
+    #[test]
+    fn builtin_namespaces_round_trip() {
+        for (i, ns) in BUILTIN.iter().enumerate() {
+            assert_eq!(Namespace::from_url(ns.url()).as_ref(), Some(ns));
+            assert_eq!(Namespace::with_prefix(ns.prefix()).as_ref(), Some(ns));
+            assert!(BUILTIN[..i].iter().all(|other| other < ns));
+            assert_eq!(ns.builtin_index(), Some(i as u32));
+        }
+    }
+
+    #[test]
+    fn parse_dates() {
+        let iso = |text: &str| {
+            let mut buf = String::new();
+            text.parse::<DateTime>().unwrap().write(&mut buf);
+            buf
+        };
+
+        for text in [
+            "2021",
+            "2021-11",
+            "2024-02-29",
+            "2021-11-06T12:30",
+            "2021-11-06T12:30Z",
+            "2021-11-06T12:30:15+01:00",
+            "2021-11-06T12:30:15.5-00:30",
+            "2021-11-06T12:30:15.000000001-14:00",
+        ] {
+            assert_eq!(iso(text), text);
+        }
+
+        assert_eq!(
+            iso("2021-11-06T12:30:15.1234567891"),
+            "2021-11-06T12:30:15.123456789"
+        );
+        assert_eq!(iso("2021-11-06T12:30:15.500"), "2021-11-06T12:30:15.5");
+        assert_eq!(
+            "2021-11-06T00:00-00:30".parse::<DateTime>().unwrap().timezone,
+            Some(Timezone::Local { hour: 0, minute: -30 })
+        );
+    }
+
+    #[test]
+    fn parse_invalid_dates() {
+        let err = |text: &str| text.parse::<DateTime>().unwrap_err();
+        for text in [
+            "",
+            "21",
+            "２０２１",
+            "2021-1",
+            "2021-11-06T12",
+            "2021-11-06T12:30:15.",
+            "2021-11-06T12:30+1:00",
+            "2021-11-06T12:30+0100",
+            "2021-11-06Z",
+            "2021-11-06 ",
+            "+2021",
+        ] {
+            assert_eq!(err(text), DateTimeError::Malformed, "{text:?}");
+        }
+
+        assert_eq!(err("2021-13"), DateTimeError::MonthOutOfRange);
+        assert_eq!(err("2021-02-29"), DateTimeError::DayOutOfRange);
+        assert_eq!(err("2021-11-06T24:00"), DateTimeError::HourOutOfRange);
+        assert_eq!(err("2021-11-06T12:60"), DateTimeError::MinuteOutOfRange);
+        assert_eq!(err("2021-11-06T12:30:60"), DateTimeError::SecondOutOfRange);
+        assert_eq!(err("2021-11-06T12:30+24:00"), DateTimeError::TimezoneOutOfRange);
+        assert_eq!(err("2021T12:30"), DateTimeError::InconsistentPrecision);
+    }
+
+    #[test]
+    fn fixed_precision() {
+        let fixed = |value, precision| {
+            let mut buf = String::new();
+            Fixed { value, precision }.write(&mut buf);
+            buf
+        };
+
+        assert_eq!(fixed(0.1 + 0.2, 2), "0.3");
+        assert_eq!(fixed(2.5, 0), "2");
+        assert_eq!(fixed(1.005, 3), "1.005");
+        assert_eq!(fixed(100.0, 4), "100");
+        assert_eq!(fixed(-0.0001, 2), "0");
+        assert_eq!(fixed(-1.25, 1), "-1.2");
+    }
+
+    #[test]
+    fn non_finite_numbers_are_not_written() {
+        let mut writer = XmpWriter::new();
+        writer.element("NaN", Namespace::Xmp).value(f64::NAN);
+        writer
+            .element("Fixed", Namespace::Xmp)
+            .value(Fixed { value: f64::INFINITY, precision: 2 });
+        writer
+            .element("Items", Namespace::Xmp)
+            .array(RdfCollectionType::Bag)
+            .element_with_attrs([("xmp:size", f32::NEG_INFINITY)])
+            .value("item");
+        let mut stc = writer.element("Struct", Namespace::Xmp).obj();
+        stc.field("field", Namespace::Xmp, f64::NAN);
+        stc.field("other", Namespace::Xmp, 1.5);
+        drop(stc);
+
+        assert_eq!(writer.errors(), vec![XmpError::NonFiniteNumber; 4]);
+        assert!(!writer.has_property(Namespace::Xmp, "NaN"));
+        assert!(!writer.has_property(Namespace::Xmp, "Fixed"));
+
+        let packet = writer.finish(None);
+        assert!(!packet.contains("NaN"));
+        assert!(!packet.contains("inf"));
+        assert!(packet.contains("<rdf:li>item</rdf:li>"));
+        assert!(!packet.contains("xmp:field"));
+        assert!(packet.contains("<xmp:other>1.5</xmp:other>"));
+        assert!(XmpPacket::parse(&packet).is_ok());
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde", feature = "serde_json"))]
+    fn borrowed_types_round_trip_through_serde() {
+        fn round_trip<'a, T>(value: &T, json: &'a mut String) -> T
+        where
+            T: serde::Serialize + serde::Deserialize<'a>,
+        {
+            *json = serde_json::to_string(value).unwrap();
+            serde_json::from_str(json).unwrap()
+        }
+
+        let json = &mut String::new();
+        let lang = LangId("de");
+        assert_eq!(round_trip(&lang, json), lang);
+
+        let class = RenditionClass::Thumbnail {
+            format: Some("jpeg"),
+            size: Some((160, 120)),
+            color_space: None,
+        };
+        assert_eq!(round_trip(&class, json), class);
+        assert_eq!(
+            round_trip(&RenditionClass::Custom("print"), json),
+            RenditionClass::Custom("print")
+        );
+
+        let dimensions = Dimensions::new(8.5, 11.0, DimensionUnit::Custom("in"));
+        assert_eq!(round_trip(&dimensions, json), dimensions);
+        assert_eq!(round_trip(&DimensionUnit::Mm, json), DimensionUnit::Mm);
+    }
+
+    #[test]
+    fn registered_namespaces_can_be_owned() {
+        let mut writer = XmpWriter::new();
+        let (prefix, url) = (String::from("ex"), format!("http://{}/ns/", "example.com"));
+        let id = writer.register_namespace("Example", prefix.clone(), url.clone());
+        assert_eq!(writer.register_namespace("Example", prefix, url), id);
+        writer.element("prop", id).value("value");
+        writer.clone().element("other", id).value("value");
+
+        let packet = writer.finish(None);
+        assert!(packet.contains(r#"xmlns:ex="http://example.com/ns/""#));
+        assert!(packet.contains("<ex:prop>value</ex:prop>"));
+    }
+
+    #[test]
+    #[should_panic = "namespace handle was not registered with this writer"]
+    fn namespace_handles_are_tied_to_their_writer() {
+        let mut first = XmpWriter::new();
+        let id = first.register_namespace("First", "first", "http://example.com/1/");
+        let mut second = XmpWriter::new();
+        second.register_namespace("Second", "second", "http://example.com/2/");
+        second.element("prop", id);
+    }
+
+    #[test]
+    fn text_is_escaped() {
+        assert!(matches!(escape_text("plain ünïcödé 日本"), Cow::Borrowed(_)));
+        assert_eq!(escape_text("a<b>c&d"), "a&lt;b&gt;c&amp;d");
+        assert_eq!(escape_text("\"it's\""), "&quot;it&apos;s&quot;");
+        assert_eq!(escape_text("日本<語>&"), "日本&lt;語&gt;&amp;");
+        assert_eq!(escape_text("line\nbreak\ttab"), "line\nbreak\ttab");
+
+        let mut writer = XmpWriter::new();
+        writer.label("日本 & <ü>\n");
+        writer.element("Nickname", Namespace::Xmp).value(RawXmpStr("<raw/>"));
+        let packet = writer.finish(None);
+        assert!(packet.contains("<xmp:Label>日本 &amp; &lt;ü&gt;\n</xmp:Label>"));
+        assert!(packet.contains("<xmp:Nickname><raw/></xmp:Nickname>"));
+    }
+
+    #[test]
+    fn attribute_values_are_escaped() {
+        assert_eq!(escape_attr("plain"), Cow::Borrowed("plain"));
+        assert_eq!(escape_attr("a\"b<c&d"), "a&quot;b&lt;c&amp;d");
+        assert_eq!(escape_attr("a\tb\nc\rd"), "a&#x9;b&#xA;c&#xD;d");
+        assert_eq!(escape_attr("it's > 1"), "it's > 1");
+
+        let mut writer = XmpWriter::new();
+        writer
+            .element("subject", Namespace::DublinCore)
+            .array(RdfCollectionType::Bag)
+            .element_with_attrs([("xml:lang", "\"en\"\n")])
+            .value("text");
+        let packet = writer.finish(None);
+        assert!(
+            packet.contains(r#"<rdf:li xml:lang="&quot;en&quot;&#xA;">text</rdf:li>"#)
+        );
+    }
+
+    #[test]
+    fn compact_struct_fields_are_escaped() {
+        let mut writer = XmpWriter::new();
+        writer.set_struct_form(StructForm::Compact);
+        writer.element("DerivedFrom", Namespace::XmpMedia).obj().field(
+            "documentID",
+            Namespace::XmpResourceRef,
+            "a\"b\n<&",
+        );
+        assert_eq!(writer.errors(), []);
+        let packet = writer.finish(None);
+        assert!(packet.contains(r#"stRef:documentID="a&quot;b&#xA;&lt;&amp;""#));
+    }
+
+    #[test]
+    fn compact_structs_use_attributes_for_simple_fields() {
+        let mut writer = XmpWriter::new();
+        writer.set_struct_form(StructForm::Compact);
+        let mut stc = writer.element("DerivedFrom", Namespace::XmpMedia).obj();
+        stc.field("documentID", Namespace::XmpResourceRef, "doc");
+        stc.element("alternatePaths", Namespace::XmpResourceRef)
+            .ordered_array(["path"]);
+        stc.field("instanceID", Namespace::XmpResourceRef, "inst");
+        drop(stc);
+        writer.element("ManagedFrom", Namespace::XmpMedia).obj();
+
+        let packet = writer.finish(None);
+        assert!(packet.contains(
+            "<xmpMM:DerivedFrom><rdf:Description stRef:documentID=\"doc\">\
+             <stRef:alternatePaths>"
+        ));
+        assert!(packet.contains(
+            "<stRef:instanceID>inst</stRef:instanceID></rdf:Description>\
+             </xmpMM:DerivedFrom>"
+        ));
+        assert!(
+            packet.contains("<xmpMM:ManagedFrom><rdf:Description/></xmpMM:ManagedFrom>")
+        );
+
+        let parsed = XmpPacket::parse(&packet).unwrap();
+        let derived = parsed.get(&Namespace::XmpMedia, "DerivedFrom").unwrap();
+        let field = |name| derived.field(&Namespace::XmpResourceRef, name);
+        assert_eq!(field("documentID"), Some(&Value::Text("doc".into())));
+        assert_eq!(field("instanceID"), Some(&Value::Text("inst".into())));
+        assert_eq!(
+            field("alternatePaths"),
+            Some(&Value::Array(RdfCollectionType::Seq, vec!["path".into()]))
+        );
+    }
+
+    #[test]
+    fn node_ids_are_written_and_resolved() {
+        for form in [StructForm::ParseTypeResource, StructForm::Compact] {
+            let mut writer = XmpWriter::new();
+            writer.set_struct_form(form);
+            writer
+                .element("DerivedFrom", Namespace::XmpMedia)
+                .obj_with_node_id("ref\"1")
+                .field("documentID", Namespace::XmpResourceRef, "doc");
+            writer.element("ManagedFrom", Namespace::XmpMedia).node_ref("ref\"1");
+            assert_eq!(writer.errors(), []);
+
+            let packet = writer.finish(None);
+            assert!(!packet.contains("parseType"));
+            assert!(packet.contains("<rdf:Description rdf:nodeID=\"ref&quot;1\""));
+            assert!(packet.contains("<xmpMM:ManagedFrom rdf:nodeID=\"ref&quot;1\"/>"));
+
+            let parsed = XmpPacket::parse(&packet).unwrap();
+            let derived = parsed.get(&Namespace::XmpMedia, "DerivedFrom");
+            assert!(derived.is_some());
+            assert_eq!(parsed.get(&Namespace::XmpMedia, "ManagedFrom"), derived);
+        }
+    }
+}
@@ -0,0 +1,403 @@
+//! The offset of the system's local timezone.
+//!
+//! Reads the zone from the `TZ` environment variable or `/etc/localtime`
+//! like the C library does on Unix-like systems. Both zone files in the
+//! TZif format and POSIX TZ strings with daylight saving time rules are
+//! supported.
+
+use std::path::Path;
+
+/// The directory that named zones like `Europe/Berlin` are read from.
+const ZONEINFO: &str = "/usr/share/zoneinfo";
+
+/// The offset east of UTC in seconds at an instant in seconds since the
+/// epoch, or `None` if it cannot be determined.
+pub(crate) fn local_offset(now: i64) -> Option<i32> {
+    match std::env::var("TZ") {
+        Ok(tz) if tz.is_empty() => Some(0),
+        Ok(tz) => {
+            let name = tz.strip_prefix(':').unwrap_or(&tz);
+            if let Some(offset) = read_zone(name).and_then(|data| tzif_offset(&data, now))
+            {
+                return Some(offset);
+            }
+            PosixTz::parse(name)?.offset(now)
+        }
+        Err(_) => tzif_offset(&std::fs::read("/etc/localtime").ok()?, now),
+    }
+}
+
+/// Read the zone file of a zone name or path.
+fn read_zone(name: &str) -> Option<Vec<u8>> {
+    if name.starts_with('/') {
+        std::fs::read(name).ok()
+    } else if name.split('/').all(|part| !part.is_empty() && part != "..") {
+        std::fs::read(Path::new(ZONEINFO).join(name)).ok()
+    } else {
+        None
+    }
+}
+
+/// The offset at an instant according to a zone file in the TZif format.
+fn tzif_offset(data: &[u8], now: i64) -> Option<i32> {
+    let mut reader = Reader(data);
+    let header = reader.header()?;
+
+    // Version 2 and later repeat the data with 64-bit transition times,
+    // followed by a TZ string for instants after the last transition.
+    let (header, time_size) = if header.version >= b'2' {
+        reader.take(header.len(4))?;
+        (reader.header()?, 8)
+    } else {
+        (header, 4)
+    };
+
+    let mut times = reader.take(header.time_count * time_size)?;
+    let indices = reader.take(header.time_count)?;
+    let types = reader.take(header.type_count * 6)?;
+    reader.take(
+        header.len(time_size) - header.time_count * (time_size + 1) - types.len(),
+    )?;
+
+    let utoff = |index: u8| -> Option<i32> {
+        let ty = types.get(usize::from(index) * 6..)?;
+        Some(i32::from_be_bytes(ty.get(..4)?.try_into().ok()?))
+    };
+
+    let mut current = None;
+    for &index in indices {
+        let (time, rest) = times.split_at(time_size);
+        times = rest;
+        let time = match time_size {
+            4 => i64::from(i32::from_be_bytes(time.try_into().ok()?)),
+            _ => i64::from_be_bytes(time.try_into().ok()?),
+        };
+        if time > now {
+            break;
+        }
+        current = Some(index);
+    }
+
+    // After the last transition, the footer is authoritative if present.
+    let last = current.is_some() && current == indices.last().copied();
+    if (last || indices.is_empty()) && time_size == 8 {
+        let footer = reader.0.strip_prefix(b"\n")?;
+        let end = footer.iter().position(|&b| b == b'\n')?;
+        let footer = std::str::from_utf8(&footer[..end]).ok()?;
+        if let Some(offset) = PosixTz::parse(footer).and_then(|tz| tz.offset(now)) {
+            return Some(offset);
+        }
+    }
+
+    // Before the first transition, the first type applies.
+    utoff(current.unwrap_or(0))
+}
+
+/// The counts in the header of a TZif file.
+struct Header {
+    version: u8,
+    leap_count: usize,
+    time_count: usize,
+    type_count: usize,
+    char_count: usize,
+    std_count: usize,
+    ut_count: usize,
+}
+
+impl Header {
+    /// The length of the data block that follows the header.
+    fn len(&self, time_size: usize) -> usize {
+        self.time_count * (time_size + 1)
+            + self.type_count * 6
+            + self.char_count
+            + self.leap_count * (time_size + 4)
+            + self.std_count
+            + self.ut_count
+    }
+}
+
+/// Reads a TZif file from front to back.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (head, tail) = (self.0.get(..len)?, self.0.get(len..)?);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn header(&mut self) -> Option<Header> {
+        let header = self.take(44)?;
+        if &header[..4] != b"TZif" {
+            return None;
+        }
+
+        let count = |i: usize| -> Option<usize> {
+            let bytes = header.get(20 + 4 * i..24 + 4 * i)?;
+            usize::try_from(u32::from_be_bytes(bytes.try_into().ok()?)).ok()
+        };
+        Some(Header {
+            version: header[4],
+            ut_count: count(0)?,
+            std_count: count(1)?,
+            leap_count: count(2)?,
+            time_count: count(3)?,
+            type_count: count(4)?,
+            char_count: count(5)?,
+        })
+    }
+}
+
+/// A timezone in the format of the POSIX `TZ` variable, e.g.
+/// `CET-1CEST,M3.5.0,M10.5.0/3`.
+#[derive(Debug, PartialEq)]
+struct PosixTz {
+    /// The standard offset east of UTC in seconds.
+    std: i32,
+    /// The daylight saving offset and the rules for when it starts and ends.
+    dst: Option<(i32, Rule, Rule)>,
+}
+
+/// The day and local time at which daylight saving time starts or ends.
+#[derive(Debug, PartialEq)]
+struct Rule {
+    date: RuleDate,
+    time: i32,
+}
+
+#[derive(Debug, PartialEq)]
+enum RuleDate {
+    /// A day of the year from 1 to 365, not counting February 29.
+    Julian(i64),
+    /// A day of the year from 0 to 365, counting February 29.
+    Day(i64),
+    /// A weekday from 0 (Sunday) to 6 in a week from 1 to 5 (the last) of a
+    /// month.
+    Weekday { month: i64, week: i64, weekday: i64 },
+}
+
+impl PosixTz {
+    fn parse(text: &str) -> Option<Self> {
+        let mut s = text;
+        name(&mut s)?;
+        // POSIX offsets are west of UTC.
+        let std = -offset(&mut s)?;
+        if s.is_empty() {
+            return Some(Self { std, dst: None });
+        }
+
+        name(&mut s)?;
+        let dst = if s.starts_with(',') { std + 3600 } else { -offset(&mut s)? };
+        let s = s.strip_prefix(',')?;
+        let (start, end) = s.split_once(',')?;
+        Some(Self {
+            std,
+            dst: Some((dst, Rule::parse(start)?, Rule::parse(end)?)),
+        })
+    }
+
+    /// The offset east of UTC in seconds at an instant.
+    fn offset(&self, now: i64) -> Option<i32> {
+        let Some((dst, start, end)) = &self.dst else { return Some(self.std) };
+
+        // Transitions are given in local time before the change.
+        let year = civil_from_days((now + i64::from(self.std)).div_euclid(86400)).0;
+        let start = start.instant(year)? - i64::from(self.std);
+        let end = end.instant(year)? - i64::from(*dst);
+        let in_dst = if start < end {
+            start <= now && now < end
+        } else {
+            !(end <= now && now < start)
+        };
+        Some(if in_dst { *dst } else { self.std })
+    }
+}
+
+impl Rule {
+    fn parse(text: &str) -> Option<Self> {
+        let (date, time) = match text.split_once('/') {
+            Some((date, mut time)) => {
+                (date, offset(&mut time).filter(|_| time.is_empty())?)
+            }
+            None => (text, 2 * 3600),
+        };
+
+        let date = if let Some(day) = date.strip_prefix('J') {
+            RuleDate::Julian(day.parse().ok().filter(|day| (1..=365).contains(day))?)
+        } else if let Some(rest) = date.strip_prefix('M') {
+            let mut parts = rest.split('.').map(|part| part.parse::<i64>().ok());
+            let (month, week, weekday) = (parts.next()??, parts.next()??, parts.next()??);
+            if parts.next().is_some()
+                || !(1..=12).contains(&month)
+                || !(1..=5).contains(&week)
+                || !(0..=6).contains(&weekday)
+            {
+                return None;
+            }
+            RuleDate::Weekday { month, week, weekday }
+        } else {
+            RuleDate::Day(date.parse().ok().filter(|day| (0..=365).contains(day))?)
+        };
+
+        Some(Self { date, time })
+    }
+
+    /// The local instant of the rule in a year, in seconds since the epoch.
+    fn instant(&self, year: i64) -> Option<i64> {
+        let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days = match self.date {
+            RuleDate::Julian(day) => {
+                let day = if leap && day >= 60 { day } else { day - 1 };
+                days_from_civil(year, 1, 1) + day
+            }
+            RuleDate::Day(day) => days_from_civil(year, 1, 1) + day,
+            RuleDate::Weekday { month, week, weekday } => {
+                let first = days_from_civil(year, month, 1);
+                // January 1, 1970 was a Thursday.
+                let first_weekday = (first + 4).rem_euclid(7);
+                let mut day =
+                    first + (weekday - first_weekday).rem_euclid(7) + (week - 1) * 7;
+                let next = match month {
+                    12 => days_from_civil(year + 1, 1, 1),
+                    _ => days_from_civil(year, month + 1, 1),
+                };
+                while day >= next {
+                    day -= 7;
+                }
+                day
+            }
+        };
+        Some(days * 86400 + i64::from(self.time))
+    }
+}
+
+/// Parse a zone abbreviation, which is either alphabetic or quoted in angle
+/// brackets.
+fn name(s: &mut &str) -> Option<()> {
+    let len = if let Some(rest) = s.strip_prefix('<') {
+        rest.find('>')? + 2
+    } else {
+        s.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(s.len())
+    };
+    if len < 3 {
+        return None;
+    }
+    *s = &s[len..];
+    Some(())
+}
+
+/// Parse an offset or time like `-5`, `+05:30`, or `2:00:00` in seconds.
+fn offset(s: &mut &str) -> Option<i32> {
+    let (sign, rest) = match s.as_bytes().first()? {
+        b'-' => (-1, &s[1..]),
+        b'+' => (1, &s[1..]),
+        _ => (1, *s),
+    };
+    let len = rest
+        .find(|c: char| !c.is_ascii_digit() && c != ':')
+        .unwrap_or(rest.len());
+    let mut seconds = 0;
+    let mut parts = rest[..len].split(':');
+    for factor in [3600, 60, 1] {
+        let Some(part) = parts.next() else { break };
+        let value: i32 = part.parse().ok().filter(|_| part.len() <= 3)?;
+        seconds += value * factor;
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    *s = &rest[len..];
+    Some(sign * seconds)
+}
+
+/// The number of days since the epoch of a civil date, see
+/// https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// The civil year, month, and day of a number of days since the epoch.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-15T12:00:00Z and 2024-07-15T12:00:00Z.
+    const WINTER: i64 = 1705320000;
+    const SUMMER: i64 = 1721044800;
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2024, 3, 1), 19783);
+        assert_eq!(civil_from_days(19783), (2024, 3, 1));
+    }
+
+    #[test]
+    fn posix_without_dst() {
+        assert_eq!(PosixTz::parse("UTC0").unwrap().offset(WINTER), Some(0));
+        assert_eq!(PosixTz::parse("EST5").unwrap().offset(WINTER), Some(-5 * 3600));
+        assert_eq!(PosixTz::parse("<+0530>-5:30").unwrap().offset(SUMMER), Some(19800));
+        assert_eq!(PosixTz::parse("<-0030>0:30").unwrap().offset(SUMMER), Some(-1800));
+    }
+
+    #[test]
+    fn posix_with_dst() {
+        let berlin = PosixTz::parse("CET-1CEST,M3.5.0,M10.5.0/3").unwrap();
+        assert_eq!(berlin.offset(WINTER), Some(3600));
+        assert_eq!(berlin.offset(SUMMER), Some(7200));
+
+        // 2024-03-31T01:00:00Z is the first instant of CEST.
+        assert_eq!(berlin.offset(1711846799), Some(3600));
+        assert_eq!(berlin.offset(1711846800), Some(7200));
+
+        let sydney = PosixTz::parse("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+        assert_eq!(sydney.offset(WINTER), Some(11 * 3600));
+        assert_eq!(sydney.offset(SUMMER), Some(10 * 3600));
+    }
+
+    #[test]
+    fn posix_invalid() {
+        assert_eq!(PosixTz::parse(""), None);
+        assert_eq!(PosixTz::parse("X1"), None);
+        assert_eq!(PosixTz::parse("CET-1CEST"), None);
+        assert_eq!(PosixTz::parse("CET-1CEST,M13.5.0,M10.5.0"), None);
+    }
+
+    #[test]
+    fn tzif_footer() {
+        // A version 2 file without transitions whose footer has the rules.
+        let mut data = vec![];
+        for _ in 0..2 {
+            data.extend(b"TZif2");
+            data.extend([0; 15]);
+            for count in [0u32, 0, 0, 0, 1, 4] {
+                data.extend(count.to_be_bytes());
+            }
+            data.extend(3600i32.to_be_bytes());
+            data.extend([0, 0]);
+            data.extend(b"CET\0");
+        }
+        data.extend(b"\nCET-1CEST,M3.5.0,M10.5.0/3\n");
+        assert_eq!(tzif_offset(&data, WINTER), Some(3600));
+        assert_eq!(tzif_offset(&data, SUMMER), Some(7200));
+        assert_eq!(tzif_offset(&data[..50], WINTER), None);
+    }
+}
@@ -0,0 +1,123 @@
+//! Bindings for JavaScript.
+
+use std::str::FromStr;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    CustomNamespace, DateTime, LocalizedText, Namespace, XmpMetadata, XmpWriter,
+};
+
+/// Builds an XMP packet from JavaScript.
+///
+/// Properties are collected first and written when the packet is finished,
+/// so setters can be called in any order and more than once. Requires the
+/// `wasm-bindgen` feature.
+#[wasm_bindgen]
+#[derive(Debug, Default, Clone)]
+pub struct XmpBuilder {
+    metadata: XmpMetadata,
+    /// The URL, prefix, name, and value of each custom property.
+    custom: Vec<(String, String, String, String)>,
+}
+
+#[wasm_bindgen]
+impl XmpBuilder {
+    /// Create a new, empty builder.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a title in a language (`dc:title`). Without a language, the title
+    /// is the default one.
+    #[wasm_bindgen(js_name = addTitle)]
+    pub fn add_title(&mut self, text: String, lang: Option<String>) {
+        self.metadata.title.push(LocalizedText { lang, text });
+    }
+
+    /// Add a description in a language (`dc:description`).
+    #[wasm_bindgen(js_name = addDescription)]
+    pub fn add_description(&mut self, text: String, lang: Option<String>) {
+        self.metadata.description.push(LocalizedText { lang, text });
+    }
+
+    /// Set the creators (`dc:creator`).
+    #[wasm_bindgen(js_name = setCreators)]
+    pub fn set_creators(&mut self, creators: Vec<String>) {
+        self.metadata.creators = creators;
+    }
+
+    /// Set the keywords (`dc:subject`).
+    #[wasm_bindgen(js_name = setSubject)]
+    pub fn set_subject(&mut self, subject: Vec<String>) {
+        self.metadata.subject = subject;
+    }
+
+    /// Set the languages (`dc:language`).
+    #[wasm_bindgen(js_name = setLanguages)]
+    pub fn set_languages(&mut self, languages: Vec<String>) {
+        self.metadata.languages = languages;
+    }
+
+    /// Set the creation date (`xmp:CreateDate`) from an ISO 8601 string.
+    #[wasm_bindgen(js_name = setCreateDate)]
+    pub fn set_create_date(&mut self, date: &str) -> Result<(), JsError> {
+        self.metadata.create_date = Some(parse_date(date)?);
+        Ok(())
+    }
+
+    /// Set the modification date (`xmp:ModifyDate`) from an ISO 8601 string.
+    #[wasm_bindgen(js_name = setModifyDate)]
+    pub fn set_modify_date(&mut self, date: &str) -> Result<(), JsError> {
+        self.metadata.modify_date = Some(parse_date(date)?);
+        Ok(())
+    }
+
+    /// Set the application that created the document (`xmp:CreatorTool`).
+    #[wasm_bindgen(js_name = setCreatorTool)]
+    pub fn set_creator_tool(&mut self, tool: String) {
+        self.metadata.creator_tool = Some(tool);
+    }
+
+    /// Set the application that produced the PDF (`pdf:Producer`).
+    #[wasm_bindgen(js_name = setProducer)]
+    pub fn set_producer(&mut self, producer: String) {
+        self.metadata.producer = Some(producer);
+    }
+
+    /// Add a text property in a custom namespace.
+    #[wasm_bindgen(js_name = addCustom)]
+    pub fn add_custom(
+        &mut self,
+        url: String,
+        prefix: String,
+        name: String,
+        value: String,
+    ) {
+        self.custom.push((url, prefix, name, value));
+    }
+
+    /// Finish the packet and return it as a string.
+    pub fn finish(&self, about: Option<String>) -> Result<String, JsError> {
+        let mut writer = XmpWriter::new();
+        self.metadata.write(&mut writer);
+        for (url, prefix, name, value) in &self.custom {
+            let namespace = CustomNamespace::new(prefix, prefix, url);
+            let namespace = Namespace::Custom(Box::new(namespace));
+            writer.try_element(name, namespace)?.value(value.as_str());
+        }
+        Ok(writer.finish(about.as_deref()))
+    }
+
+    /// Finish the packet and return it as UTF-8 bytes.
+    #[wasm_bindgen(js_name = finishBytes)]
+    pub fn finish_bytes(&self, about: Option<String>) -> Result<Vec<u8>, JsError> {
+        self.finish(about).map(String::into_bytes)
+    }
+}
+
+/// Parse an ISO 8601 date.
+fn parse_date(date: &str) -> Result<DateTime, JsError> {
+    DateTime::from_str(date).map_err(|err| JsError::new(&err.to_string()))
+}